@@ -0,0 +1,119 @@
+// Compares the hash maps of the crate with std's HashMap, all of them with the same hasher.
+// Run with `cargo bench --bench hash_map`.
+
+use data_structure::hash::{HashMap as RobinHoodMap, SwissMap};
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap as StdMap;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const LEN: u64 = 200_000;
+const ROUNDS: u32 = 5;
+
+trait Map: Default {
+    fn insert(&mut self, key: u64, value: u64);
+    fn get(&self, key: u64) -> Option<&u64>;
+    fn remove(&mut self, key: u64) -> Option<u64>;
+}
+
+macro_rules! impl_map {
+    ($ty:ty) => {
+        impl Map for $ty {
+            fn insert(&mut self, key: u64, value: u64) {
+                <$ty>::insert(self, key, value);
+            }
+
+            fn get(&self, key: u64) -> Option<&u64> {
+                <$ty>::get(self, &key)
+            }
+
+            fn remove(&mut self, key: u64) -> Option<u64> {
+                <$ty>::remove(self, &key)
+            }
+        }
+    };
+}
+
+impl_map!(StdMap<u64, u64, RandomState>);
+impl_map!(RobinHoodMap<u64, u64, RandomState>);
+impl_map!(SwissMap<u64, u64, RandomState>);
+
+// the best of a few rounds, in nanoseconds per operation
+fn measure(mut f: impl FnMut() -> Duration) -> f64 {
+    let best = (0..ROUNDS).map(|_| f()).min().unwrap();
+    best.as_nanos() as f64 / LEN as f64
+}
+
+// spreads consecutive numbers over the key space
+fn key(i: u64) -> u64 {
+    i.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+fn bench<M: Map>(name: &str) {
+    let filled = || {
+        let mut map = M::default();
+        for i in 0..LEN {
+            map.insert(key(i), i);
+        }
+        map
+    };
+
+    let insert = measure(|| {
+        let start = Instant::now();
+        black_box(filled());
+        start.elapsed()
+    });
+
+    let map = filled();
+    let hit = measure(|| {
+        let start = Instant::now();
+        for i in 0..LEN {
+            black_box(map.get(black_box(key(i))));
+        }
+        start.elapsed()
+    });
+    let miss = measure(|| {
+        let start = Instant::now();
+        for i in LEN..LEN * 2 {
+            black_box(map.get(black_box(key(i))));
+        }
+        start.elapsed()
+    });
+
+    let remove = measure(|| {
+        let mut map = filled();
+        let start = Instant::now();
+        for i in 0..LEN {
+            black_box(map.remove(key(i)));
+        }
+        start.elapsed()
+    });
+
+    // removes and inserts in turn, which leaves tombstones behind in a SwissMap
+    let churn = measure(|| {
+        let mut map = filled();
+        let start = Instant::now();
+        for i in 0..LEN {
+            map.remove(key(i));
+            map.insert(key(i + LEN), i);
+        }
+        start.elapsed()
+    });
+
+    println!(
+        "{:<12}{:>10.1}{:>10.1}{:>10.1}{:>10.1}{:>10.1}",
+        name, insert, hit, miss, remove, churn
+    );
+}
+
+fn main() {
+    println!("{} entries, ns per operation", LEN);
+    println!(
+        "{:<12}{:>10}{:>10}{:>10}{:>10}{:>10}",
+        "", "insert", "hit", "miss", "remove", "churn"
+    );
+    bench::<StdMap<u64, u64>>("std");
+    bench::<RobinHoodMap<u64, u64>>("robin hood");
+    bench::<SwissMap<u64, u64>>("swiss");
+}
@@ -0,0 +1,131 @@
+// Compares the splay map with the AVL and red-black maps over sequences of operations,
+// where only the total cost matters, as it does for the amortized bounds of splaying.
+// Run with `cargo bench --bench splay_map`.
+
+use data_structure::tree::{AvlMap, RbTreeMap, SplayMap};
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const LEN: u64 = 200_000;
+const ROUNDS: u32 = 5;
+
+// lookups take &mut self, since the splay map restructures itself on every access
+trait Map: Default {
+    fn insert(&mut self, key: u64, value: u64);
+    fn get(&mut self, key: u64) -> Option<&u64>;
+    fn remove(&mut self, key: u64) -> Option<u64>;
+}
+
+macro_rules! impl_map {
+    ($ty:ty) => {
+        impl Map for $ty {
+            fn insert(&mut self, key: u64, value: u64) {
+                <$ty>::insert(self, key, value);
+            }
+
+            fn get(&mut self, key: u64) -> Option<&u64> {
+                <$ty>::get(self, &key)
+            }
+
+            fn remove(&mut self, key: u64) -> Option<u64> {
+                <$ty>::remove(self, &key)
+            }
+        }
+    };
+}
+
+impl_map!(AvlMap<u64, u64>);
+impl_map!(RbTreeMap<u64, u64>);
+impl_map!(SplayMap<u64, u64>);
+
+// the best of a few rounds, in nanoseconds per operation
+fn measure(mut f: impl FnMut() -> Duration) -> f64 {
+    let best = (0..ROUNDS).map(|_| f()).min().unwrap();
+    best.as_nanos() as f64 / LEN as f64
+}
+
+// spreads consecutive numbers over the key space
+fn key(i: u64) -> u64 {
+    i.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+// nine in ten lookups go to one key in a hundred
+fn skewed(i: u64) -> u64 {
+    let r = key(i);
+    if r % 10 < 9 {
+        key(r % (LEN / 100))
+    } else {
+        key(r % LEN)
+    }
+}
+
+fn bench<M: Map>(name: &str) {
+    let filled = || {
+        let mut map = M::default();
+        for i in 0..LEN {
+            map.insert(key(i), i);
+        }
+        map
+    };
+
+    let insert = measure(|| {
+        let start = Instant::now();
+        black_box(filled());
+        start.elapsed()
+    });
+
+    let mut map = filled();
+    let uniform = measure(|| {
+        let start = Instant::now();
+        for i in 0..LEN {
+            black_box(map.get(black_box(key(LEN - 1 - i))));
+        }
+        start.elapsed()
+    });
+    let skewed = measure(|| {
+        let start = Instant::now();
+        for i in 0..LEN {
+            black_box(map.get(black_box(skewed(i))));
+        }
+        start.elapsed()
+    });
+
+    // ascending keys, where every splay finds the next key next to the root
+    let mut map = M::default();
+    for i in 0..LEN {
+        map.insert(i, i);
+    }
+    let sequential = measure(|| {
+        let start = Instant::now();
+        for i in 0..LEN {
+            black_box(map.get(black_box(i)));
+        }
+        start.elapsed()
+    });
+
+    let remove = measure(|| {
+        let mut map = filled();
+        let start = Instant::now();
+        for i in 0..LEN {
+            black_box(map.remove(key(i)));
+        }
+        start.elapsed()
+    });
+
+    println!(
+        "{:<12}{:>12.1}{:>12.1}{:>12.1}{:>12.1}{:>12.1}",
+        name, insert, uniform, skewed, sequential, remove
+    );
+}
+
+fn main() {
+    println!("{} entries, ns per operation", LEN);
+    println!(
+        "{:<12}{:>12}{:>12}{:>12}{:>12}{:>12}",
+        "", "insert", "uniform", "skewed", "sequential", "remove"
+    );
+    bench::<AvlMap<u64, u64>>("avl");
+    bench::<RbTreeMap<u64, u64>>("red-black");
+    bench::<SplayMap<u64, u64>>("splay");
+}
@@ -0,0 +1,90 @@
+// Minimal executors for the tests, the crate itself does not depend on a runtime.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark()
+    }
+}
+
+// polls the future on the current thread, parking it while the future is pending
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+struct TaskWaker {
+    id: usize,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.ready.lock().unwrap().push_back(self.id)
+    }
+}
+
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+// runs tasks on the current thread, a task is polled again only after it has been woken
+pub(crate) struct LocalExecutor {
+    tasks: Vec<Option<RefCell<Task>>>,
+    ready: Arc<Mutex<VecDeque<usize>>>,
+}
+
+impl LocalExecutor {
+    pub(crate) fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            ready: Arc::default(),
+        }
+    }
+
+    pub(crate) fn spawn<F: Future<Output = ()> + 'static>(&mut self, future: F) {
+        self.ready.lock().unwrap().push_back(self.tasks.len());
+        self.tasks.push(Some(RefCell::new(Box::pin(future))));
+    }
+
+    // panics if the pending tasks can never be woken
+    pub(crate) fn run(&mut self) {
+        loop {
+            let id = self.ready.lock().unwrap().pop_front();
+            let id = match id {
+                Some(id) => id,
+                None if self.tasks.iter().all(Option::is_none) => return,
+                None => panic!("tasks are stuck without a wakeup"),
+            };
+            let task = match &self.tasks[id] {
+                Some(task) => task,
+                None => continue,
+            };
+            let waker = Waker::from(Arc::new(TaskWaker {
+                id,
+                ready: self.ready.clone(),
+            }));
+            let poll = task
+                .borrow_mut()
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker));
+            if poll.is_ready() {
+                self.tasks[id] = None;
+            }
+        }
+    }
+}
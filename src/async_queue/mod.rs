@@ -0,0 +1,7 @@
+#[cfg(test)]
+mod executor;
+pub mod queue;
+pub mod stream;
+
+pub use queue::{AsyncQueue, Closed, PopStream, PushSink};
+pub use stream::{Sink, Stream};
@@ -0,0 +1,671 @@
+use super::stream::{Sink, Stream};
+use crate::deque::{LinkedQueue, RingDeque};
+use crate::linear_list::LinkedList;
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll, Waker};
+
+// the queue has been closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Closed;
+
+enum Buffer<T> {
+    Unbounded(LinkedQueue<T>),
+    Bounded(RingDeque<T>),
+}
+
+// tasks waiting on one side of the queue, in the order they started waiting
+struct Waiters {
+    next_id: usize,
+    list: LinkedList<(usize, Waker)>,
+}
+
+impl Waiters {
+    fn new() -> Self {
+        Self {
+            next_id: 0,
+            list: LinkedList::new(),
+        }
+    }
+
+    // registers a new waiter or refreshes the waker of a registered one
+    fn register(&mut self, waiter: &mut Option<usize>, waker: &Waker) {
+        if let Some(id) = *waiter {
+            if let Some((_, old)) = self.list.iter_mut().find(|(i, _)| *i == id) {
+                if !old.will_wake(waker) {
+                    *old = waker.clone();
+                }
+                return;
+            }
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.list.push_back((id, waker.clone()));
+        *waiter = Some(id);
+    }
+
+    // returns false if the waiter has already been woken
+    fn remove(&mut self, waiter: &mut Option<usize>) -> bool {
+        match waiter.take() {
+            Some(id) => self.list.remove_first(|(i, _)| *i == id).is_some(),
+            None => true,
+        }
+    }
+
+    fn take_one(&mut self) -> Option<Waker> {
+        self.list.pop_front().map(|(_, waker)| waker)
+    }
+
+    fn take_all(&mut self) -> Vec<Waker> {
+        let mut wakers = Vec::new();
+        while let Some(waker) = self.take_one() {
+            wakers.push(waker);
+        }
+        wakers
+    }
+}
+
+struct State<T> {
+    buffer: Buffer<T>,
+    // slots of a bounded buffer promised to sinks
+    reserved: usize,
+    closed: bool,
+    poppers: Waiters,
+    pushers: Waiters,
+}
+
+impl<T> State<T> {
+    fn len(&self) -> usize {
+        match &self.buffer {
+            Buffer::Unbounded(queue) => queue.len(),
+            Buffer::Bounded(deque) => deque.len(),
+        }
+    }
+
+    fn has_room(&self) -> bool {
+        match &self.buffer {
+            Buffer::Unbounded(_) => true,
+            Buffer::Bounded(deque) => deque.len() + self.reserved < deque.capacity(),
+        }
+    }
+
+    // cond: has_room or a reserved slot
+    fn push(&mut self, elem: T) -> Option<Waker> {
+        match &mut self.buffer {
+            Buffer::Unbounded(queue) => queue.push(elem),
+            Buffer::Bounded(deque) => deque.push_back(elem),
+        }
+        self.poppers.take_one()
+    }
+
+    fn pop(&mut self) -> Option<(T, Option<Waker>)> {
+        let elem = match &mut self.buffer {
+            Buffer::Unbounded(queue) => queue.pop(),
+            Buffer::Bounded(deque) => deque.pop_front(),
+        }?;
+        Some((elem, self.pushers.take_one()))
+    }
+}
+
+fn wake(waker: Option<Waker>) {
+    if let Some(waker) = waker {
+        waker.wake()
+    }
+}
+
+// FIFO channel for async tasks, unbounded on a LinkedQueue or bounded on a RingDeque.
+// It works with any executor, waiting tasks are woken in the order they started waiting.
+pub struct AsyncQueue<T> {
+    state: Mutex<State<T>>,
+}
+
+impl<T> AsyncQueue<T> {
+    fn with_buffer(buffer: Buffer<T>) -> Self {
+        Self {
+            state: Mutex::new(State {
+                buffer,
+                reserved: 0,
+                closed: false,
+                poppers: Waiters::new(),
+                pushers: Waiters::new(),
+            }),
+        }
+    }
+
+    pub fn new() -> Self {
+        Self::with_buffer(Buffer::Unbounded(LinkedQueue::new()))
+    }
+
+    // panics if capacity is 0
+    pub fn bounded(capacity: usize) -> Self {
+        assert!(capacity > 0, "bounded queue needs capacity");
+        Self::with_buffer(Buffer::Bounded(RingDeque::new(capacity)))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // None for an unbounded queue
+    pub fn capacity(&self) -> Option<usize> {
+        match &self.lock().buffer {
+            Buffer::Unbounded(_) => None,
+            Buffer::Bounded(deque) => Some(deque.capacity()),
+        }
+    }
+
+    // Pushes fail from now on, pops drain the remaining elements and then return None.
+    pub fn close(&self) {
+        let mut state = self.lock();
+        state.closed = true;
+        let mut wakers = state.poppers.take_all();
+        wakers.extend(state.pushers.take_all());
+        drop(state);
+        wakers.into_iter().for_each(Waker::wake);
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    // gives the element back if the queue is full or closed
+    pub fn try_push(&self, elem: T) -> Result<(), T> {
+        let mut state = self.lock();
+        if state.closed || !state.has_room() {
+            return Err(elem);
+        }
+        let waker = state.push(elem);
+        drop(state);
+        wake(waker);
+        Ok(())
+    }
+
+    pub fn try_pop(&self) -> Option<T> {
+        let (elem, waker) = self.lock().pop()?;
+        wake(waker);
+        Some(elem)
+    }
+
+    // waits for room, gives the element back if the queue is closed
+    pub async fn push(&self, elem: T) -> Result<(), T> {
+        PushFuture {
+            queue: self,
+            elem: Some(elem),
+            waiter: None,
+        }
+        .await
+    }
+
+    // waits for an element, returns None once the queue is closed and empty
+    pub async fn pop(&self) -> Option<T> {
+        PopFuture {
+            queue: self,
+            waiter: None,
+        }
+        .await
+    }
+
+    pub fn stream(&self) -> PopStream<'_, T> {
+        PopStream {
+            queue: self,
+            waiter: None,
+        }
+    }
+
+    pub fn sink(&self) -> PushSink<'_, T> {
+        PushSink {
+            queue: self,
+            waiter: None,
+            reserved: false,
+            closed: false,
+        }
+    }
+
+    fn poll_pop(&self, cx: &mut Context<'_>, waiter: &mut Option<usize>) -> Poll<Option<T>> {
+        let mut state = self.lock();
+        if let Some((elem, waker)) = state.pop() {
+            state.poppers.remove(waiter);
+            drop(state);
+            wake(waker);
+            return Poll::Ready(Some(elem));
+        }
+        if state.closed {
+            state.poppers.remove(waiter);
+            return Poll::Ready(None);
+        }
+        state.poppers.register(waiter, cx.waker());
+        Poll::Pending
+    }
+
+    fn poll_push(
+        &self,
+        cx: &mut Context<'_>,
+        elem: &mut Option<T>,
+        waiter: &mut Option<usize>,
+    ) -> Poll<Result<(), T>> {
+        let mut state = self.lock();
+        let item = elem.take().expect("push polled after completion");
+        if state.closed {
+            state.pushers.remove(waiter);
+            return Poll::Ready(Err(item));
+        }
+        if !state.has_room() {
+            *elem = Some(item);
+            state.pushers.register(waiter, cx.waker());
+            return Poll::Pending;
+        }
+        state.pushers.remove(waiter);
+        let waker = state.push(item);
+        drop(state);
+        wake(waker);
+        Poll::Ready(Ok(()))
+    }
+
+    // a wakeup meant for a cancelled popper goes to the next one
+    fn cancel_pop(&self, waiter: &mut Option<usize>) {
+        if waiter.is_none() {
+            return;
+        }
+        let mut state = self.lock();
+        if !state.poppers.remove(waiter) && state.len() > 0 {
+            let waker = state.poppers.take_one();
+            drop(state);
+            wake(waker);
+        }
+    }
+
+    // a wakeup meant for a cancelled pusher goes to the next one
+    fn cancel_push(&self, waiter: &mut Option<usize>) {
+        if waiter.is_none() {
+            return;
+        }
+        let mut state = self.lock();
+        if !state.pushers.remove(waiter) && state.has_room() {
+            let waker = state.pushers.take_one();
+            drop(state);
+            wake(waker);
+        }
+    }
+}
+
+impl<T> Default for AsyncQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct PopFuture<'a, T> {
+    queue: &'a AsyncQueue<T>,
+    waiter: Option<usize>,
+}
+
+impl<T> Future for PopFuture<'_, T> {
+    type Output = Option<T>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        this.queue.poll_pop(cx, &mut this.waiter)
+    }
+}
+
+impl<T> Drop for PopFuture<'_, T> {
+    fn drop(&mut self) {
+        self.queue.cancel_pop(&mut self.waiter)
+    }
+}
+
+struct PushFuture<'a, T> {
+    queue: &'a AsyncQueue<T>,
+    elem: Option<T>,
+    waiter: Option<usize>,
+}
+
+// the element is never pinned
+impl<T> Unpin for PushFuture<'_, T> {}
+
+impl<T> Future for PushFuture<'_, T> {
+    type Output = Result<(), T>;
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), T>> {
+        let this = self.get_mut();
+        this.queue.poll_push(cx, &mut this.elem, &mut this.waiter)
+    }
+}
+
+impl<T> Drop for PushFuture<'_, T> {
+    fn drop(&mut self) {
+        self.queue.cancel_push(&mut self.waiter)
+    }
+}
+
+// The receiving end as a Stream.
+pub struct PopStream<'a, T> {
+    queue: &'a AsyncQueue<T>,
+    waiter: Option<usize>,
+}
+
+impl<T> Stream for PopStream<'_, T> {
+    type Item = T;
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+        this.queue.poll_pop(cx, &mut this.waiter)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.queue.len();
+        if self.queue.is_closed() {
+            (len, Some(len))
+        } else {
+            (len, None)
+        }
+    }
+}
+
+impl<T> Drop for PopStream<'_, T> {
+    fn drop(&mut self) {
+        self.queue.cancel_pop(&mut self.waiter)
+    }
+}
+
+// The sending end as a Sink, poll_ready reserves a slot which the following start_send fills.
+// Closing a sink only closes that handle, the queue stays open for the other pushers.
+pub struct PushSink<'a, T> {
+    queue: &'a AsyncQueue<T>,
+    waiter: Option<usize>,
+    reserved: bool,
+    closed: bool,
+}
+
+impl<T> PushSink<'_, T> {
+    // gives back the reserved slot and stops waiting
+    fn release(&mut self) {
+        if self.reserved {
+            self.reserved = false;
+            let mut state = self.queue.lock();
+            state.reserved -= 1;
+            let waker = state.pushers.take_one();
+            drop(state);
+            wake(waker);
+        }
+        self.queue.cancel_push(&mut self.waiter)
+    }
+}
+
+impl<T> Sink<T> for PushSink<'_, T> {
+    type Error = Closed;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+        let this = self.get_mut();
+        if this.closed {
+            return Poll::Ready(Err(Closed));
+        }
+        let mut state = this.queue.lock();
+        if state.closed {
+            state.pushers.remove(&mut this.waiter);
+            return Poll::Ready(Err(Closed));
+        }
+        if this.reserved {
+            return Poll::Ready(Ok(()));
+        }
+        if !state.has_room() {
+            state.pushers.register(&mut this.waiter, cx.waker());
+            return Poll::Pending;
+        }
+        state.pushers.remove(&mut this.waiter);
+        state.reserved += 1;
+        this.reserved = true;
+        Poll::Ready(Ok(()))
+    }
+
+    // panics if poll_ready has not reserved a slot
+    fn start_send(self: Pin<&mut Self>, elem: T) -> Result<(), Closed> {
+        let this = self.get_mut();
+        if this.closed {
+            return Err(Closed);
+        }
+        assert!(this.reserved, "start_send called without poll_ready");
+        let mut state = this.queue.lock();
+        state.reserved -= 1;
+        this.reserved = false;
+        if state.closed {
+            return Err(Closed);
+        }
+        let waker = state.push(elem);
+        drop(state);
+        wake(waker);
+        Ok(())
+    }
+
+    // pushed elements are visible at once
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Closed>> {
+        let this = self.get_mut();
+        this.release();
+        this.closed = true;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> Drop for PushSink<'_, T> {
+    fn drop(&mut self) {
+        self.release()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AsyncQueue, Closed};
+    use crate::async_queue::executor::{block_on, LocalExecutor};
+    use crate::async_queue::{Sink, Stream};
+
+    use std::future::{poll_fn, Future};
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    struct CountWaker(AtomicUsize);
+
+    impl Wake for CountWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn count_waker() -> (Arc<CountWaker>, Waker) {
+        let count = Arc::new(CountWaker(AtomicUsize::new(0)));
+        (count.clone(), Waker::from(count))
+    }
+
+    #[test]
+    fn test_async_queue() {
+        let queue = AsyncQueue::bounded(2);
+        assert_eq!(queue.capacity(), Some(2));
+        assert_eq!(queue.try_push(1), Ok(()));
+        assert_eq!(queue.try_push(2), Ok(()));
+        assert_eq!(queue.try_push(3), Err(3));
+        assert_eq!(block_on(queue.pop()), Some(1));
+        assert_eq!(block_on(queue.push(3)), Ok(()));
+        assert_eq!(queue.len(), 2);
+
+        queue.close();
+        assert_eq!(block_on(queue.push(4)), Err(4));
+        assert_eq!(queue.try_pop(), Some(2));
+        assert_eq!(block_on(queue.pop()), Some(3));
+        assert_eq!(block_on(queue.pop()), None);
+
+        let queue = AsyncQueue::new();
+        assert_eq!(queue.capacity(), None);
+        for i in 0..100 {
+            assert_eq!(queue.try_push(i), Ok(()));
+        }
+        assert_eq!(queue.len(), 100);
+    }
+
+    #[test]
+    fn test_tasks() {
+        let queue = Rc::new(AsyncQueue::bounded(3));
+        let received = Rc::new(std::cell::RefCell::new(Vec::new()));
+        let mut executor = LocalExecutor::new();
+
+        for p in 0..3 {
+            let queue = queue.clone();
+            executor.spawn(async move {
+                for i in 0..100 {
+                    queue.push((p, i)).await.unwrap();
+                }
+            });
+        }
+        for _ in 0..2 {
+            let (queue, received) = (queue.clone(), received.clone());
+            executor.spawn(async move {
+                while let Some(elem) = queue.pop().await {
+                    received.borrow_mut().push(elem);
+                    if received.borrow().len() == 300 {
+                        queue.close();
+                    }
+                }
+            });
+        }
+        executor.run();
+
+        let received = received.borrow();
+        assert_eq!(received.len(), 300);
+        for p in 0..3 {
+            let sent: Vec<_> = received.iter().filter(|e| e.0 == p).map(|e| e.1).collect();
+            assert!(sent.into_iter().eq(0..100));
+        }
+    }
+
+    #[test]
+    fn test_threads() {
+        let queue = Arc::new(AsyncQueue::bounded(4));
+        let consumer = {
+            let queue = queue.clone();
+            std::thread::spawn(move || {
+                let mut sum = 0;
+                while let Some(elem) = block_on(queue.pop()) {
+                    sum += elem;
+                }
+                sum
+            })
+        };
+        for i in 1..=1000 {
+            block_on(queue.push(i)).unwrap();
+        }
+        queue.close();
+        assert_eq!(consumer.join().unwrap(), 500_500);
+    }
+
+    #[test]
+    fn test_stream_and_sink() {
+        let queue = AsyncQueue::bounded(1);
+        let mut stream = queue.stream();
+        let mut sink = queue.sink();
+
+        block_on(async {
+            poll_fn(|cx| Pin::new(&mut sink).poll_ready(cx))
+                .await
+                .unwrap();
+            Pin::new(&mut sink).start_send("a").unwrap();
+            assert_eq!(
+                poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await,
+                Some("a")
+            );
+
+            poll_fn(|cx| Pin::new(&mut sink).poll_ready(cx))
+                .await
+                .unwrap();
+            // the reserved slot keeps other pushers out
+            assert_eq!(queue.try_push("x"), Err("x"));
+            Pin::new(&mut sink).start_send("b").unwrap();
+            poll_fn(|cx| Pin::new(&mut sink).poll_close(cx))
+                .await
+                .unwrap();
+
+            // closing the sink leaves the queue open
+            assert_eq!(
+                poll_fn(|cx| Pin::new(&mut sink).poll_ready(cx)).await,
+                Err(Closed)
+            );
+            assert_eq!(Pin::new(&mut sink).start_send("x"), Err(Closed));
+            assert!(!queue.is_closed());
+            assert_eq!(stream.size_hint(), (1, None));
+            assert_eq!(
+                poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await,
+                Some("b")
+            );
+
+            let mut other = queue.sink();
+            poll_fn(|cx| Pin::new(&mut other).poll_ready(cx))
+                .await
+                .unwrap();
+            Pin::new(&mut other).start_send("c").unwrap();
+            queue.close();
+            assert_eq!(
+                poll_fn(|cx| Pin::new(&mut other).poll_ready(cx)).await,
+                Err(Closed)
+            );
+            assert_eq!(stream.size_hint(), (1, Some(1)));
+            assert_eq!(
+                poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await,
+                Some("c")
+            );
+            assert_eq!(
+                poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await,
+                None
+            );
+        });
+    }
+
+    #[test]
+    fn test_sink_close_releases_slot() {
+        let queue = AsyncQueue::bounded(1);
+        let (pushed, waker) = count_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut sink = queue.sink();
+        assert_eq!(Pin::new(&mut sink).poll_ready(&mut cx), Poll::Ready(Ok(())));
+
+        // a pusher waits for the slot the sink has reserved
+        let mut push = Box::pin(queue.push(1));
+        assert_eq!(push.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(Pin::new(&mut sink).poll_close(&mut cx), Poll::Ready(Ok(())));
+        assert_eq!(pushed.0.load(Ordering::SeqCst), 1);
+        assert_eq!(push.as_mut().poll(&mut cx), Poll::Ready(Ok(())));
+        assert_eq!(queue.try_pop(), Some(1));
+    }
+
+    #[test]
+    fn test_cancelled_waiter() {
+        let queue = AsyncQueue::new();
+        let (first, first_waker) = count_waker();
+        let (second, second_waker) = count_waker();
+        let mut pop1 = Box::pin(queue.pop());
+        let mut pop2 = Box::pin(queue.pop());
+        let mut cx1 = Context::from_waker(&first_waker);
+        let mut cx2 = Context::from_waker(&second_waker);
+        assert_eq!(pop1.as_mut().poll(&mut cx1), Poll::Pending);
+        assert_eq!(pop2.as_mut().poll(&mut cx2), Poll::Pending);
+
+        queue.try_push(7).unwrap();
+        assert_eq!(first.0.load(Ordering::SeqCst), 1);
+        assert_eq!(second.0.load(Ordering::SeqCst), 0);
+
+        // the first popper gives up, its wakeup must not be lost
+        drop(pop1);
+        assert_eq!(second.0.load(Ordering::SeqCst), 1);
+        assert_eq!(pop2.as_mut().poll(&mut cx2), Poll::Ready(Some(7)));
+    }
+}
@@ -0,0 +1,35 @@
+// Local mirrors of futures_core::Stream and futures_sink::Sink, the crate has no dependencies.
+// The methods have the same signatures, so an adapter to the futures traits only forwards them.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// An asynchronous sequence of values.
+pub trait Stream {
+    type Item;
+
+    /// Attempts to pull the next value, `Ready(None)` once the stream is exhausted.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>>;
+
+    /// Bounds on the number of values left, like `Iterator::size_hint`.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+}
+
+/// A value into which other values can be sent asynchronously.
+pub trait Sink<Item> {
+    type Error;
+
+    /// Prepares the sink to receive a value, which must succeed before each `start_send`.
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>>;
+
+    /// Begins sending a value to the sink.
+    fn start_send(self: Pin<&mut Self>, item: Item) -> Result<(), Self::Error>;
+
+    /// Completes the sending of the values started so far.
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>>;
+
+    /// Flushes the sink and closes it, no value can be sent afterwards.
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>>;
+}
@@ -0,0 +1,267 @@
+use super::policy::{CachePolicy, CacheStats};
+use super::segments::{entry, entry_mut, Segments};
+
+use std::borrow::Borrow;
+use std::cmp;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+
+// resident entries seen once and more than once
+const T1: usize = 0;
+const T2: usize = 1;
+// ghost keys evicted from T1 and T2
+const B1: usize = 2;
+const B2: usize = 3;
+
+// Adaptive replacement cache (Megiddo and Modha).
+// Resident entries are split between a recency list T1 and a frequency list T2,
+// the keys recently evicted from each list are remembered in the ghost lists B1 and B2.
+// A hit in a ghost list moves the target size of T1 towards the list that would have kept the entry.
+pub struct ArcCache<K, V, S = RandomState> {
+    segs: Segments<K, V, S, 4>,
+    capacity: usize,
+    target: usize,
+    stats: CacheStats,
+}
+
+impl<K: Hash + Eq, V> ArcCache<K, V, RandomState> {
+    // panics if capacity is 0
+    pub fn new(capacity: usize) -> Self {
+        Self::with_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> ArcCache<K, V, S> {
+    // panics if capacity is 0
+    pub fn with_hasher(capacity: usize, hasher: S) -> Self {
+        assert!(capacity > 0, "cache needs capacity");
+        Self {
+            segs: Segments::with_capacity_and_hasher(capacity * 2, hasher),
+            capacity,
+            target: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.segs.len(T1) + self.segs.len(T2)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // the adaptive target size of T1
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    // moves the entry to the frequency list
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self
+            .segs
+            .find(key)
+            .filter(|&node| unsafe { entry(node).value.is_some() });
+        self.stats.record(node.is_some());
+        let node = node?;
+        unsafe {
+            self.segs.move_to_front(node, T2);
+            entry_mut(node).value.as_mut()
+        }
+    }
+
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.segs.find(key)?;
+        unsafe { entry(node).value.as_ref() }
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.peek(key).is_some()
+    }
+
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(node) = self.segs.find(&key) {
+            match unsafe { entry(node).seg() } {
+                T1 | T2 => {}
+                B1 => {
+                    let delta = cmp::max(self.segs.len(B2) / self.segs.len(B1), 1);
+                    self.target = cmp::min(self.target + delta, self.capacity);
+                    self.replace(false);
+                }
+                _ => {
+                    let delta = cmp::max(self.segs.len(B1) / self.segs.len(B2), 1);
+                    self.target = self.target.saturating_sub(delta);
+                    self.replace(true);
+                }
+            }
+            unsafe {
+                self.segs.move_to_front(node, T2);
+                return entry_mut(node).value.replace(value);
+            }
+        }
+
+        let l1 = self.segs.len(T1) + self.segs.len(B1);
+        if l1 >= self.capacity {
+            if self.segs.len(T1) < self.capacity {
+                self.segs.pop_back(B1);
+                self.replace(false);
+            } else {
+                self.segs.pop_back(T1);
+                self.stats.evictions += 1;
+            }
+        } else {
+            let total = l1 + self.segs.len(T2) + self.segs.len(B2);
+            if total >= self.capacity {
+                if total >= self.capacity * 2 {
+                    self.segs.pop_back(B2);
+                }
+                self.replace(false);
+            }
+        }
+        self.segs.push_front(T1, key, Some(value));
+        None
+    }
+
+    // evicts the value of a resident entry into a ghost list if the cache is full
+    fn replace(&mut self, hit_b2: bool) {
+        if self.len() < self.capacity {
+            return;
+        }
+        let t1 = self.segs.len(T1);
+        if t1 > 0 && (t1 > self.target || (hit_b2 && t1 == self.target)) {
+            self.segs.demote_back(T1, B1);
+        } else {
+            self.segs.demote_back(T2, B2);
+        }
+        self.stats.evictions += 1;
+    }
+
+    // ghost keys are kept
+    pub fn pop<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.segs.find(key)?;
+        unsafe {
+            entry(node).value.as_ref()?;
+            self.segs.remove(node).1
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.segs.clear();
+        self.target = 0;
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> CachePolicy<K, V> for ArcCache<K, V, S> {
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.peek(key)
+    }
+
+    fn put(&mut self, key: K, value: V) -> Option<V> {
+        self.put(key, value)
+    }
+
+    fn pop(&mut self, key: &K) -> Option<V> {
+        self.pop(key)
+    }
+
+    fn clear(&mut self) {
+        self.clear()
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.reset_stats()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ArcCache;
+
+    #[test]
+    fn test_arc_cache() {
+        let mut cache = ArcCache::new(4);
+        assert_eq!(cache.put(1, "a"), None);
+        assert_eq!(cache.put(2, "b"), None);
+        assert_eq!(cache.get(&1), Some(&"a"));
+        assert_eq!(cache.get(&2), Some(&"b"));
+
+        // a scan of keys seen once does not flush the entries seen twice
+        for k in 100..120 {
+            cache.put(k, "scan");
+        }
+        assert_eq!(cache.len(), 4);
+        assert!(cache.contains(&1));
+        assert!(cache.contains(&2));
+
+        // a hit in the ghost list of T1 grows the target size of T1
+        assert!(!cache.contains(&117));
+        assert_eq!(cache.put(117, "again"), None);
+        assert_eq!(cache.target(), 1);
+        assert_eq!(cache.peek(&117), Some(&"again"));
+        assert_eq!(cache.len(), 4);
+
+        assert_eq!(cache.put(1, "c"), Some("a"));
+        assert_eq!(cache.pop(&1), Some("c"));
+        assert_eq!(cache.pop(&1), None);
+        assert_eq!(cache.len(), 3);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 0));
+        assert_eq!(stats.evictions, 19);
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.get(&2), None);
+    }
+}
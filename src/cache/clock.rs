@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A monotonic time source.
+pub trait Clock {
+    /// The time elapsed since a fixed epoch of the clock.
+    fn now(&self) -> Duration;
+}
+
+// Monotonic clock whose epoch is its creation.
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+// Clock which only moves when it is told to, clones share the same time.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.nanos.fetch_add(by.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    // panics if the clock would go backwards
+    pub fn set(&self, now: Duration) {
+        // an earlier time is never stored
+        let prev = self
+            .nanos
+            .fetch_max(now.as_nanos() as u64, Ordering::SeqCst);
+        assert!(prev <= now.as_nanos() as u64, "clock went backwards");
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Clock, ManualClock};
+
+    use std::panic;
+    use std::time::Duration;
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new();
+        let shared = clock.clone();
+        clock.advance(Duration::from_secs(2));
+        shared.set(Duration::from_secs(5));
+        assert_eq!(clock.now(), Duration::from_secs(5));
+
+        let backwards = panic::catch_unwind(|| clock.set(Duration::from_secs(1)));
+        assert!(backwards.is_err());
+        assert_eq!(shared.now(), Duration::from_secs(5));
+    }
+}
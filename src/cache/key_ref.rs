@@ -0,0 +1,55 @@
+use std::borrow::Borrow;
+use std::hash::{Hash, Hasher};
+
+// A key of the hash index, pointing at the key stored in a list node.
+// cond: the node outlives the entry of the index
+pub(crate) struct KeyRef<K>(pub(crate) *const K);
+
+impl<K: Hash> Hash for KeyRef<K> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        unsafe { (*self.0).hash(state) }
+    }
+}
+
+impl<K: PartialEq> PartialEq for KeyRef<K> {
+    fn eq(&self, other: &Self) -> bool {
+        unsafe { *self.0 == *other.0 }
+    }
+}
+
+impl<K: Eq> Eq for KeyRef<K> {}
+
+// Looks up a KeyRef<K> by a borrowed form Q of K,
+// a KeyRef<K> cannot borrow as Q itself since that impl would overlap with Borrow<T> for T.
+#[repr(transparent)]
+pub(crate) struct KeyWrapper<Q: ?Sized>(Q);
+
+impl<Q: ?Sized> KeyWrapper<Q> {
+    pub(crate) fn from_ref(key: &Q) -> &Self {
+        unsafe { &*(key as *const Q as *const Self) }
+    }
+}
+
+impl<Q: Hash + ?Sized> Hash for KeyWrapper<Q> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl<Q: PartialEq + ?Sized> PartialEq for KeyWrapper<Q> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<Q: Eq + ?Sized> Eq for KeyWrapper<Q> {}
+
+impl<K, Q> Borrow<KeyWrapper<Q>> for KeyRef<K>
+where
+    K: Borrow<Q>,
+    Q: ?Sized,
+{
+    fn borrow(&self) -> &KeyWrapper<Q> {
+        KeyWrapper::from_ref(unsafe { (*self.0).borrow() })
+    }
+}
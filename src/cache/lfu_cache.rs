@@ -0,0 +1,336 @@
+use super::key_ref::{KeyRef, KeyWrapper};
+use super::policy::{CachePolicy, CacheStats};
+use crate::hash::HashMap;
+use crate::linear_list::linked_list::{LinkedList, Node};
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+use std::ptr::NonNull;
+
+struct Item<K, V> {
+    key: K,
+    value: V,
+    bucket: BucketLink<K, V>,
+}
+
+// the items used freq times, from the most to the least recently used
+struct Bucket<K, V> {
+    freq: u64,
+    items: LinkedList<Item<K, V>>,
+}
+
+type ItemLink<K, V> = NonNull<Node<Item<K, V>>>;
+type BucketLink<K, V> = NonNull<Node<Bucket<K, V>>>;
+
+// Least frequently used cache with O(1) operations (Shah, Mitra and Matani).
+// The buckets are kept in a LinkedList by increasing frequency and each bucket is a LinkedList
+// of its items, so a use moves an item to the next bucket by relinking its node.
+// Ties between the least frequently used items are broken by recency.
+pub struct LfuCache<K, V, S = RandomState> {
+    buckets: LinkedList<Bucket<K, V>>,
+    index: HashMap<KeyRef<K>, ItemLink<K, V>, S>,
+    capacity: usize,
+    stats: CacheStats,
+}
+
+unsafe impl<K: Send, V: Send, S: Send> Send for LfuCache<K, V, S> {}
+unsafe impl<K: Sync, V: Sync, S: Sync> Sync for LfuCache<K, V, S> {}
+
+impl<K: Hash + Eq, V> LfuCache<K, V, RandomState> {
+    // panics if capacity is 0
+    pub fn new(capacity: usize) -> Self {
+        Self::with_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> LfuCache<K, V, S> {
+    // panics if capacity is 0
+    pub fn with_hasher(capacity: usize, hasher: S) -> Self {
+        assert!(capacity > 0, "cache needs capacity");
+        Self {
+            buckets: LinkedList::new(),
+            index: HashMap::with_capacity_and_hasher(capacity, hasher),
+            capacity,
+            stats: CacheStats::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    fn item<Q>(&self, key: &Q) -> Option<ItemLink<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.get(KeyWrapper::from_ref(key)).copied()
+    }
+
+    // moves the item to the bucket of the next frequency
+    // cond: item belongs to self
+    unsafe fn touch(&mut self, item: ItemLink<K, V>) {
+        let bucket = Node::elem(item).bucket;
+        let freq = Node::elem(bucket).freq;
+        let next = match self.buckets.next_node(bucket) {
+            Some(next) if Node::elem(next).freq == freq + 1 => next,
+            _ => self.buckets.insert_after_node(
+                bucket,
+                Bucket {
+                    freq: freq + 1,
+                    items: LinkedList::new(),
+                },
+            ),
+        };
+        let from = &mut Node::elem_mut(bucket).items;
+        Node::elem_mut(next).items.transfer_to_front(from, item);
+        Node::elem_mut(item).bucket = next;
+        if from.is_empty() {
+            self.buckets.unlink_node(bucket);
+        }
+    }
+
+    // cond: item belongs to self
+    unsafe fn remove(&mut self, item: ItemLink<K, V>) -> (K, V) {
+        self.index
+            .remove(KeyWrapper::from_ref(&Node::elem(item).key));
+        let bucket = Node::elem(item).bucket;
+        let items = &mut Node::elem_mut(bucket).items;
+        let item = items.unlink_node(item);
+        if items.is_empty() {
+            self.buckets.unlink_node(bucket);
+        }
+        (item.key, item.value)
+    }
+
+    // counts a use of the entry
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let item = self.item(key);
+        self.stats.record(item.is_some());
+        let item = item?;
+        unsafe {
+            self.touch(item);
+            Some(&mut Node::elem_mut(item).value)
+        }
+    }
+
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let item = self.item(key)?;
+        Some(unsafe { &Node::elem(item).value })
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.item(key).is_some()
+    }
+
+    // the number of uses of the entry, including the put which inserted it
+    pub fn frequency<Q>(&self, key: &Q) -> Option<u64>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let item = self.item(key)?;
+        Some(unsafe { Node::elem(Node::elem(item).bucket).freq })
+    }
+
+    // counts a use of an existing entry
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(item) = self.item(&key) {
+            unsafe {
+                self.touch(item);
+                return Some(mem::replace(&mut Node::elem_mut(item).value, value));
+            }
+        }
+        if self.len() == self.capacity {
+            self.pop_lfu();
+            self.stats.evictions += 1;
+        }
+        let bucket = match self.buckets.front_node() {
+            Some(bucket) if unsafe { Node::elem(bucket).freq } == 1 => bucket,
+            _ => self.buckets.push_front_node(Bucket {
+                freq: 1,
+                items: LinkedList::new(),
+            }),
+        };
+        unsafe {
+            let item = Node::elem_mut(bucket)
+                .items
+                .push_front_node(Item { key, value, bucket });
+            self.index.insert(KeyRef(&Node::elem(item).key), item);
+        }
+        None
+    }
+
+    pub fn pop<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let item = self.item(key)?;
+        Some(unsafe { self.remove(item) }.1)
+    }
+
+    // removes the least recently used of the least frequently used entries
+    pub fn pop_lfu(&mut self) -> Option<(K, V)> {
+        let bucket = self.buckets.front_node()?;
+        unsafe {
+            let item = Node::elem(bucket).items.back_node()?;
+            Some(self.remove(item))
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.index.clear();
+        self.buckets.clear();
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> CachePolicy<K, V> for LfuCache<K, V, S> {
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.peek(key)
+    }
+
+    fn put(&mut self, key: K, value: V) -> Option<V> {
+        self.put(key, value)
+    }
+
+    fn pop(&mut self, key: &K) -> Option<V> {
+        self.pop(key)
+    }
+
+    fn clear(&mut self) {
+        self.clear()
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.reset_stats()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::LfuCache;
+    use crate::cache::{ArcCache, CachePolicy, LruCache, TwoQueueCache};
+
+    #[test]
+    fn test_lfu_cache() {
+        let mut cache = LfuCache::new(3);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.put("c", 3);
+        assert_eq!(cache.get(&"a"), Some(&1));
+        assert_eq!(cache.get(&"a"), Some(&1));
+        assert_eq!(cache.put("b", 20), Some(2));
+        assert_eq!(cache.frequency(&"a"), Some(3));
+        assert_eq!(cache.frequency(&"b"), Some(2));
+        assert_eq!(cache.frequency(&"c"), Some(1));
+
+        // c is the least frequently used
+        cache.put("d", 4);
+        assert!(!cache.contains(&"c"));
+        // d and e tie, d is older
+        cache.put("e", 5);
+        assert!(!cache.contains(&"d"));
+        assert_eq!(cache.peek(&"e"), Some(&5));
+        assert_eq!(cache.frequency(&"e"), Some(1));
+
+        assert_eq!(cache.pop_lfu(), Some(("e", 5)));
+        assert_eq!(cache.pop(&"a"), Some(1));
+        assert_eq!(cache.pop(&"a"), None);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get(&"c"), None);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (2, 1, 2));
+        assert_eq!(stats.hit_ratio(), 2.0 / 3.0);
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.pop_lfu(), None);
+    }
+
+    // a hot set of keys used twice per round, interleaved with a scan of keys used once
+    fn scan_workload(cache: &mut dyn CachePolicy<u32, u32>) -> u64 {
+        for round in 0..50 {
+            for _ in 0..2 {
+                for k in 0..8 {
+                    if cache.get(&k).is_none() {
+                        cache.put(k, k);
+                    }
+                }
+            }
+            for k in 0..16 {
+                let k = 1000 + round * 16 + k;
+                cache.put(k, k);
+            }
+        }
+        assert!(cache.len() <= cache.capacity());
+        cache.stats().hits
+    }
+
+    #[test]
+    fn test_scan_resistance() {
+        let lru = scan_workload(&mut LruCache::new(16));
+        let lfu = scan_workload(&mut LfuCache::new(16));
+        let arc = scan_workload(&mut ArcCache::new(16));
+        let two_queue = scan_workload(&mut TwoQueueCache::new(16));
+        // lru only hits on the second use in a round
+        assert_eq!(lru, 8 * 50);
+        for hits in [lfu, arc, two_queue] {
+            assert!(hits >= 16 * 48, "{} {} {}", lfu, arc, two_queue);
+        }
+    }
+}
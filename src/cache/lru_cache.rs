@@ -0,0 +1,391 @@
+use super::key_ref::{KeyRef, KeyWrapper};
+use super::policy::{CachePolicy, CacheStats};
+use crate::hash::HashMap;
+use crate::linear_list::linked_list::{self, LinkedList, Node};
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::iter::FusedIterator;
+use std::mem;
+use std::ptr::NonNull;
+
+type Link<K, V> = NonNull<Node<(K, V)>>;
+
+// called with the entries dropped for lack of room
+type OnEvict<K, V> = Box<dyn FnMut(K, V) + Send>;
+
+// Least recently used cache. The entries are kept in a LinkedList from the most to
+// the least recently used one, and a hash index maps each key to its node.
+pub struct LruCache<K, V, S = RandomState> {
+    list: LinkedList<(K, V)>,
+    index: HashMap<KeyRef<K>, Link<K, V>, S>,
+    capacity: usize,
+    on_evict: Option<OnEvict<K, V>>,
+    stats: CacheStats,
+}
+
+unsafe impl<K: Send, V: Send, S: Send> Send for LruCache<K, V, S> {}
+unsafe impl<K: Sync, V: Sync, S: Sync> Sync for LruCache<K, V, S> {}
+
+impl<K: Hash + Eq, V> LruCache<K, V, RandomState> {
+    // panics if capacity is 0
+    pub fn new(capacity: usize) -> Self {
+        Self::with_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> LruCache<K, V, S> {
+    // panics if capacity is 0
+    pub fn with_hasher(capacity: usize, hasher: S) -> Self {
+        assert!(capacity > 0, "cache needs capacity");
+        Self {
+            list: LinkedList::new(),
+            index: HashMap::with_capacity_and_hasher(capacity, hasher),
+            capacity,
+            on_evict: None,
+            stats: CacheStats::default(),
+        }
+    }
+
+    pub fn set_on_evict<F: FnMut(K, V) + Send + 'static>(&mut self, on_evict: F) {
+        self.on_evict = Some(Box::new(on_evict));
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    fn node<Q>(&self, key: &Q) -> Option<Link<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.get(KeyWrapper::from_ref(key)).copied()
+    }
+
+    // returns the old value and makes the entry the most recently used one
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(node) = self.node(&key) {
+            unsafe {
+                self.list.move_to_front(node);
+                return Some(mem::replace(&mut Node::elem_mut(node).1, value));
+            }
+        }
+        if self.len() == self.capacity {
+            self.evict();
+        }
+        let node = self.list.push_front_node((key, value));
+        let key = unsafe { &Node::elem(node).0 };
+        self.index.insert(KeyRef(key), node);
+        None
+    }
+
+    // makes the entry the most recently used one
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.node(key);
+        self.stats.record(node.is_some());
+        let node = node?;
+        unsafe {
+            self.list.move_to_front(node);
+            Some(&mut Node::elem_mut(node).1)
+        }
+    }
+
+    // leaves the order of use alone
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.node(key)?;
+        Some(unsafe { &Node::elem(node).1 })
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.node(key).is_some()
+    }
+
+    // the entry to be evicted next
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        self.list.back().map(|(k, v)| (k, v))
+    }
+
+    pub fn pop<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.index.remove(KeyWrapper::from_ref(key))?;
+        Some(unsafe { self.list.unlink_node(node) }.1)
+    }
+
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let node = self.list.back_node()?;
+        unsafe {
+            self.index.remove(KeyWrapper::from_ref(&Node::elem(node).0));
+            Some(self.list.unlink_node(node))
+        }
+    }
+
+    fn evict(&mut self) {
+        if let Some((key, value)) = self.pop_lru() {
+            self.stats.evictions += 1;
+            if let Some(on_evict) = &mut self.on_evict {
+                on_evict(key, value);
+            }
+        }
+    }
+
+    // evicts the least recently used entries that no longer fit
+    // panics if capacity is 0
+    pub fn resize(&mut self, capacity: usize) {
+        assert!(capacity > 0, "cache needs capacity");
+        while self.len() > capacity {
+            self.evict();
+        }
+        self.capacity = capacity;
+    }
+
+    pub fn clear(&mut self) {
+        self.index.clear();
+        self.list.clear();
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> CachePolicy<K, V> for LruCache<K, V, S> {
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.peek(key)
+    }
+
+    fn put(&mut self, key: K, value: V) -> Option<V> {
+        self.put(key, value)
+    }
+
+    fn pop(&mut self, key: &K) -> Option<V> {
+        self.pop(key)
+    }
+
+    fn clear(&mut self) {
+        self.clear()
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.reset_stats()
+    }
+}
+
+impl<K, V, S> LruCache<K, V, S> {
+    // from the most to the least recently used entry
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.list.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut(self.list.iter_mut())
+    }
+}
+
+// ------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, K, V>(linked_list::Iter<'a, (K, V)>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a LruCache<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+// end: Iter
+// ------------------------------------
+
+// ------------------------------------
+// begin: IterMut
+
+pub struct IterMut<'a, K, V>(linked_list::IterMut<'a, (K, V)>);
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (&*k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut LruCache<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (&*k, v))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+// end: IterMut
+// ------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::LruCache;
+
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_lru_cache() {
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let mut cache = LruCache::new(3);
+        {
+            let evicted = evicted.clone();
+            cache.set_on_evict(move |k, v| evicted.lock().unwrap().push((k, v)));
+        }
+        assert_eq!(cache.put("a".to_string(), 1), None);
+        assert_eq!(cache.put("b".to_string(), 2), None);
+        assert_eq!(cache.put("c".to_string(), 3), None);
+        assert_eq!(cache.get("a"), Some(&1));
+        assert_eq!(cache.peek("b"), Some(&2));
+        assert_eq!(cache.peek_lru(), Some((&"b".to_string(), &2)));
+
+        assert_eq!(cache.put("d".to_string(), 4), None);
+        assert!(!cache.contains("b"));
+        assert_eq!(cache.put("c".to_string(), 30), Some(3));
+        let order: Vec<_> = cache.iter().map(|(k, v)| (k.as_str(), *v)).collect();
+        assert_eq!(order, [("c", 30), ("d", 4), ("a", 1)]);
+
+        *cache.get_mut("a").unwrap() += 10;
+        assert_eq!(cache.pop("d"), Some(4));
+        assert_eq!(cache.pop("d"), None);
+        cache.put("e".to_string(), 5);
+        cache.resize(1);
+        assert_eq!(cache.capacity(), 1);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.pop_lru(), Some(("e".to_string(), 5)));
+        assert!(cache.is_empty());
+
+        // explicit pops are not evictions
+        let evicted = evicted.lock().unwrap();
+        assert_eq!(
+            *evicted,
+            [
+                ("b".to_string(), 2),
+                ("c".to_string(), 30),
+                ("a".to_string(), 11)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_against_model() {
+        let mut cache = LruCache::new(16);
+        // the keys from the most to the least recently used
+        let mut model: Vec<u32> = Vec::new();
+        let mut x = 1u32;
+        for i in 0..5000 {
+            x = x.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let key = (x >> 16) % 40;
+            if let Some(pos) = model.iter().position(|&k| k == key) {
+                model.remove(pos);
+                if i % 2 == 0 {
+                    assert_eq!(cache.get(&key), Some(&key));
+                } else {
+                    assert_eq!(cache.put(key, key), Some(key));
+                }
+            } else {
+                assert_eq!(cache.get(&key), None);
+                cache.put(key, key);
+                model.truncate(15);
+            }
+            model.insert(0, key);
+            assert!(cache.iter().map(|(k, _)| *k).eq(model.iter().copied()));
+        }
+        for (_, v) in &mut cache {
+            *v = 0;
+        }
+        assert!(cache.iter().rev().all(|(_, v)| *v == 0));
+    }
+}
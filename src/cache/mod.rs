@@ -0,0 +1,19 @@
+pub mod arc_cache;
+pub mod clock;
+mod key_ref;
+pub mod lfu_cache;
+pub mod lru_cache;
+pub mod policy;
+mod segments;
+pub mod timer_wheel;
+pub mod ttl_cache;
+pub mod two_queue_cache;
+
+pub use arc_cache::ArcCache;
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use lfu_cache::LfuCache;
+pub use lru_cache::LruCache;
+pub use policy::{CachePolicy, CacheStats};
+pub use timer_wheel::TimerWheel;
+pub use ttl_cache::TtlCache;
+pub use two_queue_cache::TwoQueueCache;
@@ -0,0 +1,60 @@
+// Lookups and evictions counted by a cache since it was created or its statistics were reset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    pub fn hit_ratio(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+
+    pub(crate) fn record(&mut self, hit: bool) {
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+    }
+}
+
+/// A bounded cache with a replacement policy.
+pub trait CachePolicy<K, V> {
+    fn capacity(&self) -> usize;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Looks up a value, counts a hit or a miss and updates the policy state.
+    fn get(&mut self, key: &K) -> Option<&V>;
+
+    /// Looks up a value without touching the policy state or the statistics.
+    fn peek(&self, key: &K) -> Option<&V>;
+
+    fn contains(&self, key: &K) -> bool {
+        self.peek(key).is_some()
+    }
+
+    /// Inserts or updates a value and returns the old one.
+    /// An entry is evicted when a new key does not fit.
+    fn put(&mut self, key: K, value: V) -> Option<V>;
+
+    /// Removes an entry, which does not count as an eviction.
+    fn pop(&mut self, key: &K) -> Option<V>;
+
+    fn clear(&mut self);
+
+    fn stats(&self) -> CacheStats;
+
+    fn reset_stats(&mut self);
+}
@@ -0,0 +1,123 @@
+use super::key_ref::{KeyRef, KeyWrapper};
+use crate::hash::HashMap;
+use crate::linear_list::linked_list::{LinkedList, Node};
+
+use std::borrow::Borrow;
+use std::hash::{BuildHasher, Hash};
+use std::ptr::NonNull;
+
+// An entry of a segment, ghost entries remember a key without its value.
+pub(crate) struct Entry<K, V> {
+    pub(crate) key: K,
+    pub(crate) value: Option<V>,
+    seg: usize,
+}
+
+impl<K, V> Entry<K, V> {
+    pub(crate) fn seg(&self) -> usize {
+        self.seg
+    }
+}
+
+pub(crate) type Link<K, V> = NonNull<Node<Entry<K, V>>>;
+
+// cond: node belongs to segments which outlive 'a
+pub(crate) unsafe fn entry<'a, K, V>(node: Link<K, V>) -> &'a Entry<K, V> {
+    Node::elem(node)
+}
+
+// cond: node belongs to segments which outlive 'a, and the entry is not aliased
+pub(crate) unsafe fn entry_mut<'a, K, V>(node: Link<K, V>) -> &'a mut Entry<K, V> {
+    Node::elem_mut(node)
+}
+
+// N recency lists sharing one hash index, an entry is moved between the lists by
+// relinking its node so the index stays valid.
+// The front of each list is the most recently used entry.
+pub(crate) struct Segments<K, V, S, const N: usize> {
+    lists: [LinkedList<Entry<K, V>>; N],
+    index: HashMap<KeyRef<K>, Link<K, V>, S>,
+}
+
+unsafe impl<K: Send, V: Send, S: Send, const N: usize> Send for Segments<K, V, S, N> {}
+unsafe impl<K: Sync, V: Sync, S: Sync, const N: usize> Sync for Segments<K, V, S, N> {}
+
+impl<K: Hash + Eq, V, S: BuildHasher, const N: usize> Segments<K, V, S, N> {
+    pub(crate) fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        Self {
+            lists: std::array::from_fn(|_| LinkedList::new()),
+            index: HashMap::with_capacity_and_hasher(capacity, hasher),
+        }
+    }
+
+    pub(crate) fn len(&self, seg: usize) -> usize {
+        self.lists[seg].len()
+    }
+
+    pub(crate) fn find<Q>(&self, key: &Q) -> Option<Link<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.get(KeyWrapper::from_ref(key)).copied()
+    }
+
+    pub(crate) fn push_front(&mut self, seg: usize, key: K, value: Option<V>) -> Link<K, V> {
+        let node = self.lists[seg].push_front_node(Entry { key, value, seg });
+        let key = unsafe { &Node::elem(node).key };
+        self.index.insert(KeyRef(key), node);
+        node
+    }
+
+    // cond: node belongs to self
+    pub(crate) unsafe fn move_to_front(&mut self, node: Link<K, V>, seg: usize) {
+        let from = entry(node).seg;
+        if from == seg {
+            self.lists[seg].move_to_front(node);
+            return;
+        }
+        let (to, from) = if seg < from {
+            let (lhs, rhs) = self.lists.split_at_mut(from);
+            (&mut lhs[seg], &mut rhs[0])
+        } else {
+            let (lhs, rhs) = self.lists.split_at_mut(seg);
+            (&mut rhs[0], &mut lhs[from])
+        };
+        to.transfer_to_front(from, node);
+        entry_mut(node).seg = seg;
+    }
+
+    // cond: node belongs to self
+    pub(crate) unsafe fn remove(&mut self, node: Link<K, V>) -> (K, Option<V>) {
+        let seg = entry(node).seg;
+        self.index.remove(KeyWrapper::from_ref(&entry(node).key));
+        let entry = self.lists[seg].unlink_node(node);
+        (entry.key, entry.value)
+    }
+
+    pub(crate) fn back(&self, seg: usize) -> Option<Link<K, V>> {
+        self.lists[seg].back_node()
+    }
+
+    pub(crate) fn pop_back(&mut self, seg: usize) -> Option<(K, Option<V>)> {
+        let node = self.back(seg)?;
+        Some(unsafe { self.remove(node) })
+    }
+
+    // moves the least recently used entry of a segment to the front of a ghost segment
+    // and returns its value
+    pub(crate) fn demote_back(&mut self, seg: usize, ghost: usize) -> Option<V> {
+        let node = self.back(seg)?;
+        unsafe {
+            self.move_to_front(node, ghost);
+            entry_mut(node).value.take()
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.index.clear();
+        for list in &mut self.lists {
+            list.clear();
+        }
+    }
+}
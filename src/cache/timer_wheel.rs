@@ -0,0 +1,269 @@
+use crate::linear_list::linked_list::{LinkedList, Node};
+
+use std::cmp;
+use std::mem;
+use std::ptr::NonNull;
+use std::time::Duration;
+
+pub(crate) struct Timer<T> {
+    pub(crate) deadline: Duration,
+    pub(crate) value: T,
+    tick: u64,
+    list: usize,
+}
+
+pub(crate) type TimerLink<T> = NonNull<Node<Timer<T>>>;
+
+// Hierarchical timing wheel (Varghese and Lauck).
+// Level l has `slots` slots of slots^l ticks each, and every slot is a LinkedList of timers.
+// When the wheel reaches the start of a slot, the timers of the slot are relinked
+// into the lower levels, and the timers of the current slot of level 0 expire.
+// Timers beyond the range of the top level wait in it and are placed again on each pass.
+pub struct TimerWheel<T> {
+    // levels * slots lists, then the list of expired timers
+    lists: Vec<LinkedList<Timer<T>>>,
+    tick: Duration,
+    slots: usize,
+    levels: usize,
+    // the number of ticks processed
+    elapsed: u64,
+    // the number of timers in each level
+    pending: Vec<usize>,
+}
+
+impl<T> TimerWheel<T> {
+    // panics if tick is zero, slots is less than 2 or levels is 0
+    pub fn new(tick: Duration, slots: usize, levels: usize) -> Self {
+        assert!(!tick.is_zero(), "timer wheel needs a tick");
+        assert!(slots >= 2, "timer wheel needs 2 slots per level");
+        assert!(levels >= 1, "timer wheel needs a level");
+        Self {
+            lists: (0..=slots * levels).map(|_| LinkedList::new()).collect(),
+            tick,
+            slots,
+            levels,
+            elapsed: 0,
+            pending: vec![0; levels],
+        }
+    }
+
+    pub fn tick(&self) -> Duration {
+        self.tick
+    }
+
+    // the time up to which the wheel has advanced, rounded down to a tick
+    pub fn elapsed(&self) -> Duration {
+        let nanos = self.tick.as_nanos() * self.elapsed as u128;
+        Duration::new(
+            (nanos / 1_000_000_000) as u64,
+            (nanos % 1_000_000_000) as u32,
+        )
+    }
+
+    // the number of pending and expired timers
+    pub fn len(&self) -> usize {
+        self.pending.iter().sum::<usize>() + self.lists[self.expired_list()].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        for list in &mut self.lists {
+            list.clear();
+        }
+        self.pending.fill(0);
+    }
+
+    fn expired_list(&self) -> usize {
+        self.lists.len() - 1
+    }
+
+    // counts a timer in or out of the level of a list, the expired list is past the top level
+    fn count(&mut self, list: usize, added: bool) {
+        if let Some(pending) = self.pending.get_mut(list / self.slots) {
+            if added {
+                *pending += 1;
+            } else {
+                *pending -= 1;
+            }
+        }
+    }
+
+    fn span(&self, level: usize) -> u64 {
+        (self.slots as u64).saturating_pow(level as u32)
+    }
+
+    // the first tick at or after the deadline
+    fn tick_of(&self, deadline: Duration) -> u64 {
+        let ticks = deadline.as_nanos().div_ceil(self.tick.as_nanos());
+        cmp::min(ticks, u64::MAX as u128) as u64
+    }
+
+    // the list of a timer due at tick, or the expired list
+    fn place(&self, tick: u64) -> usize {
+        if tick <= self.elapsed {
+            return self.expired_list();
+        }
+        // the lowest level in whose current rotation the tick falls
+        let mut level = 0;
+        while level + 1 < self.levels {
+            let span = self.span(level + 1);
+            if tick / span == self.elapsed / span {
+                break;
+            }
+            level += 1;
+        }
+        level * self.slots + (tick / self.span(level) % self.slots as u64) as usize
+    }
+
+    pub(crate) fn insert_node(&mut self, deadline: Duration, value: T) -> TimerLink<T> {
+        let tick = self.tick_of(deadline);
+        let list = self.place(tick);
+        self.count(list, true);
+        self.lists[list].push_front_node(Timer {
+            deadline,
+            value,
+            tick,
+            list,
+        })
+    }
+
+    // a deadline which has passed expires on the next advance
+    pub fn insert(&mut self, deadline: Duration, value: T) {
+        self.insert_node(deadline, value);
+    }
+
+    // cond: node belongs to self
+    pub(crate) unsafe fn remove_node(&mut self, node: TimerLink<T>) -> Timer<T> {
+        let list = Node::elem(node).list;
+        self.count(list, false);
+        self.lists[list].unlink_node(node)
+    }
+
+    // moves the timers due at or before now to the expired list
+    // and returns the number of expired timers
+    pub fn advance(&mut self, now: Duration) -> usize {
+        let target = cmp::min(now.as_nanos() / self.tick.as_nanos(), u64::MAX as u128) as u64;
+        while self.elapsed < target {
+            // only the starts of the slots of the lowest level with timers can change anything
+            let next = match self.pending.iter().position(|&pending| pending > 0) {
+                Some(level) => {
+                    let span = self.span(level);
+                    (self.elapsed / span + 1).saturating_mul(span)
+                }
+                None => target,
+            };
+            self.elapsed = cmp::min(next, target);
+            if next > target {
+                break;
+            }
+            for level in (0..self.levels).rev() {
+                let span = self.span(level);
+                if !self.elapsed.is_multiple_of(span) {
+                    continue;
+                }
+                let slot = (self.elapsed / span % self.slots as u64) as usize;
+                let mut timers = mem::take(&mut self.lists[level * self.slots + slot]);
+                self.pending[level] -= timers.len();
+                while let Some(node) = timers.back_node() {
+                    unsafe {
+                        let timer = Node::elem_mut(node);
+                        timer.list = self.place(timer.tick);
+                        self.count(timer.list, true);
+                        self.lists[timer.list].transfer_to_front(&mut timers, node);
+                    }
+                }
+            }
+        }
+        self.lists[self.expired_list()].len()
+    }
+
+    pub(crate) fn expired_node(&self) -> Option<TimerLink<T>> {
+        self.lists[self.expired_list()].back_node()
+    }
+
+    // pops the expired timers in the order they were found
+    pub fn pop_expired(&mut self) -> Option<T> {
+        let node = self.expired_node()?;
+        Some(unsafe { self.remove_node(node) }.value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TimerWheel;
+    use crate::rng::Rng;
+
+    use std::time::Duration;
+
+    #[test]
+    fn test_timer_wheel() {
+        let ms = Duration::from_millis;
+        let mut wheel = TimerWheel::new(ms(10), 4, 2);
+        wheel.insert(ms(25), "a");
+        wheel.insert(ms(30), "b");
+        // beyond the 16 ticks of the wheel
+        wheel.insert(ms(1000), "c");
+        wheel.insert(ms(0), "d");
+        assert_eq!(wheel.len(), 4);
+
+        assert_eq!(wheel.advance(ms(0)), 1);
+        assert_eq!(wheel.pop_expired(), Some("d"));
+        // deadlines are rounded up to a tick
+        assert_eq!(wheel.advance(ms(29)), 0);
+        assert_eq!(wheel.advance(ms(30)), 2);
+        assert_eq!(wheel.pop_expired(), Some("a"));
+        assert_eq!(wheel.pop_expired(), Some("b"));
+        assert_eq!(wheel.pop_expired(), None);
+        assert_eq!(wheel.advance(ms(999)), 0);
+        assert_eq!(wheel.elapsed(), ms(990));
+        assert_eq!(wheel.advance(ms(1000)), 1);
+        assert_eq!(wheel.pop_expired(), Some("c"));
+        assert!(wheel.is_empty());
+
+        // an empty wheel skips ahead
+        wheel.advance(ms(10_000_000_000));
+        wheel.insert(ms(10_000_000_000 + 5), "e");
+        assert_eq!(wheel.advance(ms(10_000_000_000 + 9)), 0);
+        assert_eq!(wheel.advance(ms(10_000_000_000 + 10)), 1);
+        wheel.clear();
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn test_random_timers() {
+        let mut rng = Rng::new(45);
+        let mut wheel = TimerWheel::new(Duration::from_micros(100), 8, 3);
+        let mut pending = Vec::new();
+        let mut now = 0;
+        for id in 0..2000u64 {
+            // deadlines in nanoseconds, some of them past the range of the wheel
+            let deadline = now + rng.next_u64() % 80_000_000;
+            wheel.insert(Duration::from_nanos(deadline), (id, deadline));
+            pending.push((id, deadline));
+
+            now += rng.next_u64() % 100_000;
+            wheel.advance(Duration::from_nanos(now));
+            let mut fired = Vec::new();
+            while let Some((id, deadline)) = wheel.pop_expired() {
+                assert!(deadline <= now);
+                fired.push(id);
+            }
+            // a timer fires on the first advance past the tick of its deadline
+            let tick = 100_000;
+            let due = |deadline: u64| deadline.div_ceil(tick) <= now / tick;
+            let mut expected: Vec<_> = pending
+                .iter()
+                .filter(|&&(_, deadline)| due(deadline))
+                .map(|&(id, _)| id)
+                .collect();
+            pending.retain(|&(_, deadline)| !due(deadline));
+            fired.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(fired, expected);
+            assert_eq!(wheel.len(), pending.len());
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use super::clock::{Clock, SystemClock};
+use super::key_ref::{KeyRef, KeyWrapper};
+use super::timer_wheel::{TimerLink, TimerWheel};
+use crate::hash::HashMap;
+use crate::linear_list::linked_list::Node;
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::time::Duration;
+
+type Link<K, V> = TimerLink<(K, V)>;
+
+// Cache whose entries expire a time to live after they are inserted.
+// The entries are the timers of a TimerWheel and a hash index maps each key to its timer.
+// Expired entries are purged by the mutating operations as the wheel advances to the
+// time of the clock, so there is no background thread.
+pub struct TtlCache<K, V, C = SystemClock, S = RandomState> {
+    wheel: TimerWheel<(K, V)>,
+    index: HashMap<KeyRef<K>, Link<K, V>, S>,
+    clock: C,
+    ttl: Duration,
+}
+
+unsafe impl<K: Send, V: Send, C: Send, S: Send> Send for TtlCache<K, V, C, S> {}
+unsafe impl<K: Sync, V: Sync, C: Sync, S: Sync> Sync for TtlCache<K, V, C, S> {}
+
+impl<K: Hash + Eq, V> TtlCache<K, V, SystemClock, RandomState> {
+    pub fn new(ttl: Duration) -> Self {
+        Self::with_clock(ttl, SystemClock::new())
+    }
+}
+
+impl<K: Hash + Eq, V, C: Clock> TtlCache<K, V, C, RandomState> {
+    pub fn with_clock(ttl: Duration, clock: C) -> Self {
+        Self::with_clock_and_hasher(ttl, clock, RandomState::new())
+    }
+}
+
+impl<K: Hash + Eq, V, C: Clock, S: BuildHasher> TtlCache<K, V, C, S> {
+    // the wheel ticks every millisecond, with 4 levels of 64 slots
+    pub fn with_clock_and_hasher(ttl: Duration, clock: C, hasher: S) -> Self {
+        Self::with_wheel(ttl, Duration::from_millis(1), 64, 4, clock, hasher)
+    }
+
+    // panics if tick is zero, slots is less than 2 or levels is 0
+    pub fn with_wheel(
+        ttl: Duration,
+        tick: Duration,
+        slots: usize,
+        levels: usize,
+        clock: C,
+        hasher: S,
+    ) -> Self {
+        let mut wheel = TimerWheel::new(tick, slots, levels);
+        wheel.advance(clock.now());
+        Self {
+            wheel,
+            index: HashMap::with_hasher(hasher),
+            clock,
+            ttl,
+        }
+    }
+
+    // the time to live of the entries inserted by insert
+    pub fn default_ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    // the number of entries, including the expired ones which are not purged yet
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    // removes the expired entries and returns how many there were
+    pub fn purge_expired(&mut self) -> usize {
+        let now = self.clock.now();
+        self.wheel.advance(now);
+        let mut purged = 0;
+        while let Some(node) = self.wheel.expired_node() {
+            unsafe { self.remove_node(node) };
+            purged += 1;
+        }
+        purged
+    }
+
+    // cond: node belongs to self
+    unsafe fn remove_node(&mut self, node: Link<K, V>) -> V {
+        let (key, _) = &Node::elem(node).value;
+        self.index.remove(KeyWrapper::from_ref(key));
+        self.wheel.remove_node(node).value.1
+    }
+
+    // an entry whose deadline has passed is only purged once the wheel reaches the tick
+    // of the deadline, lookups skip it meanwhile
+    fn live<Q>(&self, key: &Q, now: Duration) -> Option<Link<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = *self.index.get(KeyWrapper::from_ref(key))?;
+        if unsafe { Node::elem(node).deadline } > now {
+            Some(node)
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_with_ttl(key, value, self.ttl)
+    }
+
+    // returns the old value if it has not expired
+    pub fn insert_with_ttl(&mut self, key: K, value: V, ttl: Duration) -> Option<V> {
+        self.purge_expired();
+        let now = self.clock.now();
+        let old = self
+            .index
+            .get(KeyWrapper::from_ref(&key))
+            .copied()
+            .and_then(|node| unsafe {
+                let live = Node::elem(node).deadline > now;
+                let value = self.remove_node(node);
+                if live {
+                    Some(value)
+                } else {
+                    None
+                }
+            });
+        // a time to live past the range of Duration never expires
+        let deadline = now.checked_add(ttl).unwrap_or(Duration::MAX);
+        let node = self.wheel.insert_node(deadline, (key, value));
+        let key = unsafe { &Node::elem(node).value.0 };
+        self.index.insert(KeyRef(key), node);
+        old
+    }
+
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.purge_expired();
+        let node = self.live(key, self.clock.now())?;
+        Some(unsafe { &mut Node::elem_mut(node).value.1 })
+    }
+
+    // does not purge
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.live(key, self.clock.now())?;
+        Some(unsafe { &Node::elem(node).value.1 })
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.peek(key).is_some()
+    }
+
+    // the time the entry has left to live
+    pub fn ttl<Q>(&self, key: &Q) -> Option<Duration>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = self.clock.now();
+        let node = self.live(key, now)?;
+        Some(unsafe { Node::elem(node).deadline } - now)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.purge_expired();
+        let node = self.live(key, self.clock.now())?;
+        Some(unsafe { self.remove_node(node) })
+    }
+
+    pub fn clear(&mut self) {
+        self.index.clear();
+        self.wheel.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TtlCache;
+    use crate::cache::ManualClock;
+
+    use std::collections::hash_map::RandomState;
+    use std::time::Duration;
+
+    #[test]
+    fn test_ttl_cache() {
+        let secs = Duration::from_secs;
+        let clock = ManualClock::new();
+        let mut cache = TtlCache::with_clock(secs(10), clock.clone());
+        assert_eq!(cache.insert("a", 1), None);
+        assert_eq!(cache.insert_with_ttl("b", 2, secs(5)), None);
+        assert_eq!(cache.insert_with_ttl("c", 3, secs(3600)), None);
+
+        clock.advance(secs(4));
+        assert_eq!(cache.get(&"b"), Some(&2));
+        assert_eq!(cache.ttl(&"b"), Some(secs(1)));
+        clock.advance(secs(1));
+        assert_eq!(cache.peek(&"b"), None);
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.purge_expired(), 1);
+        assert_eq!(cache.len(), 2);
+
+        // inserting again restarts the time to live
+        assert_eq!(cache.insert("a", 10), Some(1));
+        clock.advance(secs(9));
+        *cache.get_mut(&"a").unwrap() += 1;
+        clock.advance(secs(1));
+        assert!(!cache.contains(&"a"));
+        assert_eq!(cache.insert("a", 20), None);
+        assert_eq!(cache.remove(&"a"), Some(20));
+        assert_eq!(cache.remove(&"a"), None);
+
+        // past the range of the wheel
+        clock.advance(secs(3600 - 16));
+        assert_eq!(cache.get(&"c"), Some(&3));
+        clock.advance(secs(1));
+        assert_eq!(cache.get(&"c"), None);
+        assert!(cache.is_empty());
+
+        cache.insert_with_ttl("d", 4, Duration::ZERO);
+        assert_eq!(cache.get(&"d"), None);
+        cache.insert("e", 5);
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_unbounded_ttl() {
+        let clock = ManualClock::new();
+        clock.advance(Duration::from_secs(1));
+        let mut cache = TtlCache::with_clock(Duration::MAX, clock.clone());
+        assert_eq!(cache.insert("a", 1), None);
+        assert_eq!(cache.insert_with_ttl("b", 2, Duration::MAX), None);
+        assert_eq!(
+            cache.ttl(&"a"),
+            Some(Duration::MAX - Duration::from_secs(1))
+        );
+
+        clock.advance(Duration::from_secs(365 * 24 * 3600));
+        assert_eq!(cache.purge_expired(), 0);
+        assert_eq!(cache.get(&"a"), Some(&1));
+        assert_eq!(cache.insert("b", 3), Some(2));
+    }
+
+    #[test]
+    fn test_many_entries() {
+        let clock = ManualClock::new();
+        let mut cache = TtlCache::with_wheel(
+            Duration::from_millis(100),
+            Duration::from_millis(1),
+            16,
+            2,
+            clock.clone(),
+            RandomState::new(),
+        );
+        for i in 0..1000u64 {
+            cache.insert_with_ttl(i, i, Duration::from_millis(i));
+        }
+        for t in 0..999 {
+            clock.set(Duration::from_millis(t));
+            cache.purge_expired();
+            assert_eq!(cache.len() as u64, 1000 - t - 1);
+            assert_eq!(cache.peek(&t), None);
+            assert_eq!(cache.peek(&(t + 1)), Some(&(t + 1)));
+        }
+    }
+}
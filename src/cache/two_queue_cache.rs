@@ -0,0 +1,254 @@
+use super::policy::{CachePolicy, CacheStats};
+use super::segments::{entry, entry_mut, Segments};
+
+use std::borrow::Borrow;
+use std::cmp;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+
+// resident entries seen once, in FIFO order
+const A1_IN: usize = 0;
+// resident entries seen again after leaving A1_IN, in LRU order
+const AM: usize = 1;
+// ghost keys which left A1_IN
+const A1_OUT: usize = 2;
+
+// Full 2Q cache (Johnson and Shasha).
+// New entries pass through the FIFO A1in, and only the keys requested again while they
+// are remembered in the ghost queue A1out are promoted to the LRU list Am,
+// so a scan of keys seen once cannot flush Am.
+pub struct TwoQueueCache<K, V, S = RandomState> {
+    segs: Segments<K, V, S, 3>,
+    capacity: usize,
+    kin: usize,
+    kout: usize,
+    stats: CacheStats,
+}
+
+impl<K: Hash + Eq, V> TwoQueueCache<K, V, RandomState> {
+    // panics if capacity is 0
+    pub fn new(capacity: usize) -> Self {
+        Self::with_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> TwoQueueCache<K, V, S> {
+    // A1in gets a quarter of the capacity and A1out remembers half of the capacity
+    // panics if capacity is 0
+    pub fn with_hasher(capacity: usize, hasher: S) -> Self {
+        Self::with_queue_sizes(
+            capacity,
+            cmp::max(capacity / 4, 1),
+            cmp::max(capacity / 2, 1),
+            hasher,
+        )
+    }
+
+    // panics if capacity is 0
+    pub fn with_queue_sizes(capacity: usize, kin: usize, kout: usize, hasher: S) -> Self {
+        assert!(capacity > 0, "cache needs capacity");
+        Self {
+            segs: Segments::with_capacity_and_hasher(capacity + kout, hasher),
+            capacity,
+            kin,
+            kout,
+            stats: CacheStats::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.segs.len(A1_IN) + self.segs.len(AM)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    // a hit in A1in leaves the entry in place
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|v| &*v)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self
+            .segs
+            .find(key)
+            .filter(|&node| unsafe { entry(node).seg() } != A1_OUT);
+        self.stats.record(node.is_some());
+        let node = node?;
+        unsafe {
+            if entry(node).seg() == AM {
+                self.segs.move_to_front(node, AM);
+            }
+            entry_mut(node).value.as_mut()
+        }
+    }
+
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.segs.find(key)?;
+        unsafe { entry(node).value.as_ref() }
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.peek(key).is_some()
+    }
+
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(node) = self.segs.find(&key) {
+            unsafe {
+                match entry(node).seg() {
+                    A1_IN => {}
+                    AM => self.segs.move_to_front(node, AM),
+                    _ => {
+                        // the ghost is dropped first since reclaiming may trim A1out
+                        self.segs.remove(node);
+                        self.reclaim();
+                        self.segs.push_front(AM, key, Some(value));
+                        return None;
+                    }
+                }
+                return entry_mut(node).value.replace(value);
+            }
+        }
+        self.reclaim();
+        self.segs.push_front(A1_IN, key, Some(value));
+        None
+    }
+
+    // makes room for one entry if the cache is full
+    fn reclaim(&mut self) {
+        if self.len() < self.capacity {
+            return;
+        }
+        if self.segs.len(A1_IN) > self.kin || self.segs.len(AM) == 0 {
+            self.segs.demote_back(A1_IN, A1_OUT);
+            if self.segs.len(A1_OUT) > self.kout {
+                self.segs.pop_back(A1_OUT);
+            }
+        } else {
+            self.segs.pop_back(AM);
+        }
+        self.stats.evictions += 1;
+    }
+
+    // ghost keys are kept
+    pub fn pop<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.segs.find(key)?;
+        unsafe {
+            entry(node).value.as_ref()?;
+            self.segs.remove(node).1
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.segs.clear();
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> CachePolicy<K, V> for TwoQueueCache<K, V, S> {
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        self.get(key)
+    }
+
+    fn peek(&self, key: &K) -> Option<&V> {
+        self.peek(key)
+    }
+
+    fn put(&mut self, key: K, value: V) -> Option<V> {
+        self.put(key, value)
+    }
+
+    fn pop(&mut self, key: &K) -> Option<V> {
+        self.pop(key)
+    }
+
+    fn clear(&mut self) {
+        self.clear()
+    }
+
+    fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.reset_stats()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TwoQueueCache;
+
+    #[test]
+    fn test_two_queue_cache() {
+        // A1in holds 2 entries and A1out remembers 4 keys
+        let mut cache = TwoQueueCache::new(8);
+        for k in 0..8 {
+            cache.put(k, k * 10);
+        }
+        assert_eq!(cache.len(), 8);
+        assert_eq!(cache.get(&0), Some(&0));
+
+        // the oldest entry of A1in leaves for A1out
+        cache.put(8, 80);
+        assert!(!cache.contains(&0));
+        assert_eq!(cache.get(&0), None);
+
+        // and is promoted to Am when requested again
+        assert_eq!(cache.put(0, 1), None);
+        assert_eq!(cache.peek(&0), Some(&1));
+        for k in 100..200 {
+            cache.put(k, k);
+        }
+        assert_eq!(cache.get(&0), Some(&1));
+        assert_eq!(cache.len(), 8);
+
+        assert_eq!(cache.put(0, 2), Some(1));
+        assert_eq!(cache.pop(&199), Some(199));
+        assert_eq!(cache.pop(&100), None);
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+        assert_eq!(stats.evictions, 102);
+    }
+}
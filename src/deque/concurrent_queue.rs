@@ -0,0 +1,231 @@
+use crate::epoch;
+
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{AtomicIsize, AtomicPtr, AtomicUsize, Ordering};
+
+struct Node<T> {
+    // uninitialized in the dummy node at the head
+    elem: MaybeUninit<T>,
+    next: AtomicPtr<Node<T>>,
+}
+
+impl<T> Node<T> {
+    fn alloc(elem: MaybeUninit<T>) -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            elem,
+            next: AtomicPtr::default(),
+        }))
+    }
+}
+
+// Michael-Scott queue. The head always points to a dummy node whose successor holds the front,
+// popping makes that successor the new dummy. Old dummies are reclaimed through epochs.
+pub struct ConcurrentQueue<T> {
+    head: AtomicPtr<Node<T>>,
+    tail: AtomicPtr<Node<T>>,
+    len: AtomicIsize,
+}
+
+unsafe impl<T: Send> Send for ConcurrentQueue<T> {}
+unsafe impl<T: Send> Sync for ConcurrentQueue<T> {}
+
+impl<T> ConcurrentQueue<T> {
+    pub fn new() -> Self {
+        let dummy = Node::alloc(MaybeUninit::uninit());
+        Self {
+            head: AtomicPtr::new(dummy),
+            tail: AtomicPtr::new(dummy),
+            len: AtomicIsize::new(0),
+        }
+    }
+
+    // the length may be briefly off while other threads are pushing or popping
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed).max(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let _guard = epoch::pin();
+        let head = self.head.load(Ordering::Acquire);
+        unsafe { (*head).next.load(Ordering::Acquire).is_null() }
+    }
+
+    pub fn push(&self, elem: T) {
+        let node = Node::alloc(MaybeUninit::new(elem));
+        let _guard = epoch::pin();
+        loop {
+            let tail = self.tail.load(Ordering::Acquire);
+            let next = unsafe { (*tail).next.load(Ordering::Acquire) };
+            if !next.is_null() {
+                // the tail is lagging behind, helps the other push to finish
+                let _ =
+                    self.tail
+                        .compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+                continue;
+            }
+            let link = unsafe {
+                (*tail).next.compare_exchange(
+                    ptr::null_mut(),
+                    node,
+                    Ordering::Release,
+                    Ordering::Relaxed,
+                )
+            };
+            if link.is_ok() {
+                let _ =
+                    self.tail
+                        .compare_exchange(tail, node, Ordering::Release, Ordering::Relaxed);
+                break;
+            }
+        }
+        self.len.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+        loop {
+            let head = self.head.load(Ordering::Acquire);
+            let next = unsafe { (*head).next.load(Ordering::Acquire) };
+            if next.is_null() {
+                return None;
+            }
+            // the tail must not point to a node that is about to be retired
+            let tail = self.tail.load(Ordering::Acquire);
+            if tail == head {
+                let _ =
+                    self.tail
+                        .compare_exchange(tail, next, Ordering::Release, Ordering::Relaxed);
+            }
+            let unlink =
+                self.head
+                    .compare_exchange(head, next, Ordering::Acquire, Ordering::Relaxed);
+            if unlink.is_ok() {
+                self.len.fetch_sub(1, Ordering::Relaxed);
+                unsafe {
+                    // next is the new dummy, nobody else reads its element
+                    let elem = ptr::read((*next).elem.as_ptr());
+                    guard.defer_destroy(head);
+                    return Some(elem);
+                }
+            }
+        }
+    }
+}
+
+impl<T> Default for ConcurrentQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for ConcurrentQueue<T> {
+    fn drop(&mut self) {
+        let dummy = unsafe { Box::from_raw(*self.head.get_mut()) };
+        let mut curr = dummy.next.load(Ordering::Relaxed);
+        while !curr.is_null() {
+            let mut node = unsafe { Box::from_raw(curr) };
+            unsafe { ptr::drop_in_place(node.elem.as_mut_ptr()) };
+            curr = node.next.load(Ordering::Relaxed);
+        }
+    }
+}
+
+// ConcurrentQueue holding at most capacity elements
+pub struct BoundedConcurrentQueue<T> {
+    queue: ConcurrentQueue<T>,
+    // slots taken by pushes, released after the matching pops
+    reserved: AtomicUsize,
+    capacity: usize,
+}
+
+impl<T> BoundedConcurrentQueue<T> {
+    // panics if capacity is 0
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "bounded queue needs capacity");
+        Self {
+            queue: ConcurrentQueue::new(),
+            reserved: AtomicUsize::new(0),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.reserved.load(Ordering::Relaxed) >= self.capacity
+    }
+
+    // gives the element back if the queue is full
+    pub fn push(&self, elem: T) -> Result<(), T> {
+        let mut reserved = self.reserved.load(Ordering::Relaxed);
+        loop {
+            if reserved >= self.capacity {
+                return Err(elem);
+            }
+            match self.reserved.compare_exchange_weak(
+                reserved,
+                reserved + 1,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(curr) => reserved = curr,
+            }
+        }
+        self.queue.push(elem);
+        Ok(())
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let elem = self.queue.pop()?;
+        self.reserved.fetch_sub(1, Ordering::Relaxed);
+        Some(elem)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BoundedConcurrentQueue, ConcurrentQueue};
+
+    #[test]
+    fn test_concurrent_queue() {
+        let queue = ConcurrentQueue::new();
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop(), None);
+        for i in 0..5 {
+            queue.push(i.to_string());
+        }
+        assert_eq!(queue.len(), 5);
+        assert_eq!(queue.pop().unwrap(), "0");
+        assert_eq!(queue.pop().unwrap(), "1");
+        assert_eq!(queue.len(), 3);
+        assert!(!queue.is_empty());
+        // the rest is dropped with the queue
+    }
+
+    #[test]
+    fn test_bounded_concurrent_queue() {
+        let queue = BoundedConcurrentQueue::new(2);
+        assert_eq!(queue.push('a'), Ok(()));
+        assert_eq!(queue.push('b'), Ok(()));
+        assert!(queue.is_full());
+        assert_eq!(queue.push('c'), Err('c'));
+        assert_eq!(queue.pop(), Some('a'));
+        assert_eq!(queue.push('c'), Ok(()));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some('b'));
+        assert_eq!(queue.pop(), Some('c'));
+        assert_eq!(queue.pop(), None);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::linear_list::LinkedList;
+use crate::linear_list::{LinkedList, PoolStats};
 
 pub struct LinkedQueue<T>(LinkedList<T>);
 
@@ -7,6 +7,14 @@ impl<T> LinkedQueue<T> {
         Self(LinkedList::new())
     }
 
+    pub fn with_pool(capacity: usize) -> Self {
+        Self(LinkedList::with_pool(capacity))
+    }
+
+    pub fn pool_stats(&self) -> Option<PoolStats> {
+        self.0.pool_stats()
+    }
+
     pub fn clear(&mut self) {
         self.0.clear()
     }
@@ -35,3 +43,9 @@ impl<T> LinkedQueue<T> {
         self.0
     }
 }
+
+impl<T> Default for LinkedQueue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
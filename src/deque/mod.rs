@@ -1,4 +1,7 @@
+pub mod concurrent_queue;
 pub mod linked_queue;
-pub use linked_queue::LinkedQueue;
 pub mod ring_deque;
-pub use ring_deque::RingDeque;
\ No newline at end of file
+pub mod work_stealing;
+pub use concurrent_queue::{BoundedConcurrentQueue, ConcurrentQueue};
+pub use linked_queue::LinkedQueue;
+pub use ring_deque::RingDeque;
@@ -48,18 +48,18 @@ impl<T> RingDeque<T> {
         }
         if self.head <= self.tail {
             unsafe {
-                drop_in_place(std::slice::from_raw_parts_mut(
+                drop_in_place(std::ptr::slice_from_raw_parts_mut(
                     self.raw.offset(self.head),
                     self.len,
                 ));
             }
         } else if self.head > self.tail {
             unsafe {
-                drop_in_place(std::slice::from_raw_parts_mut(
+                drop_in_place(std::ptr::slice_from_raw_parts_mut(
                     self.raw.offset(self.head),
                     self.raw.cap - self.head,
                 ));
-                drop_in_place(std::slice::from_raw_parts_mut(
+                drop_in_place(std::ptr::slice_from_raw_parts_mut(
                     self.raw.arr.as_ptr(),
                     self.tail,
                 ));
@@ -0,0 +1,336 @@
+use crate::epoch;
+use crate::raw::RawArray;
+
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::mem::{self, MaybeUninit};
+use std::ptr;
+use std::sync::atomic::{fence, AtomicIsize, AtomicPtr, Ordering};
+use std::sync::Arc;
+
+const MIN_CAPACITY: usize = 16;
+const MAX_BATCH: usize = 32;
+
+// ring of slots, the elements in it are owned by Inner
+struct Buffer<T> {
+    raw: RawArray<T>,
+}
+
+impl<T> Buffer<T> {
+    fn alloc(capacity: usize) -> *mut Self {
+        let raw = unsafe { RawArray::alloc(capacity) };
+        Box::into_raw(Box::new(Self { raw }))
+    }
+
+    // cond: index is not negative
+    // wraps around like the indices of RingDeque
+    unsafe fn at(&self, index: isize) -> *mut T {
+        self.raw.offset(index as usize % self.raw.cap)
+    }
+}
+
+impl<T> Drop for Buffer<T> {
+    fn drop(&mut self) {
+        unsafe { self.raw.dealloc() }
+    }
+}
+
+struct Inner<T> {
+    // thieves take from the top, the worker pushes and pops at the bottom
+    top: AtomicIsize,
+    bottom: AtomicIsize,
+    buffer: AtomicPtr<Buffer<T>>,
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        let top = *self.top.get_mut();
+        let bottom = *self.bottom.get_mut();
+        let buffer = unsafe { Box::from_raw(*self.buffer.get_mut()) };
+        for index in top..bottom {
+            unsafe { ptr::drop_in_place(buffer.at(index)) };
+        }
+    }
+}
+
+// the result of a steal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Steal<T> {
+    Empty,
+    Success(T),
+    // lost a race with another thread, trying again may succeed
+    Retry,
+}
+
+impl<T> Steal<T> {
+    pub fn success(self) -> Option<T> {
+        match self {
+            Steal::Success(elem) => Some(elem),
+            _ => None,
+        }
+    }
+}
+
+// The owner side of a Chase-Lev deque. It pushes and pops at the bottom in LIFO order,
+// while any number of stealers take the oldest elements from the top.
+pub struct Worker<T> {
+    inner: Arc<Inner<T>>,
+    // only the worker replaces the buffer
+    buffer: Cell<*mut Buffer<T>>,
+    _marker: PhantomData<T>,
+}
+
+unsafe impl<T: Send> Send for Worker<T> {}
+
+impl<T> Worker<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(MIN_CAPACITY)
+    }
+
+    // the buffer still grows when it is full
+    pub fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(MIN_CAPACITY).next_power_of_two();
+        let buffer = Buffer::alloc(capacity);
+        Self {
+            inner: Arc::new(Inner {
+                top: AtomicIsize::new(0),
+                bottom: AtomicIsize::new(0),
+                buffer: AtomicPtr::new(buffer),
+            }),
+            buffer: Cell::new(buffer),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn stealer(&self) -> Stealer<T> {
+        Stealer {
+            inner: self.inner.clone(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        let bottom = self.inner.bottom.load(Ordering::Relaxed);
+        let top = self.inner.top.load(Ordering::Relaxed);
+        (bottom - top).max(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        unsafe { (*self.buffer.get()).raw.cap }
+    }
+
+    // doubles the buffer, thieves may still read from the old one until they unpin
+    fn grow(&self, top: isize, bottom: isize) {
+        let old = self.buffer.get();
+        let new = Buffer::alloc(self.capacity() * 2);
+        unsafe {
+            for index in top..bottom {
+                ptr::copy_nonoverlapping((*old).at(index), (*new).at(index), 1);
+            }
+        }
+        let guard = epoch::pin();
+        self.buffer.set(new);
+        self.inner.buffer.store(new, Ordering::Release);
+        unsafe { guard.defer_destroy(old) };
+    }
+
+    pub fn push(&self, elem: T) {
+        let bottom = self.inner.bottom.load(Ordering::Relaxed);
+        let top = self.inner.top.load(Ordering::Acquire);
+        if bottom - top >= self.capacity() as isize {
+            self.grow(top, bottom);
+        }
+        unsafe { (*self.buffer.get()).at(bottom).write(elem) };
+        fence(Ordering::Release);
+        self.inner.bottom.store(bottom + 1, Ordering::Relaxed);
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let bottom = self.inner.bottom.load(Ordering::Relaxed) - 1;
+        self.inner.bottom.store(bottom, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+        let top = self.inner.top.load(Ordering::Relaxed);
+
+        if top > bottom {
+            self.inner.bottom.store(bottom + 1, Ordering::Relaxed);
+            return None;
+        }
+        let elem = unsafe { (*self.buffer.get()).at(bottom).read() };
+        if top == bottom {
+            // the last element, thieves may race for it
+            let won = self
+                .inner
+                .top
+                .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok();
+            self.inner.bottom.store(bottom + 1, Ordering::Relaxed);
+            if !won {
+                mem::forget(elem);
+                return None;
+            }
+        }
+        Some(elem)
+    }
+}
+
+impl<T> Default for Worker<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The thief side of a Chase-Lev deque, it can be cloned and shared between threads.
+pub struct Stealer<T> {
+    inner: Arc<Inner<T>>,
+}
+
+unsafe impl<T: Send> Send for Stealer<T> {}
+unsafe impl<T: Send> Sync for Stealer<T> {}
+
+impl<T> Stealer<T> {
+    pub fn len(&self) -> usize {
+        let top = self.inner.top.load(Ordering::Relaxed);
+        let bottom = self.inner.bottom.load(Ordering::Relaxed);
+        (bottom - top).max(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // takes the oldest element
+    pub fn steal(&self) -> Steal<T> {
+        let top = self.inner.top.load(Ordering::Acquire);
+        fence(Ordering::SeqCst);
+        let bottom = self.inner.bottom.load(Ordering::Acquire);
+        if top >= bottom {
+            return Steal::Empty;
+        }
+
+        let _guard = epoch::pin();
+        let buffer = self.inner.buffer.load(Ordering::Acquire);
+        // the slot may be overwritten once top moves on, so the element is only
+        // assumed to be valid after winning the race for it
+        let elem = unsafe { ptr::read_volatile((*buffer).at(top) as *const MaybeUninit<T>) };
+        let won = self
+            .inner
+            .top
+            .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+            .is_ok();
+        if won {
+            Steal::Success(unsafe { elem.assume_init() })
+        } else {
+            Steal::Retry
+        }
+    }
+
+    // Moves about half of the elements into dest and returns how many were moved.
+    // The elements are taken one by one, since the worker pops from the other end without synchronizing.
+    pub fn steal_batch(&self, dest: &Worker<T>) -> Steal<usize> {
+        let batch = self.len().div_ceil(2).clamp(1, MAX_BATCH);
+        let mut stolen = 0;
+        while stolen < batch {
+            match self.steal() {
+                Steal::Success(elem) => dest.push(elem),
+                _ if stolen > 0 => break,
+                Steal::Empty => return Steal::Empty,
+                Steal::Retry => return Steal::Retry,
+            }
+            stolen += 1;
+        }
+        Steal::Success(stolen)
+    }
+}
+
+impl<T> Clone for Stealer<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Steal, Worker};
+
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_work_stealing() {
+        let worker = <Worker<String>>::with_capacity(2);
+        let stealer = worker.stealer();
+        assert_eq!(worker.capacity(), 16);
+        assert_eq!(worker.pop(), None);
+        assert_eq!(stealer.steal(), Steal::Empty);
+
+        for i in 0..40 {
+            worker.push(i.to_string());
+        }
+        assert_eq!(worker.capacity(), 64);
+        assert_eq!(stealer.len(), 40);
+        assert_eq!(worker.pop().unwrap(), "39");
+        assert_eq!(stealer.steal().success().unwrap(), "0");
+        assert_eq!(stealer.clone().steal().success().unwrap(), "1");
+
+        let other = Worker::new();
+        assert_eq!(stealer.steal_batch(&other), Steal::Success(19));
+        assert_eq!(other.pop().unwrap(), "20");
+        assert_eq!(other.stealer().steal().success().unwrap(), "2");
+        assert_eq!(worker.len(), 18);
+        // the rest is dropped with the deque
+    }
+
+    #[test]
+    fn test_stealers() {
+        const COUNT: usize = 100_000;
+        let worker = Worker::new();
+        let done = Arc::new(AtomicBool::new(false));
+
+        let thieves: Vec<_> = (0..4)
+            .map(|_| {
+                let (stealer, done) = (worker.stealer(), done.clone());
+                thread::spawn(move || {
+                    let local = Worker::new();
+                    let mut seen = Vec::new();
+                    loop {
+                        match stealer.steal_batch(&local) {
+                            Steal::Success(_) => {
+                                while let Some(elem) = local.pop() {
+                                    seen.push(elem);
+                                }
+                            }
+                            Steal::Retry => {}
+                            Steal::Empty if done.load(Ordering::SeqCst) => break,
+                            Steal::Empty => thread::yield_now(),
+                        }
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        let mut seen = Vec::new();
+        for i in 0..COUNT {
+            worker.push(i);
+            if i % 3 == 0 {
+                seen.extend(worker.pop());
+            }
+        }
+        while let Some(elem) = worker.pop() {
+            seen.push(elem);
+        }
+        done.store(true, Ordering::SeqCst);
+
+        for thief in thieves {
+            seen.extend(thief.join().unwrap());
+        }
+        seen.sort_unstable();
+        assert!(seen.into_iter().eq(0..COUNT));
+    }
+}
@@ -0,0 +1,249 @@
+use std::cell::{Cell, RefCell};
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+
+// Epoch based memory reclamation for the lock-free structures.
+//
+// A pinned thread announces the global epoch it has observed. The global epoch only advances
+// when every pinned thread has observed the current one, so garbage retired in epoch e
+// can not be reached by anyone once the global epoch is e + 2.
+
+const COLLECT_THRESHOLD: usize = 64;
+
+static EPOCH: AtomicUsize = AtomicUsize::new(0);
+
+// participants are never freed, a new thread reuses the record of an exited one
+static LOCALS: AtomicPtr<Local> = AtomicPtr::new(ptr::null_mut());
+
+// garbage left behind by exited threads
+static ORPHANS: Mutex<Vec<(usize, Deferred)>> = Mutex::new(Vec::new());
+
+struct Local {
+    // (epoch << 1) | 1 while pinned, 0 otherwise
+    state: AtomicUsize,
+    in_use: AtomicBool,
+    next: *mut Local,
+}
+
+impl Local {
+    fn acquire() -> &'static Local {
+        let mut curr = LOCALS.load(Ordering::Acquire);
+        while let Some(local) = unsafe { curr.as_ref() } {
+            let acquired =
+                local
+                    .in_use
+                    .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed);
+            if acquired.is_ok() {
+                return local;
+            }
+            curr = local.next;
+        }
+
+        let local = Box::into_raw(Box::new(Local {
+            state: AtomicUsize::new(0),
+            in_use: AtomicBool::new(true),
+            next: ptr::null_mut(),
+        }));
+        let mut head = LOCALS.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*local).next = head };
+            match LOCALS.compare_exchange_weak(head, local, Ordering::Release, Ordering::Relaxed) {
+                Ok(_) => return unsafe { &*local },
+                Err(curr) => head = curr,
+            }
+        }
+    }
+}
+
+struct Deferred {
+    ptr: *mut u8,
+    destroy: unsafe fn(*mut u8),
+}
+
+// the structures only retire values that may be sent across threads
+unsafe impl Send for Deferred {}
+
+impl Deferred {
+    fn run(self) {
+        unsafe { (self.destroy)(self.ptr) }
+    }
+}
+
+unsafe fn destroy_box<T>(ptr: *mut u8) {
+    drop(Box::from_raw(ptr as *mut T))
+}
+
+struct Handle {
+    local: &'static Local,
+    pins: Cell<usize>,
+    bag: RefCell<Vec<(usize, Deferred)>>,
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        let bag = mem::take(self.bag.get_mut());
+        ORPHANS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend(bag);
+        self.local.state.store(0, Ordering::Release);
+        self.local.in_use.store(false, Ordering::Release);
+    }
+}
+
+thread_local! {
+    static HANDLE: Handle = Handle {
+        local: Local::acquire(),
+        pins: Cell::new(0),
+        bag: RefCell::new(Vec::new()),
+    };
+}
+
+fn try_advance() {
+    let epoch = EPOCH.load(Ordering::Relaxed);
+    fence(Ordering::SeqCst);
+
+    let mut curr = LOCALS.load(Ordering::Acquire);
+    while let Some(local) = unsafe { curr.as_ref() } {
+        let state = local.state.load(Ordering::Relaxed);
+        if state & 1 == 1 && state >> 1 != epoch {
+            return;
+        }
+        curr = local.next;
+    }
+
+    fence(Ordering::Acquire);
+    let _ = EPOCH.compare_exchange(epoch, epoch + 1, Ordering::Release, Ordering::Relaxed);
+}
+
+// Keeps the current thread pinned. Pointers loaded from a lock-free structure
+// stay valid until the guard is dropped.
+pub(crate) struct Guard {
+    handle: *const Handle,
+}
+
+pub(crate) fn pin() -> Guard {
+    HANDLE.with(|handle| {
+        let pins = handle.pins.get();
+        if pins == 0 {
+            let epoch = EPOCH.load(Ordering::Relaxed);
+            handle.local.state.store(epoch << 1 | 1, Ordering::Relaxed);
+            fence(Ordering::SeqCst);
+        }
+        handle.pins.set(pins + 1);
+        Guard { handle }
+    })
+}
+
+impl Guard {
+    // cond: ptr comes from Box::into_raw and is no longer reachable by threads pinned later
+    pub(crate) unsafe fn defer_destroy<T>(&self, ptr: *mut T) {
+        let handle = &*self.handle;
+        let epoch = EPOCH.load(Ordering::SeqCst);
+        let deferred = Deferred {
+            ptr: ptr as *mut u8,
+            destroy: destroy_box::<T>,
+        };
+        let len = {
+            let mut bag = handle.bag.borrow_mut();
+            bag.push((epoch, deferred));
+            bag.len()
+        };
+        if len >= COLLECT_THRESHOLD {
+            self.flush();
+        }
+    }
+
+    // tries to advance the epoch and destroys the garbage nobody can reach anymore
+    pub(crate) fn flush(&self) {
+        try_advance();
+        let epoch = EPOCH.load(Ordering::SeqCst);
+        let expired = |&(retired, _): &(usize, Deferred)| retired + 2 <= epoch;
+
+        let handle = unsafe { &*self.handle };
+        // the bag is ordered by epoch since the global epoch never goes back
+        let garbage: Vec<_> = {
+            let mut bag = handle.bag.borrow_mut();
+            let n = bag.iter().take_while(|item| expired(item)).count();
+            bag.drain(..n).collect()
+        };
+        garbage.into_iter().for_each(|(_, deferred)| deferred.run());
+
+        if let Ok(mut orphans) = ORPHANS.try_lock() {
+            let (garbage, rest): (Vec<_>, Vec<_>) = orphans.drain(..).partition(expired);
+            *orphans = rest;
+            drop(orphans);
+            garbage.into_iter().for_each(|(_, deferred)| deferred.run());
+        }
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let handle = unsafe { &*self.handle };
+        let pins = handle.pins.get() - 1;
+        handle.pins.set(pins);
+        if pins == 0 {
+            handle.local.state.store(0, Ordering::Release);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::pin;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    struct Counted(Arc<AtomicUsize>);
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn flush_until(dropped: &AtomicUsize, expected: usize) {
+        for _ in 0..100_000 {
+            if dropped.load(Ordering::SeqCst) == expected {
+                return;
+            }
+            pin().flush();
+            std::thread::yield_now();
+        }
+        panic!("garbage was not reclaimed")
+    }
+
+    #[test]
+    fn test_epoch() {
+        let dropped = Arc::new(AtomicUsize::new(0));
+        {
+            let guard = pin();
+            let nested = pin();
+            for _ in 0..10 {
+                let ptr = Box::into_raw(Box::new(Counted(dropped.clone())));
+                unsafe { nested.defer_destroy(ptr) };
+            }
+            drop(nested);
+            // the thread is still pinned, so the epoch can not move far enough
+            guard.flush();
+            guard.flush();
+            assert_eq!(dropped.load(Ordering::SeqCst), 0);
+        }
+        flush_until(&dropped, 10);
+
+        let handle = {
+            let dropped = dropped.clone();
+            std::thread::spawn(move || {
+                let guard = pin();
+                let ptr = Box::into_raw(Box::new(Counted(dropped)));
+                unsafe { guard.defer_destroy(ptr) };
+            })
+        };
+        handle.join().unwrap();
+        flush_until(&dropped, 11);
+    }
+}
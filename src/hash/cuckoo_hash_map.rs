@@ -0,0 +1,831 @@
+use crate::raw::RawArray;
+use crate::rng::Rng;
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::iter::{FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+
+// slots in a bucket
+const SLOTS: usize = 4;
+const MIN_BITS: u32 = 2;
+const MAX_TABLES: usize = 8;
+const DEFAULT_MAX_EVICTIONS: usize = 128;
+// entries the stash holds at most, so a lookup scans a bounded number of them
+const STASH_SIZE: usize = 4;
+// tries of a rehash to place the entries in the tables and the stash before it gives up
+const REHASH_TRIES: usize = 8;
+
+// one multiplicative hash function per table
+const MULTIPLIERS: [u64; MAX_TABLES] = [
+    0x9E37_79B9_7F4A_7C15,
+    0xC2B2_AE3D_27D4_EB4F,
+    0x1656_67B1_9E37_79F9,
+    0xD6E8_FEB8_6659_FD93,
+    0xFF51_AFD7_ED55_8CCD,
+    0xC4CE_B9FE_1A85_EC53,
+    0x94D0_49BB_1331_11EB,
+    0xBF58_4767_1CE4_E5B9,
+];
+
+// the number of entries the slots hold before growing, the load factor is 9/10
+fn usable(slots: usize) -> usize {
+    slots - slots / 10
+}
+
+struct Slot<K, V> {
+    hash: u64,
+    key: K,
+    value: V,
+}
+
+// The tables laid out one after another, an entry of table t lives in bucket(hash, t).
+// The hashes are kept in the slots, so picking new hash functions needs no hasher.
+// Up to STASH_SIZE entries which no hash functions could place, like keys sharing their
+// whole hash, wait in the stash, whose entries follow the slots in the indices.
+struct RawTables<K, V> {
+    slots: RawArray<Slot<K, V>>,
+    // bit s is set when slot s of the bucket is occupied
+    occupied: Box<[u8]>,
+    stash: Vec<Slot<K, V>>,
+    tables: usize,
+    // each table has 1 << bits buckets
+    bits: u32,
+    seed: u64,
+    // the number of entries in the slots
+    len: usize,
+}
+
+impl<K, V> RawTables<K, V> {
+    fn new(tables: usize, bits: u32, seed: u64) -> Self {
+        let buckets = tables << bits;
+        Self {
+            slots: unsafe { RawArray::alloc(buckets * SLOTS) },
+            occupied: vec![0; buckets].into_boxed_slice(),
+            stash: Vec::new(),
+            tables,
+            bits,
+            seed,
+            len: 0,
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.slots.cap
+    }
+
+    fn entries(&self) -> usize {
+        self.len + self.stash.len()
+    }
+
+    fn bucket(&self, hash: u64, table: usize) -> usize {
+        let h = (hash ^ self.seed).wrapping_mul(MULTIPLIERS[table]);
+        (table << self.bits) + (h >> (64 - self.bits)) as usize
+    }
+
+    fn is_occupied(&self, index: usize) -> bool {
+        self.occupied[index / SLOTS] & (1 << (index % SLOTS)) != 0
+    }
+
+    unsafe fn slot(&self, index: usize) -> *mut Slot<K, V> {
+        self.slots.offset(index)
+    }
+
+    // index is a slot or an entry of the stash
+    fn entry(&self, index: usize) -> &Slot<K, V> {
+        match index.checked_sub(self.capacity()) {
+            Some(i) => &self.stash[i],
+            None => unsafe { &*self.slot(index) },
+        }
+    }
+
+    fn entry_mut(&mut self, index: usize) -> &mut Slot<K, V> {
+        match index.checked_sub(self.capacity()) {
+            Some(i) => &mut self.stash[i],
+            None => unsafe { &mut *self.slot(index) },
+        }
+    }
+
+    // looks at no more than tables * SLOTS slots and STASH_SIZE entries of the stash
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        for table in 0..self.tables {
+            let bucket = self.bucket(hash, table);
+            let mut bits = self.occupied[bucket];
+            while bits != 0 {
+                let index = bucket * SLOTS + bits.trailing_zeros() as usize;
+                let slot = unsafe { &*self.slot(index) };
+                if slot.hash == hash && slot.key.borrow() == key {
+                    return Some(index);
+                }
+                bits &= bits - 1;
+            }
+        }
+        let stashed = self
+            .stash
+            .iter()
+            .position(|slot| slot.hash == hash && slot.key.borrow() == key)?;
+        Some(self.capacity() + stashed)
+    }
+
+    // puts the entry into a free slot of one of its buckets
+    fn try_place(&mut self, slot: Slot<K, V>) -> Result<usize, Slot<K, V>> {
+        for table in 0..self.tables {
+            let bucket = self.bucket(slot.hash, table);
+            let free = !self.occupied[bucket] & ((1 << SLOTS) - 1);
+            if free != 0 {
+                let offset = free.trailing_zeros() as usize;
+                self.occupied[bucket] |= 1 << offset;
+                self.len += 1;
+                let index = bucket * SLOTS + offset;
+                unsafe { self.slot(index).write(slot) };
+                return Ok(index);
+            }
+        }
+        Err(slot)
+    }
+
+    // Kicks random entries out of their buckets until every entry has a place.
+    // Returns the length of the eviction path, or the entry left homeless after max_evictions kicks.
+    fn insert(
+        &mut self,
+        slot: Slot<K, V>,
+        rng: &mut Rng,
+        max_evictions: usize,
+    ) -> Result<usize, Slot<K, V>> {
+        let mut homeless = slot;
+        let mut from = self.tables;
+        for evictions in 0..=max_evictions {
+            homeless = match self.try_place(homeless) {
+                Ok(_) => return Ok(evictions),
+                Err(slot) => slot,
+            };
+            if evictions == max_evictions {
+                break;
+            }
+            // the bucket the entry was kicked out of is full
+            let mut table = rng.next_u64() as usize % self.tables;
+            if table == from {
+                table = (table + 1) % self.tables;
+            }
+            let bucket = self.bucket(homeless.hash, table);
+            let index = bucket * SLOTS + rng.next_u64() as usize % SLOTS;
+            mem::swap(&mut homeless, unsafe { &mut *self.slot(index) });
+            from = table;
+        }
+        Err(homeless)
+    }
+
+    fn remove_at(&mut self, index: usize) -> Slot<K, V> {
+        if let Some(i) = index.checked_sub(self.capacity()) {
+            return self.stash.swap_remove(i);
+        }
+        self.occupied[index / SLOTS] &= !(1 << (index % SLOTS));
+        self.len -= 1;
+        unsafe { self.slot(index).read() }
+    }
+
+    fn drain_into(&mut self, entries: &mut Vec<Slot<K, V>>) {
+        for index in 0..self.capacity() {
+            if self.is_occupied(index) {
+                entries.push(self.remove_at(index));
+            }
+        }
+        entries.append(&mut self.stash);
+    }
+
+    fn clear(&mut self) {
+        for index in 0..self.capacity() {
+            if self.is_occupied(index) {
+                unsafe { ptr::drop_in_place(self.slot(index)) };
+            }
+        }
+        self.occupied.iter_mut().for_each(|bits| *bits = 0);
+        self.len = 0;
+        self.stash.clear();
+    }
+}
+
+impl<K, V> Drop for RawTables<K, V> {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe { self.slots.dealloc() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CuckooStats {
+    pub len: usize,
+    // the number of slots over all tables
+    pub slots: usize,
+    // the number of entries in each table
+    pub per_table: Vec<usize>,
+    // the number of entries no hash functions could place, at most 4
+    pub stashed: usize,
+    // how many times the hash functions were replaced
+    pub rehashes: usize,
+    pub longest_eviction_path: usize,
+}
+
+impl CuckooStats {
+    pub fn load_factor(&self) -> f64 {
+        if self.slots == 0 {
+            0.0
+        } else {
+            self.len as f64 / self.slots as f64
+        }
+    }
+}
+
+// Cuckoo hash map. Every key has one bucket of SLOTS slots in each of the tables,
+// so a lookup probes a fixed number of slots. An insert that runs into a cycle of
+// evictions puts the homeless entry into a small stash, which lookups scan after the tables.
+// Once the stash is full it picks new hash functions and rebuilds the tables, growing them
+// if that fails, and panics if no tables place the entries, as with a hasher giving many keys
+// the same hash.
+pub struct CuckooHashMap<K, V, S = RandomState> {
+    tables: RawTables<K, V>,
+    hasher: S,
+    rng: Rng,
+    max_evictions: usize,
+    rehashes: usize,
+    longest_eviction_path: usize,
+}
+
+unsafe impl<K: Send, V: Send, S: Send> Send for CuckooHashMap<K, V, S> {}
+unsafe impl<K: Sync, V: Sync, S: Sync> Sync for CuckooHashMap<K, V, S> {}
+
+impl<K, V> CuckooHashMap<K, V, RandomState> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_tables(2, capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> CuckooHashMap<K, V, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        Self::with_tables(2, 0, hasher)
+    }
+
+    // panics if tables is not in 2..=8
+    pub fn with_tables(tables: usize, capacity: usize, hasher: S) -> Self {
+        Self::with_seed(tables, capacity, hasher, Rng::from_entropy().next_u64())
+    }
+
+    // the seed determines the hash functions of the tables and the entries they evict,
+    // panics if tables is not in 2..=8
+    pub fn with_seed(tables: usize, capacity: usize, hasher: S, seed: u64) -> Self {
+        assert!(
+            (2..=MAX_TABLES).contains(&tables),
+            "cuckoo hashing needs 2 to 8 tables"
+        );
+        let mut bits = MIN_BITS;
+        while usable((tables << bits) * SLOTS) < capacity {
+            bits += 1;
+        }
+        let mut rng = Rng::new(seed);
+        Self {
+            tables: RawTables::new(tables, bits, rng.next_u64()),
+            hasher,
+            rng,
+            max_evictions: DEFAULT_MAX_EVICTIONS,
+            rehashes: 0,
+            longest_eviction_path: 0,
+        }
+    }
+
+    // Bounds the entries kicked out by one insert before it rehashes.
+    // A shorter path bounds the insert time but rehashes more often.
+    pub fn set_max_evictions(&mut self, max_evictions: usize) {
+        self.max_evictions = max_evictions;
+    }
+
+    pub fn tables(&self) -> usize {
+        self.tables.tables
+    }
+
+    pub fn len(&self) -> usize {
+        self.tables.entries()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // the number of entries held without growing
+    pub fn capacity(&self) -> usize {
+        usable(self.tables.capacity())
+    }
+
+    pub fn clear(&mut self) {
+        self.tables.clear()
+    }
+
+    pub fn stats(&self) -> CuckooStats {
+        let buckets = 1 << self.tables.bits;
+        let per_table = self
+            .tables
+            .occupied
+            .chunks(buckets)
+            .map(|table| table.iter().map(|bits| bits.count_ones() as usize).sum())
+            .collect();
+        CuckooStats {
+            len: self.len(),
+            slots: self.tables.capacity(),
+            per_table,
+            stashed: self.tables.stash.len(),
+            rehashes: self.rehashes,
+            longest_eviction_path: self.longest_eviction_path,
+        }
+    }
+
+    // Builds the tables again with new hash functions until the entries fit into them
+    // and the stash, growing them after a few failed tries while they are at least half full.
+    // Panics after REHASH_TRIES.
+    fn rehash(&mut self, homeless: Slot<K, V>, grow: bool) {
+        let mut entries = Vec::with_capacity(self.len() + 1);
+        entries.push(homeless);
+        self.tables.drain_into(&mut entries);
+        let mut bits = self.tables.bits + grow as u32;
+        for tries in 1..=REHASH_TRIES {
+            self.rehashes += 1;
+            let mut tables = RawTables::new(self.tables(), bits, self.rng.next_u64());
+            while let Some(entry) = entries.pop() {
+                if let Err(entry) = tables.insert(entry, &mut self.rng, self.max_evictions) {
+                    if tables.stash.len() == STASH_SIZE {
+                        entries.push(entry);
+                        break;
+                    }
+                    tables.stash.push(entry);
+                }
+            }
+            if entries.is_empty() {
+                self.tables = tables;
+                return;
+            }
+            tables.drain_into(&mut entries);
+            if tries % 4 == 0 && 2 * entries.len() >= tables.capacity() {
+                bits += 1;
+            }
+        }
+        panic!("cuckoo hashing cannot place the entries")
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> CuckooHashMap<K, V, S> {
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hasher.hash_one(&key);
+        if let Some(index) = self.tables.find(hash, &key) {
+            let slot = self.tables.entry_mut(index);
+            return Some(mem::replace(&mut slot.value, value));
+        }
+        let slot = Slot { hash, key, value };
+        if self.len() >= self.capacity() {
+            self.rehash(slot, true);
+            return None;
+        }
+        match self.tables.insert(slot, &mut self.rng, self.max_evictions) {
+            Ok(path) => {
+                self.longest_eviction_path = self.longest_eviction_path.max(path);
+            }
+            Err(homeless) if self.tables.stash.len() < STASH_SIZE => {
+                self.tables.stash.push(homeless);
+            }
+            Err(homeless) => self.rehash(homeless, false),
+        }
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, v)| v)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.tables.find(self.hasher.hash_one(key), key)?;
+        let slot = self.tables.entry(index);
+        Some((&slot.key, &slot.value))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.tables.find(self.hasher.hash_one(key), key)?;
+        Some(&mut self.tables.entry_mut(index).value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.tables.find(self.hasher.hash_one(key), key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.tables.find(self.hasher.hash_one(key), key)?;
+        let slot = self.tables.remove_at(index);
+        Some((slot.key, slot.value))
+    }
+}
+
+impl<K, V, S> CuckooHashMap<K, V, S> {
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        // the stash is only read through
+        let stash = self.tables.stash.as_ptr() as *mut _;
+        Iter {
+            iter: RawIter::new(&self.tables, stash),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        let stash = self.tables.stash.as_mut_ptr();
+        IterMut {
+            iter: RawIter::new(&self.tables, stash),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.iter().map(|(_, v)| v)
+    }
+}
+
+impl<K, V, S: Default> Default for CuckooHashMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for CuckooHashMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for CuckooHashMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        map.extend(iter);
+        map
+    }
+}
+
+struct RawIter<K, V> {
+    slots: RawArray<Slot<K, V>>,
+    occupied: *const [u8],
+    stash: *mut Slot<K, V>,
+    index: usize,
+    // the entries left in the slots and in the stash
+    in_slots: usize,
+    len: usize,
+}
+
+impl<K, V> RawIter<K, V> {
+    // stash points to the entries of the stash of tables
+    fn new(tables: &RawTables<K, V>, stash: *mut Slot<K, V>) -> Self {
+        Self {
+            slots: unsafe { tables.slots.shadow_clone() },
+            occupied: &*tables.occupied,
+            stash,
+            index: 0,
+            in_slots: tables.len,
+            len: tables.entries(),
+        }
+    }
+
+    fn next(&mut self) -> Option<*mut Slot<K, V>> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        if self.in_slots == 0 {
+            return Some(unsafe { self.stash.add(self.len) });
+        }
+        self.in_slots -= 1;
+        loop {
+            let index = self.index;
+            self.index += 1;
+            let bits = unsafe { (*self.occupied)[index / SLOTS] };
+            if bits & (1 << (index % SLOTS)) != 0 {
+                return Some(unsafe { self.slots.offset(index) });
+            }
+        }
+    }
+}
+
+// ------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, K, V> {
+    iter: RawIter<K, V>,
+    _marker: PhantomData<&'a CuckooHashMap<K, V>>,
+}
+
+unsafe impl<K: Sync, V: Sync> Send for Iter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Iter<'_, K, V> {}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|slot| unsafe { (&(*slot).key, &(*slot).value) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.iter.len, Some(self.iter.len))
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a CuckooHashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+// end: Iter
+// ------------------------------------
+
+// ------------------------------------
+// begin: IterMut
+
+pub struct IterMut<'a, K, V> {
+    iter: RawIter<K, V>,
+    _marker: PhantomData<&'a mut CuckooHashMap<K, V>>,
+}
+
+unsafe impl<K: Sync, V: Send> Send for IterMut<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for IterMut<'_, K, V> {}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|slot| unsafe { (&(*slot).key, &mut (*slot).value) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.iter.len, Some(self.iter.len))
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut CuckooHashMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+// end: IterMut
+// ------------------------------------
+
+// ------------------------------------
+// begin: IntoIter
+
+pub struct IntoIter<K, V> {
+    tables: RawTables<K, V>,
+    index: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<(K, V)> {
+        if self.tables.len == 0 {
+            let slot = self.tables.stash.pop()?;
+            return Some((slot.key, slot.value));
+        }
+        loop {
+            let index = self.index;
+            self.index += 1;
+            if self.tables.is_occupied(index) {
+                let slot = self.tables.remove_at(index);
+                return Some((slot.key, slot.value));
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.tables.entries();
+        (len, Some(len))
+    }
+}
+
+impl<K, V, S> IntoIterator for CuckooHashMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            tables: self.tables,
+            index: 0,
+        }
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+// end: IntoIter
+// ------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::{CuckooHashMap, STASH_SIZE};
+
+    use crate::rng::Rng;
+
+    use std::collections::hash_map::{DefaultHasher, RandomState};
+    use std::collections::BTreeMap;
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    // every key gets the same hash
+    #[derive(Default)]
+    struct Constant;
+
+    impl Hasher for Constant {
+        fn finish(&self) -> u64 {
+            7
+        }
+
+        fn write(&mut self, _: &[u8]) {}
+    }
+
+    #[test]
+    fn test_cuckoo_hash_map() {
+        let mut map = CuckooHashMap::new();
+        assert_eq!(map.tables(), 2);
+        assert_eq!(map.capacity(), 29);
+        assert_eq!(map.insert("a".to_string(), 1), None);
+        assert_eq!(map.insert("b".to_string(), 2), None);
+        assert_eq!(map.insert("a".to_string(), 3), Some(1));
+        assert_eq!(map.get("a"), Some(&3));
+        *map.get_mut("b").unwrap() += 10;
+        assert_eq!(map.remove_entry("b"), Some(("b".to_string(), 12)));
+        assert_eq!(map.remove("b"), None);
+        assert!(map.contains_key("a"));
+
+        map.extend((0..1000).map(|i| (i.to_string(), i)));
+        assert_eq!(map.len(), 1001);
+        assert!(map.capacity() >= 1001);
+        for i in 0..1000 {
+            assert_eq!(map.get(&i.to_string()), Some(&i));
+        }
+        for (_, v) in &mut map {
+            *v += 1;
+        }
+        assert_eq!(map.values().sum::<i32>(), 500_500 + 3 + 1);
+        let stats = map.stats();
+        assert_eq!(stats.per_table.iter().sum::<usize>(), 1001);
+        assert!(stats.load_factor() <= 0.9);
+        assert_eq!(map.into_iter().count(), 1001);
+    }
+
+    #[test]
+    fn test_rehash_on_cycle() {
+        let mut rng = Rng::new(41);
+        let mut map = CuckooHashMap::with_seed(3, 0, RandomState::new(), 41);
+        // no evictions at all, every full bucket set means new hash functions
+        map.set_max_evictions(0);
+        let mut expected = BTreeMap::new();
+        for _ in 0..20_000 {
+            let key = rng.next_u64() % 2000;
+            if rng.next_u64() % 3 == 1 {
+                assert_eq!(map.remove(&key), expected.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, key * 2), expected.insert(key, key * 2));
+            }
+        }
+        assert!(map.stats().rehashes > 0);
+        assert_eq!(map.stats().per_table.len(), 3);
+        let mut items: Vec<_> = map.iter().map(|(&k, &v)| (k, v)).collect();
+        items.sort_unstable();
+        assert!(items.into_iter().eq(expected.into_iter()));
+    }
+
+    #[test]
+    fn test_high_load() {
+        let mut map = CuckooHashMap::with_tables(2, 4000, RandomState::new());
+        let capacity = map.capacity();
+        for i in 0..capacity {
+            map.insert(i, ());
+        }
+        // the tables were filled to 90% without growing
+        let stats = map.stats();
+        assert_eq!(map.capacity(), capacity);
+        assert!(stats.load_factor() > 0.85);
+        assert!(stats.longest_eviction_path <= 128);
+        assert!((0..capacity).all(|i| map.contains_key(&i)));
+    }
+
+    #[test]
+    fn test_same_hash() {
+        // 2 buckets of SLOTS slots and the stash hold the keys
+        let mut map = CuckooHashMap::with_seed(2, 0, BuildHasherDefault::<Constant>::default(), 3);
+        for i in 0..12 {
+            assert_eq!(map.insert(i, i), None);
+            assert!(map.stats().stashed <= STASH_SIZE);
+        }
+        let stats = map.stats();
+        assert_eq!(stats.per_table.iter().sum::<usize>(), 8);
+        assert_eq!(stats.stashed, STASH_SIZE);
+        assert_eq!(map.len(), 12);
+        assert!((0..12).all(|i| map.get(&i) == Some(&i)));
+
+        assert_eq!(map.insert(5, 0), Some(5));
+        for i in (0..12).step_by(3) {
+            assert_eq!(map.remove(&i), Some(i));
+        }
+        // the removals made room for new keys with the same hash
+        for i in 12..16 {
+            assert_eq!(map.insert(i, i), None);
+            assert!(map.stats().stashed <= STASH_SIZE);
+        }
+        for (_, v) in &mut map {
+            *v += 1;
+        }
+        let mut items: Vec<_> = map.iter().map(|(&k, &v)| (k, v)).collect();
+        items.sort_unstable();
+        let expected: Vec<_> = (0..16)
+            .filter(|i| i % 3 != 0 || *i >= 12)
+            .map(|i| (i, if i == 5 { 1 } else { i + 1 }))
+            .collect();
+        assert_eq!(items, expected);
+        assert_eq!(map.into_iter().count(), expected.len());
+    }
+
+    #[test]
+    #[should_panic(expected = "cuckoo hashing cannot place the entries")]
+    fn test_too_many_same_hash() {
+        let mut map = CuckooHashMap::with_seed(2, 0, BuildHasherDefault::<Constant>::default(), 3);
+        for i in 0..13 {
+            map.insert(i, i);
+        }
+    }
+
+    #[test]
+    fn test_seed() {
+        // the same keys, hasher and seed give the same tables
+        let build = |seed| {
+            let hasher = BuildHasherDefault::<DefaultHasher>::default();
+            let mut map = CuckooHashMap::with_seed(2, 0, hasher, seed);
+            map.set_max_evictions(4);
+            map.extend((0..500).map(|i| (i, i)));
+            map.stats()
+        };
+        assert_eq!(build(5), build(5));
+        assert!(build(5).rehashes > 0);
+    }
+}
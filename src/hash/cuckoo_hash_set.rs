@@ -0,0 +1,216 @@
+use super::cuckoo_hash_map::{self, CuckooHashMap, CuckooStats};
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::iter::{FromIterator, FusedIterator};
+
+pub struct CuckooHashSet<T, S = RandomState>(CuckooHashMap<T, (), S>);
+
+impl<T> CuckooHashSet<T, RandomState> {
+    pub fn new() -> Self {
+        Self(CuckooHashMap::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(CuckooHashMap::with_capacity(capacity))
+    }
+}
+
+impl<T, S> CuckooHashSet<T, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        Self(CuckooHashMap::with_hasher(hasher))
+    }
+
+    // panics if tables is not in 2..=8
+    pub fn with_tables(tables: usize, capacity: usize, hasher: S) -> Self {
+        Self(CuckooHashMap::with_tables(tables, capacity, hasher))
+    }
+
+    // the seed determines the hash functions of the tables, panics if tables is not in 2..=8
+    pub fn with_seed(tables: usize, capacity: usize, hasher: S, seed: u64) -> Self {
+        Self(CuckooHashMap::with_seed(tables, capacity, hasher, seed))
+    }
+
+    pub fn set_max_evictions(&mut self, max_evictions: usize) {
+        self.0.set_max_evictions(max_evictions)
+    }
+
+    pub fn tables(&self) -> usize {
+        self.0.tables()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.0.capacity()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    pub fn stats(&self) -> CuckooStats {
+        self.0.stats()
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter(self.0.iter())
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> CuckooHashSet<T, S> {
+    // returns false if the set already contains value
+    pub fn insert(&mut self, value: T) -> bool {
+        self.0.insert(value, ()).is_none()
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.0.contains_key(value)
+    }
+
+    pub fn get<Q>(&self, value: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.0.get_key_value(value).map(|(value, _)| value)
+    }
+
+    pub fn remove<Q>(&mut self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.0.remove(value).is_some()
+    }
+
+    pub fn take<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.0.remove_entry(value).map(|(value, _)| value)
+    }
+}
+
+impl<T, S: Default> Default for CuckooHashSet<T, S> {
+    fn default() -> Self {
+        Self(CuckooHashMap::default())
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> Extend<T> for CuckooHashSet<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.0.extend(iter.into_iter().map(|value| (value, ())))
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher + Default> FromIterator<T> for CuckooHashSet<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self(iter.into_iter().map(|value| (value, ())).collect())
+    }
+}
+
+// ------------------------------------
+// begin: IntoIter
+
+pub struct IntoIter<T>(cuckoo_hash_map::IntoIter<T, ()>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.next().map(|(value, _)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<T, S> IntoIterator for CuckooHashSet<T, S> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self.0.into_iter())
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> FusedIterator for IntoIter<T> {}
+
+// end: IntoIter
+// ------------------------------------
+
+// ------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, T>(cuckoo_hash_map::Iter<'a, T, ()>);
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        self.0.next().map(|(value, _)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, T, S> IntoIterator for &'a CuckooHashSet<T, S> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> FusedIterator for Iter<'_, T> {}
+
+// end: Iter
+// ------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::CuckooHashSet;
+
+    use std::collections::hash_map::RandomState;
+
+    #[test]
+    fn test_cuckoo_hash_set() {
+        let mut set = CuckooHashSet::with_tables(4, 100, RandomState::new());
+        assert_eq!(set.tables(), 4);
+        assert!(set.insert("a".to_string()));
+        assert!(!set.insert("a".to_string()));
+        assert!(set.contains("a"));
+        assert_eq!(set.get("a").map(String::as_str), Some("a"));
+        assert_eq!(set.take("a"), Some("a".to_string()));
+        assert!(!set.remove("a"));
+
+        set.extend((0..500).map(|i| i.to_string()));
+        assert_eq!(set.len(), 500);
+        assert!(set.remove("7"));
+        let mut values: Vec<usize> = set.iter().map(|s| s.parse().unwrap()).collect();
+        values.sort_unstable();
+        assert!(values.into_iter().eq((0..500).filter(|&i| i != 7)));
+        assert_eq!(set.stats().per_table.len(), 4);
+
+        let set: CuckooHashSet<_> = (0..10).chain(0..10).collect();
+        assert_eq!(set.into_iter().count(), 10);
+    }
+}
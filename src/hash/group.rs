@@ -0,0 +1,238 @@
+// Control bytes of a SwissMap, scanned a group at a time.
+// A full bucket stores the top 7 bits of its hash, the special bytes have the top bit set.
+
+pub(crate) const GROUP_WIDTH: usize = 16;
+
+pub(crate) const EMPTY: u8 = 0b1111_1111;
+pub(crate) const DELETED: u8 = 0b1000_0000;
+
+pub(crate) fn is_full(ctrl: u8) -> bool {
+    ctrl & 0x80 == 0
+}
+
+// Positions in a group, each one is STRIDE bits wide with the lowest bit of a position
+// set to its byte index times STRIDE plus (STRIDE - 1) for SWAR and plus 0 for SSE2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BitMask<const STRIDE: u32>(pub(crate) u128);
+
+impl<const STRIDE: u32> BitMask<STRIDE> {
+    pub(crate) fn any(self) -> bool {
+        self.0 != 0
+    }
+
+    pub(crate) fn lowest(self) -> Option<usize> {
+        if self.any() {
+            Some(self.trailing_zeros())
+        } else {
+            None
+        }
+    }
+
+    // the number of unset positions at the start of the group
+    pub(crate) fn trailing_zeros(self) -> usize {
+        if self.any() {
+            (self.0.trailing_zeros() / STRIDE) as usize
+        } else {
+            GROUP_WIDTH
+        }
+    }
+
+    // the number of unset positions at the end of the group
+    pub(crate) fn leading_zeros(self) -> usize {
+        if self.any() {
+            let unused = 128 - GROUP_WIDTH as u32 * STRIDE;
+            ((self.0.leading_zeros() - unused) / STRIDE) as usize
+        } else {
+            GROUP_WIDTH
+        }
+    }
+}
+
+impl<const STRIDE: u32> Iterator for BitMask<STRIDE> {
+    type Item = usize;
+    fn next(&mut self) -> Option<usize> {
+        let index = self.lowest()?;
+        self.0 &= self.0 - 1;
+        Some(index)
+    }
+}
+
+// the fallback for other targets, also tested on x86_64
+#[cfg_attr(all(target_arch = "x86_64", target_feature = "sse2"), allow(dead_code))]
+pub(crate) mod generic {
+    use super::{BitMask, GROUP_WIDTH};
+
+    const fn repeat(byte: u8) -> u128 {
+        u128::from_ne_bytes([byte; GROUP_WIDTH])
+    }
+
+    pub(crate) type Mask = BitMask<8>;
+
+    const LSB: u128 = repeat(0x01);
+    const MSB: u128 = repeat(0x80);
+
+    // a group read as one little endian integer, bit 7 of each byte marks the matches
+    #[derive(Clone, Copy)]
+    pub(crate) struct Group(u128);
+
+    impl Group {
+        // cond: ptr points to GROUP_WIDTH readable bytes
+        pub(crate) unsafe fn load(ptr: *const u8) -> Self {
+            Self(u128::from_le(ptr.cast::<u128>().read_unaligned()))
+        }
+
+        // May report a false match right after a real one, the caller compares the keys anyway.
+        pub(crate) fn match_byte(self, byte: u8) -> BitMask<8> {
+            let cmp = self.0 ^ repeat(byte);
+            BitMask(cmp.wrapping_sub(LSB) & !cmp & MSB)
+        }
+
+        // only EMPTY has both of the two top bits set
+        pub(crate) fn match_empty(self) -> BitMask<8> {
+            BitMask(self.0 & (self.0 << 1) & MSB)
+        }
+
+        pub(crate) fn match_empty_or_deleted(self) -> BitMask<8> {
+            BitMask(self.0 & MSB)
+        }
+
+        pub(crate) fn match_full(self) -> BitMask<8> {
+            BitMask(!self.0 & MSB)
+        }
+
+        // EMPTY and DELETED become EMPTY, full bytes become DELETED
+        pub(crate) fn convert_special_to_empty_and_full_to_deleted(self) -> [u8; GROUP_WIDTH] {
+            let full = !self.0 & MSB;
+            (!full + (full >> 7)).to_le_bytes()
+        }
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+pub(crate) mod sse2 {
+    use super::{BitMask, GROUP_WIDTH};
+
+    use std::arch::x86_64::*;
+
+    pub(crate) type Mask = BitMask<1>;
+
+    #[derive(Clone, Copy)]
+    pub(crate) struct Group(__m128i);
+
+    impl Group {
+        // cond: ptr points to GROUP_WIDTH readable bytes
+        pub(crate) unsafe fn load(ptr: *const u8) -> Self {
+            Self(_mm_loadu_si128(ptr.cast()))
+        }
+
+        fn mask(vector: __m128i) -> BitMask<1> {
+            BitMask(unsafe { _mm_movemask_epi8(vector) } as u16 as u128)
+        }
+
+        pub(crate) fn match_byte(self, byte: u8) -> BitMask<1> {
+            Self::mask(unsafe { _mm_cmpeq_epi8(self.0, _mm_set1_epi8(byte as i8)) })
+        }
+
+        pub(crate) fn match_empty(self) -> BitMask<1> {
+            self.match_byte(super::EMPTY)
+        }
+
+        pub(crate) fn match_empty_or_deleted(self) -> BitMask<1> {
+            Self::mask(self.0)
+        }
+
+        pub(crate) fn match_full(self) -> BitMask<1> {
+            BitMask(!self.match_empty_or_deleted().0 & 0xFFFF)
+        }
+
+        // EMPTY and DELETED become EMPTY, full bytes become DELETED
+        pub(crate) fn convert_special_to_empty_and_full_to_deleted(self) -> [u8; GROUP_WIDTH] {
+            let mut bytes = [0; GROUP_WIDTH];
+            unsafe {
+                // special bytes are negative, so they compare to all ones
+                let special = _mm_cmpgt_epi8(_mm_setzero_si128(), self.0);
+                let converted = _mm_or_si128(special, _mm_set1_epi8(super::DELETED as i8));
+                _mm_storeu_si128(bytes.as_mut_ptr().cast(), converted);
+            }
+            bytes
+        }
+    }
+}
+
+#[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+pub(crate) use sse2::{Group, Mask};
+
+#[cfg(not(all(target_arch = "x86_64", target_feature = "sse2")))]
+pub(crate) use generic::{Group, Mask};
+
+#[cfg(test)]
+mod test {
+    use super::{generic, BitMask, DELETED, EMPTY, GROUP_WIDTH};
+
+    fn sample() -> [u8; GROUP_WIDTH] {
+        let mut bytes = [EMPTY; GROUP_WIDTH];
+        bytes[0] = 0x12;
+        bytes[3] = DELETED;
+        bytes[4] = 0x12;
+        bytes[5] = 0x13;
+        bytes[9] = 0x00;
+        bytes[15] = 0x7F;
+        bytes
+    }
+
+    fn positions<const STRIDE: u32>(mask: BitMask<STRIDE>) -> Vec<usize> {
+        mask.collect()
+    }
+
+    #[test]
+    fn test_generic_group() {
+        let bytes = sample();
+        let group = unsafe { generic::Group::load(bytes.as_ptr()) };
+        // 0x13 right after a match of 0x12 is the one false positive
+        assert_eq!(positions(group.match_byte(0x12)), [0, 4, 5]);
+        assert_eq!(positions(group.match_byte(0x00)), [9]);
+        assert_eq!(positions(group.match_full()), [0, 4, 5, 9, 15]);
+        let empty = group.match_empty();
+        assert_eq!(positions(empty), [1, 2, 6, 7, 8, 10, 11, 12, 13, 14]);
+        assert_eq!(empty.trailing_zeros(), 1);
+        assert_eq!(empty.leading_zeros(), 1);
+        assert_eq!(
+            positions(group.match_empty_or_deleted()),
+            [1, 2, 3, 6, 7, 8, 10, 11, 12, 13, 14]
+        );
+        let converted = group.convert_special_to_empty_and_full_to_deleted();
+        for (byte, new) in bytes.iter().zip(converted.iter()) {
+            let expected = if byte & 0x80 == 0 { DELETED } else { EMPTY };
+            assert_eq!(*new, expected);
+        }
+    }
+
+    #[cfg(all(target_arch = "x86_64", target_feature = "sse2"))]
+    #[test]
+    fn test_sse2_group() {
+        use super::sse2;
+
+        let bytes = sample();
+        let group = unsafe { sse2::Group::load(bytes.as_ptr()) };
+        let portable = unsafe { generic::Group::load(bytes.as_ptr()) };
+        assert_eq!(positions(group.match_byte(0x12)), [0, 4]);
+        assert_eq!(
+            positions(group.match_full()),
+            positions(portable.match_full())
+        );
+        assert_eq!(
+            positions(group.match_empty()),
+            positions(portable.match_empty())
+        );
+        assert_eq!(
+            positions(group.match_empty_or_deleted()),
+            positions(portable.match_empty_or_deleted())
+        );
+        assert_eq!(group.match_empty().leading_zeros(), 1);
+        assert_eq!(group.match_byte(0x55).leading_zeros(), GROUP_WIDTH);
+        assert_eq!(
+            group.convert_special_to_empty_and_full_to_deleted(),
+            portable.convert_special_to_empty_and_full_to_deleted()
+        );
+    }
+}
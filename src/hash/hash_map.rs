@@ -0,0 +1,916 @@
+use crate::raw::RawArray;
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::iter::{FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+
+const MIN_BUCKETS: usize = 8;
+
+pub(super) struct Slot<K, V> {
+    pub(super) hash: u64,
+    pub(super) key: K,
+    pub(super) value: V,
+}
+
+// the buckets with the hashes kept beside the entries, so resizing needs no hasher,
+// IndexMap keeps the positions of its entries in one
+pub(super) struct RawTable<K, V> {
+    slots: RawArray<Slot<K, V>>,
+    // 0 marks an empty bucket, otherwise the probe distance plus one
+    dists: Box<[u32]>,
+    len: usize,
+}
+
+// the number of entries the buckets hold before growing, the load factor is 7/8
+fn usable(buckets: usize) -> usize {
+    buckets - buckets / 8
+}
+
+impl<K, V> RawTable<K, V> {
+    pub(super) fn new() -> Self {
+        Self::with_buckets(0)
+    }
+
+    // cond: buckets is 0 or a power of two
+    fn with_buckets(buckets: usize) -> Self {
+        assert!(buckets <= u32::MAX as usize, "capacity overflow");
+        Self {
+            slots: unsafe { RawArray::alloc(buckets) },
+            dists: vec![0; buckets].into_boxed_slice(),
+            len: 0,
+        }
+    }
+
+    fn buckets(&self) -> usize {
+        self.dists.len()
+    }
+
+    fn mask(&self) -> usize {
+        self.buckets() - 1
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.len
+    }
+
+    // cond: the bucket is occupied
+    pub(super) unsafe fn slot(&self, index: usize) -> &Slot<K, V> {
+        &*self.slots.offset(index)
+    }
+
+    // cond: the bucket is occupied
+    pub(super) unsafe fn slot_mut(&mut self, index: usize) -> &mut Slot<K, V> {
+        &mut *self.slots.offset(index)
+    }
+
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        self.find_by(hash, |k| k.borrow() == key)
+    }
+
+    // the bucket of the entry with the hash whose key satisfies eq
+    pub(super) fn find_by(&self, hash: u64, mut eq: impl FnMut(&K) -> bool) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+        let mut index = hash as usize & self.mask();
+        let mut dist = 0;
+        loop {
+            // a richer entry would have taken this bucket, so the key is absent
+            let d = self.dists[index];
+            if d == 0 || d - 1 < dist {
+                return None;
+            }
+            let slot = unsafe { self.slot(index) };
+            if slot.hash == hash && eq(&slot.key) {
+                return Some(index);
+            }
+            index = (index + 1) & self.mask();
+            dist += 1;
+        }
+    }
+
+    pub(super) fn reserve(&mut self, additional: usize) {
+        let needed = self.len.checked_add(additional).expect("capacity overflow");
+        if needed <= usable(self.buckets()) {
+            return;
+        }
+        let mut buckets = self.buckets().max(MIN_BUCKETS);
+        while usable(buckets) < needed {
+            buckets = buckets.checked_mul(2).expect("capacity overflow");
+        }
+        self.resize(buckets);
+    }
+
+    fn resize(&mut self, buckets: usize) {
+        let mut old = mem::replace(self, Self::with_buckets(buckets));
+        for index in 0..old.buckets() {
+            if old.dists[index] != 0 {
+                old.dists[index] = 0;
+                let slot = unsafe { old.slots.offset(index).read() };
+                self.insert_new(slot);
+            }
+        }
+        old.len = 0;
+    }
+
+    // Places an entry whose key is absent and returns its bucket.
+    // Entries closer to their home give up the bucket to the one probing further.
+    // cond: there is room for one more entry
+    pub(super) fn insert_new(&mut self, mut slot: Slot<K, V>) -> usize {
+        let mask = self.mask();
+        let mut index = slot.hash as usize & mask;
+        let mut dist = 0;
+        let mut placed = None;
+        loop {
+            let d = self.dists[index];
+            if d == 0 {
+                unsafe { self.slots.offset(index).write(slot) };
+                self.dists[index] = dist + 1;
+                self.len += 1;
+                return placed.unwrap_or(index);
+            }
+            if d - 1 < dist {
+                mem::swap(&mut slot, unsafe { self.slot_mut(index) });
+                self.dists[index] = dist + 1;
+                dist = d - 1;
+                placed.get_or_insert(index);
+            }
+            index = (index + 1) & mask;
+            dist += 1;
+        }
+    }
+
+    // Takes the entry out and shifts the following entries back, so no tombstones are left.
+    // cond: the bucket is occupied
+    pub(super) fn remove_at(&mut self, index: usize) -> Slot<K, V> {
+        let mask = self.mask();
+        let slot = unsafe { self.slots.offset(index).read() };
+        self.dists[index] = 0;
+        self.len -= 1;
+        let mut hole = index;
+        loop {
+            let next = (hole + 1) & mask;
+            let d = self.dists[next];
+            // stops at an empty bucket or an entry at its home
+            if d <= 1 {
+                return slot;
+            }
+            unsafe {
+                ptr::copy_nonoverlapping(self.slots.offset(next), self.slots.offset(hole), 1)
+            };
+            self.dists[hole] = d - 1;
+            self.dists[next] = 0;
+            hole = next;
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        for index in 0..self.buckets() {
+            if self.dists[index] != 0 {
+                self.dists[index] = 0;
+                unsafe { ptr::drop_in_place(self.slots.offset(index)) };
+            }
+        }
+        self.len = 0;
+    }
+}
+
+impl<K, V> Drop for RawTable<K, V> {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe { self.slots.dealloc() }
+    }
+}
+
+impl<K: Clone, V: Clone> Clone for RawTable<K, V> {
+    fn clone(&self) -> Self {
+        let mut table = Self::with_buckets(self.buckets());
+        for index in 0..self.buckets() {
+            if self.dists[index] != 0 {
+                let slot = unsafe { self.slot(index) };
+                let slot = Slot {
+                    hash: slot.hash,
+                    key: slot.key.clone(),
+                    value: slot.value.clone(),
+                };
+                unsafe { table.slots.offset(index).write(slot) };
+                table.dists[index] = self.dists[index];
+                table.len += 1;
+            }
+        }
+        table
+    }
+}
+
+// Probe distances of the entries, a probe distance is how far an entry sits from its home bucket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProbeStats {
+    pub len: usize,
+    pub buckets: usize,
+    pub max: usize,
+    pub mean: f64,
+    // the number of entries at each probe distance
+    pub histogram: Vec<usize>,
+}
+
+impl ProbeStats {
+    pub fn load_factor(&self) -> f64 {
+        if self.buckets == 0 {
+            0.0
+        } else {
+            self.len as f64 / self.buckets as f64
+        }
+    }
+}
+
+// Open addressing hash map with Robin Hood linear probing and backward shift deletion.
+pub struct HashMap<K, V, S = RandomState> {
+    table: RawTable<K, V>,
+    hasher: S,
+}
+
+unsafe impl<K: Send, V: Send, S: Send> Send for HashMap<K, V, S> {}
+unsafe impl<K: Sync, V: Sync, S: Sync> Sync for HashMap<K, V, S> {}
+
+impl<K, V> HashMap<K, V, RandomState> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> HashMap<K, V, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            table: RawTable::new(),
+            hasher,
+        }
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        let mut map = Self::with_hasher(hasher);
+        map.table.reserve(capacity);
+        map
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.len == 0
+    }
+
+    // the number of entries held without growing
+    pub fn capacity(&self) -> usize {
+        usable(self.table.buckets())
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.table.reserve(additional)
+    }
+
+    pub fn clear(&mut self) {
+        self.table.clear()
+    }
+
+    // keeps the entries for which f returns true
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        let buckets = self.table.buckets();
+        if self.table.len == 0 {
+            return;
+        }
+        // Starts after an empty bucket, so shifting never brings back a visited entry.
+        let start = self.table.dists.iter().position(|&d| d == 0).unwrap();
+        let mut visited = 0;
+        let mut index = (start + 1) & self.table.mask();
+        while visited < buckets - 1 {
+            if self.table.dists[index] != 0 {
+                let slot = unsafe { self.table.slot_mut(index) };
+                if !f(&slot.key, &mut slot.value) {
+                    drop(self.table.remove_at(index));
+                    // the bucket now holds the next entry of the cluster
+                    if self.table.dists[index] != 0 {
+                        continue;
+                    }
+                }
+            }
+            index = (index + 1) & self.table.mask();
+            visited += 1;
+        }
+    }
+
+    // Removes all entries as an iterator, the capacity is kept.
+    // Leaking the iterator leaks the remaining entries and leaves the map empty.
+    pub fn drain(&mut self) -> Drain<'_, K, V> {
+        let table = mem::replace(&mut self.table, RawTable::new());
+        Drain {
+            iter: IntoIter { table, index: 0 },
+            home: &mut self.table,
+        }
+    }
+
+    pub fn probe_stats(&self) -> ProbeStats {
+        let mut histogram = Vec::new();
+        let mut total = 0;
+        for &d in self.table.dists.iter().filter(|&&d| d != 0) {
+            let dist = d as usize - 1;
+            if histogram.len() <= dist {
+                histogram.resize(dist + 1, 0);
+            }
+            histogram[dist] += 1;
+            total += dist;
+        }
+        let len = self.table.len;
+        ProbeStats {
+            len,
+            buckets: self.table.buckets(),
+            max: histogram.len().saturating_sub(1),
+            mean: if len == 0 {
+                0.0
+            } else {
+                total as f64 / len as f64
+            },
+            histogram,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            iter: RawIter::new(&self.table),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            iter: RawIter::new(&self.table),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> + '_ {
+        self.iter_mut().map(|(_, v)| v)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> HashMap<K, V, S> {
+    fn hash<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        self.hasher.hash_one(key)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        let hash = self.hash(&key);
+        match self.table.find(hash, &key) {
+            Some(index) => Entry::Occupied(OccupiedEntry {
+                table: &mut self.table,
+                index,
+            }),
+            None => {
+                self.table.reserve(1);
+                Entry::Vacant(VacantEntry {
+                    table: &mut self.table,
+                    hash,
+                    key,
+                })
+            }
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, v)| v)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.table.find(self.hash(key), key)?;
+        let slot = unsafe { self.table.slot(index) };
+        Some((&slot.key, &slot.value))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.table.find(self.hash(key), key)?;
+        Some(unsafe { &mut self.table.slot_mut(index).value })
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.table.find(self.hash(key), key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.table.find(self.hash(key), key)?;
+        let slot = self.table.remove_at(index);
+        Some((slot.key, slot.value))
+    }
+}
+
+impl<K, V, S: Default> Default for HashMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Clone, V: Clone, S: Clone> Clone for HashMap<K, V, S> {
+    fn clone(&self) -> Self {
+        Self {
+            table: self.table.clone(),
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for HashMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for HashMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        map.extend(iter);
+        map
+    }
+}
+
+// ------------------------------------
+// begin: Entry
+
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    table: &'a mut RawTable<K, V>,
+    index: usize,
+}
+
+// the table already has room for the key
+pub struct VacantEntry<'a, K, V> {
+    table: &'a mut RawTable<K, V>,
+    hash: u64,
+    key: K,
+}
+
+impl<'a, K, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        unsafe { &self.table.slot(self.index).key }
+    }
+
+    pub fn get(&self) -> &V {
+        unsafe { &self.table.slot(self.index).value }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { &mut self.table.slot_mut(self.index).value }
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { &mut self.table.slot_mut(self.index).value }
+    }
+
+    // returns the old value
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        let slot = self.table.remove_at(self.index);
+        (slot.key, slot.value)
+    }
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let index = self.table.insert_new(Slot {
+            hash: self.hash,
+            key: self.key,
+            value,
+        });
+        unsafe { &mut self.table.slot_mut(index).value }
+    }
+}
+
+// end: Entry
+// ------------------------------------
+
+struct RawIter<K, V> {
+    slots: RawArray<Slot<K, V>>,
+    dists: *const [u32],
+    index: usize,
+    len: usize,
+}
+
+impl<K, V> RawIter<K, V> {
+    fn new(table: &RawTable<K, V>) -> Self {
+        Self {
+            slots: unsafe { table.slots.shadow_clone() },
+            dists: &*table.dists,
+            index: 0,
+            len: table.len,
+        }
+    }
+
+    fn next(&mut self) -> Option<*mut Slot<K, V>> {
+        if self.len == 0 {
+            return None;
+        }
+        loop {
+            let index = self.index;
+            self.index += 1;
+            if unsafe { (*self.dists)[index] } != 0 {
+                self.len -= 1;
+                return Some(unsafe { self.slots.offset(index) });
+            }
+        }
+    }
+}
+
+// ------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, K, V> {
+    iter: RawIter<K, V>,
+    _marker: PhantomData<&'a HashMap<K, V>>,
+}
+
+unsafe impl<K: Sync, V: Sync> Send for Iter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Iter<'_, K, V> {}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|slot| unsafe { (&(*slot).key, &(*slot).value) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.iter.len, Some(self.iter.len))
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a HashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+// end: Iter
+// ------------------------------------
+
+// ------------------------------------
+// begin: IterMut
+
+pub struct IterMut<'a, K, V> {
+    iter: RawIter<K, V>,
+    _marker: PhantomData<&'a mut HashMap<K, V>>,
+}
+
+unsafe impl<K: Sync, V: Send> Send for IterMut<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for IterMut<'_, K, V> {}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|slot| unsafe { (&(*slot).key, &mut (*slot).value) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.iter.len, Some(self.iter.len))
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut HashMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+// end: IterMut
+// ------------------------------------
+
+// ------------------------------------
+// begin: IntoIter
+
+pub struct IntoIter<K, V> {
+    table: RawTable<K, V>,
+    index: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<(K, V)> {
+        if self.table.len == 0 {
+            return None;
+        }
+        loop {
+            let index = self.index;
+            self.index += 1;
+            if self.table.dists[index] != 0 {
+                // marks the bucket empty, so the table drops only what is left
+                self.table.dists[index] = 0;
+                self.table.len -= 1;
+                let slot = unsafe { self.table.slots.offset(index).read() };
+                return Some((slot.key, slot.value));
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.table.len, Some(self.table.len))
+    }
+}
+
+impl<K, V, S> IntoIterator for HashMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            table: self.table,
+            index: 0,
+        }
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+// end: IntoIter
+// ------------------------------------
+
+// ------------------------------------
+// begin: Drain
+
+pub struct Drain<'a, K, V> {
+    iter: IntoIter<K, V>,
+    home: &'a mut RawTable<K, V>,
+}
+
+impl<K, V> Iterator for Drain<'_, K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<(K, V)> {
+        self.iter.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Drain<'_, K, V> {}
+
+impl<K, V> FusedIterator for Drain<'_, K, V> {}
+
+impl<K, V> Drop for Drain<'_, K, V> {
+    fn drop(&mut self) {
+        // gives the emptied buckets back to the map
+        self.iter.table.clear();
+        mem::swap(self.home, &mut self.iter.table);
+    }
+}
+
+// end: Drain
+// ------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::{Entry, HashMap};
+
+    use crate::rng::Rng;
+
+    use std::collections::BTreeMap;
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    // maps every key to a few buckets to force long clusters
+    #[derive(Default)]
+    struct Collide(u64);
+
+    impl Hasher for Collide {
+        fn finish(&self) -> u64 {
+            self.0 % 4
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &b in bytes {
+                self.0 = self.0.wrapping_mul(31).wrapping_add(b as u64);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hash_map() {
+        let mut map = HashMap::new();
+        assert_eq!(map.capacity(), 0);
+        assert_eq!(map.get("a"), None);
+        assert_eq!(map.insert("a".to_string(), 1), None);
+        assert_eq!(map.insert("b".to_string(), 2), None);
+        assert_eq!(map.insert("a".to_string(), 3), Some(1));
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("a"), Some(&3));
+        *map.get_mut("b").unwrap() += 10;
+        assert_eq!(map.remove("b"), Some(12));
+        assert_eq!(map.remove("b"), None);
+        assert!(map.contains_key("a"));
+
+        *map.entry("c".to_string()).or_insert(0) += 5;
+        *map.entry("c".to_string()).or_default() += 5;
+        map.entry("a".to_string()).and_modify(|v| *v *= 2);
+        match map.entry("a".to_string()) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove_entry(), ("a".to_string(), 6)),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        assert_eq!(map.get("c"), Some(&10));
+        assert_eq!(map.len(), 1);
+
+        map.reserve(100);
+        assert!(map.capacity() >= 101);
+        let capacity = map.capacity();
+        map.extend((0..100).map(|i| (i.to_string(), i)));
+        assert_eq!(map.capacity(), capacity);
+
+        map.retain(|_, v| *v % 2 == 0);
+        assert_eq!(map.len(), 51);
+        let cloned = map.clone();
+        let mut drained: Vec<_> = map.drain().map(|(_, v)| v).collect();
+        drained.sort_unstable();
+        assert_eq!(drained[..3], [0, 2, 4]);
+        assert!(map.is_empty());
+        assert_eq!(map.capacity(), capacity);
+        assert_eq!(cloned.len(), 51);
+        assert_eq!(cloned.values().sum::<i32>(), 2460);
+    }
+
+    #[test]
+    fn test_random_ops() {
+        let mut rng = Rng::new(39);
+        let mut map: HashMap<u64, u64, BuildHasherDefault<Collide>> = HashMap::default();
+        let mut expected = BTreeMap::new();
+        for _ in 0..20_000 {
+            let key = rng.next_u64() % 500;
+            let value = rng.next_u64();
+            match rng.next_u64() % 4 {
+                0 | 1 => assert_eq!(map.insert(key, value), expected.insert(key, value)),
+                2 => assert_eq!(map.remove(&key), expected.remove(&key)),
+                _ => assert_eq!(map.get(&key), expected.get(&key)),
+            }
+            assert_eq!(map.len(), expected.len());
+        }
+        map.retain(|k, v| {
+            *v = 0;
+            k % 3 == 1
+        });
+        expected.retain(|k, _| k % 3 == 1);
+        let mut items: Vec<_> = map.iter_mut().map(|(k, v)| (*k, *v)).collect();
+        items.sort_unstable();
+        assert!(items.into_iter().eq(expected.keys().map(|&k| (k, 0))));
+
+        let stats = map.probe_stats();
+        assert_eq!(stats.len, map.len());
+        assert_eq!(stats.histogram.iter().sum::<usize>(), map.len());
+        assert!(stats.load_factor() <= 0.875);
+
+        let mut keys: Vec<_> = map.into_iter().map(|(k, _)| k).collect();
+        keys.sort_unstable();
+        assert!(keys.into_iter().eq(expected.into_keys()));
+    }
+
+    #[test]
+    fn test_probe_stats() {
+        let map: HashMap<u32, u32> = (0..1000).map(|i| (i, i)).collect();
+        let stats = map.probe_stats();
+        assert_eq!(stats.len, 1000);
+        assert_eq!(stats.buckets, 2048);
+        assert!(stats.mean < 2.0);
+        assert_eq!(stats.histogram.len(), stats.max + 1);
+    }
+}
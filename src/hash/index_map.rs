@@ -0,0 +1,567 @@
+use super::hash_map::{RawTable, Slot};
+use crate::linear_list::SequenceList;
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::iter::{FromIterator, FusedIterator};
+use std::mem;
+use std::ops::Range;
+use std::slice;
+
+// The positions of the entries in the table of HashMap under the hashes of their keys,
+// the keys are compared in the entries.
+#[derive(Clone)]
+struct Indices(RawTable<usize, ()>);
+
+// the table holds nothing but positions
+unsafe impl Send for Indices {}
+unsafe impl Sync for Indices {}
+
+impl Indices {
+    fn new() -> Self {
+        Self(RawTable::new())
+    }
+
+    // returns the bucket whose entry satisfies eq
+    fn find(&self, hash: u64, mut eq: impl FnMut(usize) -> bool) -> Option<usize> {
+        self.0.find_by(hash, |&index| eq(index))
+    }
+
+    // the bucket pointing at the entry
+    fn find_index(&self, hash: u64, index: usize) -> usize {
+        self.find(hash, |i| i == index).expect("index is missing")
+    }
+
+    // cond: the bucket comes from find
+    fn index(&self, bucket: usize) -> usize {
+        unsafe { self.0.slot(bucket).key }
+    }
+
+    // cond: the bucket comes from find
+    fn set_index(&mut self, bucket: usize, index: usize) {
+        unsafe { self.0.slot_mut(bucket).key = index }
+    }
+
+    // grows so that len entries fit
+    fn reserve(&mut self, len: usize) {
+        self.0.reserve(len.saturating_sub(self.0.len()))
+    }
+
+    // cond: there is room for one more entry
+    fn insert(&mut self, hash: u64, index: usize) {
+        self.0.insert_new(Slot {
+            hash,
+            key: index,
+            value: (),
+        });
+    }
+
+    fn remove_at(&mut self, bucket: usize) {
+        self.0.remove_at(bucket);
+    }
+
+    fn clear(&mut self) {
+        self.0.clear()
+    }
+}
+
+// Hash map that keeps its entries in insertion order in a SequenceList.
+// An entry can be reached by its key or by its position.
+pub struct IndexMap<K, V, S = RandomState> {
+    entries: SequenceList<(K, V)>,
+    indices: Indices,
+    hasher: S,
+}
+
+impl<K, V> IndexMap<K, V, RandomState> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> IndexMap<K, V, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        Self::with_capacity_and_hasher(0, hasher)
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        let mut map = Self {
+            entries: SequenceList::new(capacity),
+            indices: Indices::new(),
+            hasher,
+        };
+        map.indices.reserve(capacity);
+        map
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.entries.reserve(additional);
+        self.indices.reserve(self.entries.len() + additional);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.indices.clear();
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        self.entries.get(index).map(|(k, v)| (k, v))
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        self.entries.get_mut(index).map(|(k, v)| (&*k, v))
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.get_index(0)
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.entries.last().map(|(k, v)| (k, v))
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.entries.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut(self.entries.iter_mut())
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator + '_ {
+        self.iter().map(|(_, v)| v)
+    }
+
+    pub fn values_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = &mut V> + ExactSizeIterator + '_ {
+        self.iter_mut().map(|(_, v)| v)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> IndexMap<K, V, S> {
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let entries = &self.entries;
+        self.indices.find(hash, |i| entries[i].0.borrow() == key)
+    }
+
+    // keeps the position of an existing key
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_full(key, value).1
+    }
+
+    // returns the position of the key and the old value
+    pub fn insert_full(&mut self, key: K, value: V) -> (usize, Option<V>) {
+        let hash = self.hasher.hash_one(&key);
+        if let Some(bucket) = self.find(hash, &key) {
+            let index = self.indices.index(bucket);
+            let old = mem::replace(&mut self.entries[index].1, value);
+            return (index, Some(old));
+        }
+        let index = self.entries.len();
+        self.reserve(1);
+        self.entries.push((key, value));
+        self.indices.insert(hash, index);
+        (index, None)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_full(key).map(|(_, _, v)| v)
+    }
+
+    pub fn get_full<Q>(&self, key: &Q) -> Option<(usize, &K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        let (k, v) = &self.entries[index];
+        Some((index, k, v))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        Some(&mut self.entries[index].1)
+    }
+
+    pub fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let bucket = self.find(self.hasher.hash_one(key), key)?;
+        Some(self.indices.index(bucket))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_index_of(key).is_some()
+    }
+
+    // Removes the entry by moving the last one into its place, which is O(1) but perturbs the order.
+    pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        self.swap_remove_index(index).map(|(_, v)| v)
+    }
+
+    // Removes the entry by shifting all the following ones, which is O(n) but keeps the order.
+    pub fn shift_remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        self.shift_remove_index(index).map(|(_, v)| v)
+    }
+
+    pub fn swap_remove_index(&mut self, index: usize) -> Option<(K, V)> {
+        let last = self.entries.len().checked_sub(1)?;
+        if index > last {
+            return None;
+        }
+        let hash = self.hasher.hash_one(&self.entries[index].0);
+        self.indices.remove_at(self.indices.find_index(hash, index));
+        if index != last {
+            let hash = self.hasher.hash_one(&self.entries[last].0);
+            let bucket = self.indices.find_index(hash, last);
+            self.indices.set_index(bucket, index);
+            self.entries.swap(index, last);
+        }
+        self.entries.pop()
+    }
+
+    pub fn shift_remove_index(&mut self, index: usize) -> Option<(K, V)> {
+        if index >= self.entries.len() {
+            return None;
+        }
+        let hash = self.hasher.hash_one(&self.entries[index].0);
+        self.indices.remove_at(self.indices.find_index(hash, index));
+        self.shift_indices(index + 1..self.entries.len(), false);
+        Some(self.entries.remove(index))
+    }
+
+    pub fn pop(&mut self) -> Option<(K, V)> {
+        self.swap_remove_index(self.entries.len().checked_sub(1)?)
+    }
+
+    // moves the entry at from to to, shifting the entries in between
+    // panics if an index is out of bounds
+    pub fn move_index(&mut self, from: usize, to: usize) {
+        let len = self.entries.len();
+        assert!(from < len && to < len, "index out of bounds");
+        let hash = self.hasher.hash_one(&self.entries[from].0);
+        let bucket = self.indices.find_index(hash, from);
+        match from.cmp(&to) {
+            Ordering::Less => {
+                self.shift_indices(from + 1..to + 1, false);
+                let entries: &mut [(K, V)] = &mut self.entries;
+                entries[from..=to].rotate_left(1);
+            }
+            Ordering::Greater => {
+                self.shift_indices(to..from, true);
+                let entries: &mut [(K, V)] = &mut self.entries;
+                entries[to..=from].rotate_right(1);
+            }
+            Ordering::Equal => {}
+        }
+        self.indices.set_index(bucket, to);
+    }
+
+    // Moves the positions of the entries in range one step up or down in the index,
+    // looking up only their buckets. The positions are changed starting from the end
+    // they move away from, so the one looked up is never held by two buckets.
+    fn shift_indices(&mut self, range: Range<usize>, up: bool) {
+        let mut shift = |index: usize| {
+            let hash = self.hasher.hash_one(&self.entries[index].0);
+            let bucket = self.indices.find_index(hash, index);
+            self.indices
+                .set_index(bucket, if up { index + 1 } else { index - 1 });
+        };
+        if up {
+            range.rev().for_each(&mut shift)
+        } else {
+            range.for_each(&mut shift)
+        }
+    }
+
+    pub fn sort_keys(&mut self)
+    where
+        K: Ord,
+    {
+        self.sort_by(|k1, _, k2, _| k1.cmp(k2))
+    }
+
+    // a stable sort of the entries
+    pub fn sort_by<F>(&mut self, mut cmp: F)
+    where
+        F: FnMut(&K, &V, &K, &V) -> Ordering,
+    {
+        self.entries
+            .sort_by(|(k1, v1), (k2, v2)| cmp(k1, v1, k2, v2));
+        self.indices.clear();
+        for (index, (key, _)) in self.entries.iter().enumerate() {
+            let hash = self.hasher.hash_one(key);
+            self.indices.insert(hash, index);
+        }
+    }
+}
+
+impl<K, V, S: Default> Default for IndexMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Clone, V: Clone, S: Clone> Clone for IndexMap<K, V, S> {
+    fn clone(&self) -> Self {
+        Self {
+            entries: self.entries.clone(),
+            indices: self.indices.clone(),
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for IndexMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for IndexMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        map.extend(iter);
+        map
+    }
+}
+
+// ------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, K, V>(slice::Iter<'a, (K, V)>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a IndexMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+// end: Iter
+// ------------------------------------
+
+// ------------------------------------
+// begin: IterMut
+
+pub struct IterMut<'a, K, V>(slice::IterMut<'a, (K, V)>);
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (&*k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut IndexMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (&*k, v))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+// end: IterMut
+// ------------------------------------
+
+impl<K, V, S> IntoIterator for IndexMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = <SequenceList<(K, V)> as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::IndexMap;
+
+    use crate::rng::Rng;
+
+    #[test]
+    fn test_index_map() {
+        let mut map = IndexMap::new();
+        for key in ["c", "a", "d", "b", "e"].iter() {
+            map.insert(key.to_string(), key.len());
+        }
+        assert_eq!(map.insert("a".to_string(), 10), Some(1));
+        assert_eq!(map.insert_full("f".to_string(), 6), (5, None));
+        assert_eq!(map.get_index(1), Some((&"a".to_string(), &10)));
+        assert_eq!(map.get_full("d"), Some((2, &"d".to_string(), &1)));
+        assert!(map.keys().eq(["c", "a", "d", "b", "e", "f"].iter()));
+
+        assert_eq!(map.swap_remove("a"), Some(10));
+        assert!(map.keys().eq(["c", "f", "d", "b", "e"].iter()));
+        assert_eq!(map.shift_remove("f"), Some(6));
+        assert!(map.keys().eq(["c", "d", "b", "e"].iter()));
+        assert_eq!(map.get_index_of("e"), Some(3));
+
+        map.move_index(0, 2);
+        assert!(map.keys().eq(["d", "b", "c", "e"].iter()));
+        map.move_index(3, 0);
+        assert!(map.keys().eq(["e", "d", "b", "c"].iter()));
+        assert_eq!(map.get_index_of("c"), Some(3));
+
+        map.sort_keys();
+        assert!(map.keys().eq(["b", "c", "d", "e"].iter()));
+        assert_eq!(map.get_index_of("e"), Some(3));
+        *map.get_mut("c").unwrap() = 3;
+        assert_eq!(map.pop(), Some(("e".to_string(), 1)));
+        assert_eq!(map.first(), Some((&"b".to_string(), &1)));
+        assert_eq!(map.last(), Some((&"d".to_string(), &1)));
+        let cloned = map.clone();
+        assert!(map.into_iter().eq(cloned.into_iter()));
+    }
+
+    #[test]
+    fn test_random_ops() {
+        let mut rng = Rng::new(42);
+        let mut map = IndexMap::new();
+        // the entries in insertion order
+        let mut expected: Vec<(u64, u64)> = Vec::new();
+        for i in 0..5000 {
+            let key = rng.next_u64() % 300;
+            let index = expected.iter().position(|&(k, _)| k == key);
+            match rng.next_u64() % 5 {
+                0 | 1 => {
+                    let old = match index {
+                        Some(index) => Some(std::mem::replace(&mut expected[index].1, i)),
+                        None => {
+                            expected.push((key, i));
+                            None
+                        }
+                    };
+                    assert_eq!(map.insert(key, i), old);
+                }
+                2 => {
+                    let old = index.map(|index| expected.swap_remove(index).1);
+                    assert_eq!(map.swap_remove(&key), old);
+                }
+                3 => {
+                    let old = index.map(|index| expected.remove(index).1);
+                    assert_eq!(map.shift_remove(&key), old);
+                }
+                _ if !expected.is_empty() => {
+                    let from = rng.next_u64() as usize % expected.len();
+                    let to = rng.next_u64() as usize % expected.len();
+                    let entry = expected.remove(from);
+                    expected.insert(to, entry);
+                    map.move_index(from, to);
+                }
+                _ => {}
+            }
+            assert!(map
+                .iter()
+                .map(|(&k, &v)| (k, v))
+                .eq(expected.iter().copied()));
+        }
+        for (index, (key, _)) in expected.iter().enumerate() {
+            assert_eq!(map.get_index_of(key), Some(index));
+        }
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let mut map = IndexMap::<(), ()>::new();
+        assert_eq!(map.insert((), ()), None);
+        assert_eq!(map.insert((), ()), Some(()));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get_index(0), Some((&(), &())));
+        assert_eq!(map.swap_remove(&()), Some(()));
+        assert!(map.is_empty());
+    }
+}
@@ -0,0 +1,12 @@
+pub mod cuckoo_hash_map;
+pub mod cuckoo_hash_set;
+mod group;
+pub mod hash_map;
+pub mod index_map;
+pub mod swiss_map;
+
+pub use cuckoo_hash_map::CuckooHashMap;
+pub use cuckoo_hash_set::CuckooHashSet;
+pub use hash_map::HashMap;
+pub use index_map::IndexMap;
+pub use swiss_map::SwissMap;
@@ -0,0 +1,795 @@
+use super::group::{self, BitMask, Group, Mask, DELETED, EMPTY, GROUP_WIDTH};
+use crate::raw::RawArray;
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::iter::{FromIterator, FusedIterator};
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr;
+
+// the lowest bits of the hash pick the first group
+fn h1(hash: u64) -> usize {
+    hash as usize
+}
+
+// the top 7 bits of the hash are kept in the control byte
+fn h2(hash: u64) -> u8 {
+    (hash >> 57) as u8
+}
+
+// the number of entries the buckets hold before growing, the load factor is 7/8
+fn usable(buckets: usize) -> usize {
+    buckets - buckets / 8
+}
+
+// triangular probing over groups, it visits every group when the bucket count is a power of two
+struct ProbeSeq {
+    pos: usize,
+    stride: usize,
+}
+
+impl ProbeSeq {
+    fn move_next(&mut self, mask: usize) {
+        self.stride += GROUP_WIDTH;
+        self.pos = (self.pos + self.stride) & mask;
+    }
+}
+
+struct RawTable<K, V> {
+    slots: RawArray<(K, V)>,
+    // one byte per bucket, followed by a copy of the first group so a group can be read at any bucket
+    ctrl: Box<[u8]>,
+    items: usize,
+    // the EMPTY buckets that may still be filled, tombstones do not give it back
+    growth_left: usize,
+}
+
+impl<K, V> RawTable<K, V> {
+    fn new() -> Self {
+        Self::with_buckets(0)
+    }
+
+    // cond: buckets is 0 or a power of two no less than GROUP_WIDTH
+    fn with_buckets(buckets: usize) -> Self {
+        let ctrl_len = if buckets == 0 {
+            0
+        } else {
+            buckets + GROUP_WIDTH
+        };
+        Self {
+            slots: unsafe { RawArray::alloc(buckets) },
+            ctrl: vec![EMPTY; ctrl_len].into_boxed_slice(),
+            items: 0,
+            growth_left: usable(buckets),
+        }
+    }
+
+    fn buckets(&self) -> usize {
+        self.slots.cap
+    }
+
+    fn mask(&self) -> usize {
+        self.buckets() - 1
+    }
+
+    fn probe_seq(&self, hash: u64) -> ProbeSeq {
+        ProbeSeq {
+            pos: h1(hash) & self.mask(),
+            stride: 0,
+        }
+    }
+
+    // cond: buckets > 0 and pos < buckets
+    unsafe fn group(&self, pos: usize) -> Group {
+        Group::load(self.ctrl.as_ptr().add(pos))
+    }
+
+    unsafe fn slot(&self, index: usize) -> *mut (K, V) {
+        self.slots.offset(index)
+    }
+
+    // writes the byte and its copy in the trailing group
+    fn set_ctrl(&mut self, index: usize, ctrl: u8) {
+        let mirror = (index.wrapping_sub(GROUP_WIDTH) & self.mask()) + GROUP_WIDTH;
+        self.ctrl[index] = ctrl;
+        self.ctrl[mirror] = ctrl;
+    }
+
+    fn find<Q>(&self, hash: u64, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        if self.buckets() == 0 {
+            return None;
+        }
+        let mut seq = self.probe_seq(hash);
+        loop {
+            let group = unsafe { self.group(seq.pos) };
+            for bit in group.match_byte(h2(hash)) {
+                let index = (seq.pos + bit) & self.mask();
+                if unsafe { (*self.slot(index)).0.borrow() } == key {
+                    return Some(index);
+                }
+            }
+            // an insert would have stopped at this group
+            if group.match_empty().any() {
+                return None;
+            }
+            seq.move_next(self.mask());
+        }
+    }
+
+    // the first EMPTY or DELETED bucket on the probe sequence
+    // cond: buckets > 0
+    fn find_insert_slot(&self, hash: u64) -> usize {
+        let mut seq = self.probe_seq(hash);
+        loop {
+            let group = unsafe { self.group(seq.pos) };
+            if let Some(bit) = group.match_empty_or_deleted().lowest() {
+                return (seq.pos + bit) & self.mask();
+            }
+            seq.move_next(self.mask());
+        }
+    }
+
+    // which group of the probe sequence of hash the bucket lies in
+    fn probe_index(&self, hash: u64, index: usize) -> usize {
+        (index.wrapping_sub(h1(hash)) & self.mask()) / GROUP_WIDTH
+    }
+
+    // cond: the key is absent
+    fn insert_new(&mut self, hash: u64, key: K, value: V, hasher: impl Fn(&K) -> u64) -> usize {
+        if self.buckets() == 0 {
+            self.reserve_rehash(1, &hasher);
+        }
+        let mut index = self.find_insert_slot(hash);
+        // a tombstone can be reused even when there is no growth left
+        if self.growth_left == 0 && self.ctrl[index] == EMPTY {
+            self.reserve_rehash(1, &hasher);
+            index = self.find_insert_slot(hash);
+        }
+        if self.ctrl[index] == EMPTY {
+            self.growth_left -= 1;
+        }
+        self.set_ctrl(index, h2(hash));
+        unsafe { self.slot(index).write((key, value)) };
+        self.items += 1;
+        index
+    }
+
+    // Leaves an EMPTY byte when no probe can have passed this bucket, that is
+    // when the groups around it never were completely non-empty.
+    fn erase(&mut self, index: usize) -> (K, V) {
+        let before = index.wrapping_sub(GROUP_WIDTH) & self.mask();
+        let empty_before = unsafe { self.group(before).match_empty() };
+        let empty_after = unsafe { self.group(index).match_empty() };
+        let ctrl = if empty_before.leading_zeros() + empty_after.trailing_zeros() >= GROUP_WIDTH {
+            DELETED
+        } else {
+            self.growth_left += 1;
+            EMPTY
+        };
+        self.set_ctrl(index, ctrl);
+        self.items -= 1;
+        unsafe { self.slot(index).read() }
+    }
+
+    fn reserve(&mut self, additional: usize, hasher: impl Fn(&K) -> u64) {
+        if additional > self.growth_left {
+            self.reserve_rehash(additional, &hasher);
+        }
+    }
+
+    // Clears the tombstones in place when they take much of the table, otherwise grows.
+    fn reserve_rehash(&mut self, additional: usize, hasher: &impl Fn(&K) -> u64) {
+        let needed = self
+            .items
+            .checked_add(additional)
+            .expect("capacity overflow");
+        let full_capacity = usable(self.buckets());
+        if needed <= full_capacity / 2 {
+            self.rehash_in_place(hasher);
+        } else {
+            let mut buckets = self.buckets().max(GROUP_WIDTH);
+            while usable(buckets) < needed.max(full_capacity + 1) {
+                buckets = buckets.checked_mul(2).expect("capacity overflow");
+            }
+            self.resize(buckets, hasher);
+        }
+    }
+
+    fn resize(&mut self, buckets: usize, hasher: &impl Fn(&K) -> u64) {
+        let mut new = Self::with_buckets(buckets);
+        for index in 0..self.buckets() {
+            if group::is_full(self.ctrl[index]) {
+                let hash = hasher(unsafe { &(*self.slot(index)).0 });
+                // the entry leaves the old table before it may be dropped with the new one
+                self.set_ctrl(index, EMPTY);
+                self.items -= 1;
+                let target = new.find_insert_slot(hash);
+                new.set_ctrl(target, h2(hash));
+                unsafe { ptr::copy_nonoverlapping(self.slot(index), new.slot(target), 1) };
+                new.items += 1;
+                new.growth_left -= 1;
+            }
+        }
+        *self = new;
+    }
+
+    // Marks every entry as DELETED, then moves each one to the first free bucket of its probe sequence.
+    // DELETED means "not yet rehashed" during the loop.
+    fn rehash_in_place(&mut self, hasher: &impl Fn(&K) -> u64) {
+        let buckets = self.buckets();
+        for pos in (0..buckets).step_by(GROUP_WIDTH) {
+            let converted =
+                unsafe { self.group(pos) }.convert_special_to_empty_and_full_to_deleted();
+            self.ctrl[pos..pos + GROUP_WIDTH].copy_from_slice(&converted);
+        }
+        let (head, tail) = self.ctrl.split_at_mut(buckets);
+        tail.copy_from_slice(&head[..GROUP_WIDTH]);
+
+        // drops the entries left behind if the hasher panics
+        let guard = RehashGuard(self);
+        let table = &mut *guard.0;
+        'outer: for index in 0..buckets {
+            if table.ctrl[index] != DELETED {
+                continue;
+            }
+            loop {
+                let hash = hasher(unsafe { &(*table.slot(index)).0 });
+                let target = table.find_insert_slot(hash);
+                if table.probe_index(hash, index) == table.probe_index(hash, target) {
+                    // already in the best group it can get
+                    table.set_ctrl(index, h2(hash));
+                    continue 'outer;
+                }
+                let prev = table.ctrl[target];
+                table.set_ctrl(target, h2(hash));
+                if prev == EMPTY {
+                    table.set_ctrl(index, EMPTY);
+                    unsafe { ptr::copy_nonoverlapping(table.slot(index), table.slot(target), 1) };
+                    continue 'outer;
+                }
+                // the target is waiting to be rehashed too, takes its entry over to this bucket
+                unsafe { ptr::swap(table.slot(index), table.slot(target)) };
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        if self.items != 0 {
+            for index in 0..self.buckets() {
+                if group::is_full(self.ctrl[index]) {
+                    unsafe { ptr::drop_in_place(self.slot(index)) };
+                }
+            }
+        }
+        self.ctrl.iter_mut().for_each(|ctrl| *ctrl = EMPTY);
+        self.items = 0;
+        self.growth_left = usable(self.buckets());
+    }
+
+    #[cfg(test)]
+    fn tombstones(&self) -> usize {
+        self.ctrl[..self.buckets()]
+            .iter()
+            .filter(|&&c| c == DELETED)
+            .count()
+    }
+}
+
+impl<K, V> Drop for RawTable<K, V> {
+    fn drop(&mut self) {
+        self.clear();
+        unsafe { self.slots.dealloc() }
+    }
+}
+
+struct RehashGuard<'a, K, V>(&'a mut RawTable<K, V>);
+
+impl<K, V> Drop for RehashGuard<'_, K, V> {
+    fn drop(&mut self) {
+        let table = &mut *self.0;
+        for index in 0..table.buckets() {
+            if table.ctrl[index] == DELETED {
+                table.set_ctrl(index, EMPTY);
+                unsafe { ptr::drop_in_place(table.slot(index)) };
+                table.items -= 1;
+            }
+        }
+        table.growth_left = usable(table.buckets()) - table.items;
+    }
+}
+
+// Hash map in the layout of SwissTable. A lookup compares the control bytes of
+// a whole group at once, with SSE2 on x86_64 and with SWAR integer tricks elsewhere.
+pub struct SwissMap<K, V, S = RandomState> {
+    table: RawTable<K, V>,
+    hasher: S,
+}
+
+unsafe impl<K: Send, V: Send, S: Send> Send for SwissMap<K, V, S> {}
+unsafe impl<K: Sync, V: Sync, S: Sync> Sync for SwissMap<K, V, S> {}
+
+impl<K, V> SwissMap<K, V, RandomState> {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+}
+
+impl<K: Hash, V> SwissMap<K, V, RandomState> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> SwissMap<K, V, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            table: RawTable::new(),
+            hasher,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.items
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.items == 0
+    }
+
+    // the number of entries held without growing
+    pub fn capacity(&self) -> usize {
+        self.table.items + self.table.growth_left
+    }
+
+    pub fn clear(&mut self) {
+        self.table.clear()
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            iter: RawIter::new(&self.table),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            iter: RawIter::new(&self.table),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.iter().map(|(_, v)| v)
+    }
+}
+
+impl<K: Hash, V, S: BuildHasher> SwissMap<K, V, S> {
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        let mut map = Self::with_hasher(hasher);
+        map.reserve(capacity);
+        map
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        let hasher = &self.hasher;
+        self.table.reserve(additional, |k| hasher.hash_one(k))
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> SwissMap<K, V, S> {
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hasher.hash_one(&key);
+        if let Some(index) = self.table.find(hash, &key) {
+            let slot = unsafe { &mut *self.table.slot(index) };
+            return Some(mem::replace(&mut slot.1, value));
+        }
+        let hasher = &self.hasher;
+        self.table
+            .insert_new(hash, key, value, |k| hasher.hash_one(k));
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, v)| v)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.table.find(self.hasher.hash_one(key), key)?;
+        let slot = unsafe { &*self.table.slot(index) };
+        Some((&slot.0, &slot.1))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.table.find(self.hasher.hash_one(key), key)?;
+        Some(unsafe { &mut (*self.table.slot(index)).1 })
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.table.find(self.hasher.hash_one(key), key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.table.find(self.hasher.hash_one(key), key)?;
+        Some(self.table.erase(index))
+    }
+}
+
+impl<K, V, S: Default> Default for SwissMap<K, V, S> {
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for SwissMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for SwissMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        map.extend(iter);
+        map
+    }
+}
+
+// walks the full buckets a group at a time
+struct RawIter<K, V> {
+    slots: RawArray<(K, V)>,
+    ctrl: *const u8,
+    pos: usize,
+    bits: Mask,
+    len: usize,
+}
+
+impl<K, V> RawIter<K, V> {
+    fn new(table: &RawTable<K, V>) -> Self {
+        Self {
+            slots: unsafe { table.slots.shadow_clone() },
+            ctrl: table.ctrl.as_ptr(),
+            pos: 0,
+            bits: if table.items == 0 {
+                BitMask(0)
+            } else {
+                unsafe { table.group(0) }.match_full()
+            },
+            len: table.items,
+        }
+    }
+
+    fn next(&mut self) -> Option<*mut (K, V)> {
+        if self.len == 0 {
+            return None;
+        }
+        loop {
+            if let Some(bit) = self.bits.next() {
+                self.len -= 1;
+                return Some(unsafe { self.slots.offset(self.pos + bit) });
+            }
+            self.pos += GROUP_WIDTH;
+            self.bits = unsafe { Group::load(self.ctrl.add(self.pos)) }.match_full();
+        }
+    }
+}
+
+// ------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, K, V> {
+    iter: RawIter<K, V>,
+    _marker: PhantomData<&'a SwissMap<K, V>>,
+}
+
+unsafe impl<K: Sync, V: Sync> Send for Iter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Iter<'_, K, V> {}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|slot| unsafe { (&(*slot).0, &(*slot).1) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.iter.len, Some(self.iter.len))
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a SwissMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+// end: Iter
+// ------------------------------------
+
+// ------------------------------------
+// begin: IterMut
+
+pub struct IterMut<'a, K, V> {
+    iter: RawIter<K, V>,
+    _marker: PhantomData<&'a mut SwissMap<K, V>>,
+}
+
+unsafe impl<K: Sync, V: Send> Send for IterMut<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for IterMut<'_, K, V> {}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter
+            .next()
+            .map(|slot| unsafe { (&(*slot).0, &mut (*slot).1) })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.iter.len, Some(self.iter.len))
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut SwissMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+// end: IterMut
+// ------------------------------------
+
+// ------------------------------------
+// begin: IntoIter
+
+pub struct IntoIter<K, V> {
+    table: RawTable<K, V>,
+    index: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<(K, V)> {
+        if self.table.items == 0 {
+            return None;
+        }
+        loop {
+            let index = self.index;
+            self.index += 1;
+            if group::is_full(self.table.ctrl[index]) {
+                // the table drops only what is left
+                self.table.set_ctrl(index, EMPTY);
+                self.table.items -= 1;
+                return Some(unsafe { self.table.slot(index).read() });
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.table.items, Some(self.table.items))
+    }
+}
+
+impl<K, V, S> IntoIterator for SwissMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            table: self.table,
+            index: 0,
+        }
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+// end: IntoIter
+// ------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::SwissMap;
+
+    use crate::rng::Rng;
+
+    use std::collections::BTreeMap;
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    // keeps the key as the hash, so the tests control where entries land
+    #[derive(Default)]
+    struct Identity(u64);
+
+    impl Hasher for Identity {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &b in bytes {
+                self.0 = (self.0 << 8) | u64::from(b);
+            }
+        }
+
+        fn write_u64(&mut self, n: u64) {
+            self.0 = n;
+        }
+    }
+
+    type IdentityMap<V> = SwissMap<u64, V, BuildHasherDefault<Identity>>;
+
+    #[test]
+    fn test_swiss_map() {
+        let mut map = SwissMap::new();
+        assert_eq!(map.capacity(), 0);
+        assert_eq!(map.get("a"), None);
+        assert_eq!(map.insert("a".to_string(), 1), None);
+        assert_eq!(map.insert("b".to_string(), 2), None);
+        assert_eq!(map.insert("a".to_string(), 3), Some(1));
+        assert_eq!(map.capacity(), 14);
+        assert_eq!(map.get("a"), Some(&3));
+        *map.get_mut("b").unwrap() += 10;
+        assert_eq!(map.remove_entry("b"), Some(("b".to_string(), 12)));
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(map.len(), 1);
+
+        map.extend((0..100).map(|i| (i.to_string(), i)));
+        assert_eq!(map.len(), 101);
+        assert_eq!(map.values().sum::<i32>(), 4953);
+        for (_, v) in &mut map {
+            *v = 0;
+        }
+        let mut keys: Vec<_> = map.into_iter().map(|(k, v)| (v, k)).collect();
+        keys.sort_unstable();
+        assert_eq!(keys[0], (0, "0".to_string()));
+    }
+
+    #[test]
+    fn test_tombstones() {
+        let mut map = IdentityMap::default();
+        // the first group still has empty buckets, so removing leaves no tombstones
+        for i in 0..14 {
+            map.insert(i << 10, i);
+        }
+        assert_eq!(map.table.buckets(), 16);
+        for i in 0..13 {
+            assert_eq!(map.remove(&(i << 10)), Some(i));
+        }
+        assert_eq!(map.table.tombstones(), 0);
+
+        let mut map = IdentityMap::default();
+        for i in 0..56 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.table.buckets(), 64);
+        for i in 0..16 {
+            map.remove(&i);
+        }
+        assert_eq!(map.table.tombstones(), 16);
+        assert_eq!(map.capacity(), 40);
+
+        // keys probing the first group reuse its tombstones
+        for i in 64..80 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.table.tombstones(), 0);
+        assert_eq!(map.capacity(), 56);
+
+        // no growth is left, so filling an empty bucket rehashes in place
+        for i in 16..56 {
+            map.remove(&i);
+        }
+        assert_eq!(map.table.tombstones(), 40);
+        map.insert(56, 56);
+        assert_eq!(map.table.buckets(), 64);
+        assert_eq!(map.table.tombstones(), 0);
+        assert_eq!(map.len(), 17);
+        for i in (64..80).chain(Some(56)) {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+    }
+
+    #[test]
+    fn test_random_ops() {
+        let mut rng = Rng::new(40);
+        let mut map: IdentityMap<String> = SwissMap::default();
+        let mut expected = BTreeMap::new();
+        for _ in 0..50_000 {
+            // clusters the keys in a few groups and spreads them over the high bits
+            let key = (rng.next_u64() % 300).wrapping_mul(0x0101_0000_0000_0001);
+            let value = rng.next_u64().to_string();
+            match rng.next_u64() % 3 {
+                0 => assert_eq!(map.insert(key, value.clone()), expected.insert(key, value)),
+                1 => assert_eq!(map.remove(&key), expected.remove(&key)),
+                _ => assert_eq!(map.get(&key), expected.get(&key)),
+            }
+            assert_eq!(map.len(), expected.len());
+        }
+        let mut items: Vec<_> = map.iter().map(|(k, v)| (*k, v.clone())).collect();
+        items.sort_unstable();
+        assert!(items.into_iter().eq(expected.into_iter()));
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let mut map = SwissMap::<(), ()>::new();
+        assert_eq!(map.insert((), ()), None);
+        assert_eq!(map.insert((), ()), Some(()));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&()), Some(&()));
+        assert_eq!(map.remove(&()), Some(()));
+        assert!(map.is_empty());
+
+        // only the values are zero-sized, hashed through write
+        let mut set: SwissMap<String, (), BuildHasherDefault<Identity>> = SwissMap::default();
+        for i in 0..100 {
+            set.insert(i.to_string(), ());
+        }
+        assert_eq!(set.len(), 100);
+        assert!((0..100).all(|i| set.get(&i.to_string()).is_some()));
+        assert_eq!(set.into_iter().count(), 100);
+    }
+}
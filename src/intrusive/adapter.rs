@@ -0,0 +1,113 @@
+use super::Link;
+
+use std::ops::Deref;
+use std::pin::Pin;
+use std::ptr::NonNull;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Owning pointer which can be turned into a raw pointer while the value is linked.
+///
+/// # Safety
+/// The value must stay at the same address until the pointer is restored by `from_raw`.
+pub unsafe trait Pointer: Deref {
+    fn into_raw(self) -> *const Self::Target;
+
+    /// # Safety
+    /// `ptr` must be returned by `into_raw` and restored at most once.
+    unsafe fn from_raw(ptr: *const Self::Target) -> Self;
+}
+
+unsafe impl<T> Pointer for Box<T> {
+    fn into_raw(self) -> *const T {
+        Box::into_raw(self)
+    }
+
+    unsafe fn from_raw(ptr: *const T) -> Self {
+        Box::from_raw(ptr as *mut T)
+    }
+}
+
+unsafe impl<T> Pointer for Rc<T> {
+    fn into_raw(self) -> *const T {
+        Rc::into_raw(self)
+    }
+
+    unsafe fn from_raw(ptr: *const T) -> Self {
+        Rc::from_raw(ptr)
+    }
+}
+
+unsafe impl<T> Pointer for Arc<T> {
+    fn into_raw(self) -> *const T {
+        Arc::into_raw(self)
+    }
+
+    unsafe fn from_raw(ptr: *const T) -> Self {
+        Arc::from_raw(ptr)
+    }
+}
+
+unsafe impl<T> Pointer for Pin<&mut T> {
+    fn into_raw(self) -> *const T {
+        unsafe { Pin::into_inner_unchecked(self) as *const T }
+    }
+
+    unsafe fn from_raw(ptr: *const T) -> Self {
+        Pin::new_unchecked(&mut *(ptr as *mut T))
+    }
+}
+
+/// Maps a value to one of its link fields and back.
+/// Use `intrusive_adapter!` to implement it for a field.
+///
+/// # Safety
+/// `value` must be the inverse of `link`.
+pub unsafe trait Adapter {
+    type Value;
+    type Pointer: Pointer<Target = Self::Value>;
+
+    fn link(value: &Self::Value) -> &Link;
+
+    /// # Safety
+    /// `link` must be returned by `Self::link`.
+    unsafe fn value(link: NonNull<Link>) -> *const Self::Value;
+}
+
+#[macro_export]
+macro_rules! intrusive_adapter {
+    ($vis:vis $name:ident = $ptr:ty : $value:ty { $field:ident }) => {
+        $vis struct $name;
+
+        unsafe impl $crate::intrusive::Adapter for $name {
+            type Value = $value;
+            type Pointer = $ptr;
+
+            fn link(value: &$value) -> &$crate::intrusive::Link {
+                &value.$field
+            }
+
+            unsafe fn value(link: ::std::ptr::NonNull<$crate::intrusive::Link>) -> *const $value {
+                let offset = ::std::mem::offset_of!($value, $field);
+                (link.as_ptr() as *const u8).sub(offset) as *const $value
+            }
+        }
+    };
+    ($vis:vis $name:ident<$lt:lifetime> = $ptr:ty : $value:ty { $field:ident }) => {
+        $vis struct $name<$lt>(::std::marker::PhantomData<&$lt ()>);
+
+        unsafe impl<$lt> $crate::intrusive::Adapter for $name<$lt> {
+            type Value = $value;
+            type Pointer = $ptr;
+
+            fn link(value: &$value) -> &$crate::intrusive::Link {
+                &value.$field
+            }
+
+            unsafe fn value(link: ::std::ptr::NonNull<$crate::intrusive::Link>) -> *const $value {
+                let offset = ::std::mem::offset_of!($value, $field);
+                (link.as_ptr() as *const u8).sub(offset) as *const $value
+            }
+        }
+    };
+}
@@ -0,0 +1,79 @@
+use std::fmt;
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+// Link field embedded in a value, one for each list the value can be linked into.
+// A shared value (`Arc`) may be pushed into lists on several threads at once,
+// so the fields are atomics and a list claims the link before writing it.
+pub struct Link {
+    pub(crate) prev: LinkPtr,
+    pub(crate) next: LinkPtr,
+    linked: AtomicBool,
+}
+
+// only the list which claimed the link writes it, while it holds `&mut` of the list,
+// so relaxed accesses are enough, the claim orders them against other lists
+pub(crate) struct LinkPtr(AtomicPtr<Link>);
+
+impl LinkPtr {
+    const fn new() -> Self {
+        Self(AtomicPtr::new(ptr::null_mut()))
+    }
+
+    pub(crate) fn get(&self) -> Option<NonNull<Link>> {
+        NonNull::new(self.0.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn set(&self, link: Option<NonNull<Link>>) {
+        let ptr = link.map_or(ptr::null_mut(), NonNull::as_ptr);
+        self.0.store(ptr, Ordering::Relaxed)
+    }
+}
+
+impl Link {
+    pub const fn new() -> Self {
+        Self {
+            prev: LinkPtr::new(),
+            next: LinkPtr::new(),
+            linked: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_linked(&self) -> bool {
+        self.linked.load(Ordering::Acquire)
+    }
+
+    // returns false if the link is already in a list
+    pub(crate) fn claim(&self) -> bool {
+        self.linked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    pub(crate) fn unlinked(&self) {
+        self.prev.set(None);
+        self.next.set(None);
+        self.linked.store(false, Ordering::Release);
+    }
+}
+
+impl Default for Link {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// a clone is never linked, even if the original one is
+impl Clone for Link {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Link")
+            .field("linked", &self.is_linked())
+            .finish()
+    }
+}
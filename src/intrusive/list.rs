@@ -0,0 +1,399 @@
+use super::{Adapter, Link, Pointer};
+
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+// Double-linked circular list threaded through the link fields of its values.
+// The list holds the owning pointers of its values while they are linked.
+pub struct IntrusiveList<A: Adapter> {
+    head: Option<NonNull<Link>>,
+    len: usize,
+    _marker: PhantomData<A::Pointer>,
+}
+
+unsafe impl<A: Adapter> Send for IntrusiveList<A> where A::Pointer: Send {}
+unsafe impl<A: Adapter> Sync for IntrusiveList<A> where A::Value: Sync {}
+
+impl<A: Adapter> IntrusiveList<A> {
+    fn link_of(ptr: A::Pointer) -> NonNull<Link> {
+        let link = A::link(unsafe { &*ptr.into_raw() });
+        NonNull::from(link)
+    }
+
+    // cond: link is in self and unlinked
+    unsafe fn consume(link: NonNull<Link>) -> A::Pointer {
+        link.as_ref().unlinked();
+        A::Pointer::from_raw(A::value(link))
+    }
+
+    unsafe fn value<'a>(link: NonNull<Link>) -> &'a A::Value {
+        &*A::value(link)
+    }
+
+    // cond: link is not linked
+    unsafe fn link_before(link: NonNull<Link>, next: NonNull<Link>) {
+        let prev = next.as_ref().prev.get().unwrap();
+        link.as_ref().prev.set(Some(prev));
+        link.as_ref().next.set(Some(next));
+        prev.as_ref().next.set(Some(link));
+        next.as_ref().prev.set(Some(link));
+    }
+
+    // cond: link is in self
+    unsafe fn unlink(&mut self, link: NonNull<Link>) -> A::Pointer {
+        if self.len == 1 {
+            self.head = None;
+        } else {
+            let prev = link.as_ref().prev.get().unwrap();
+            let next = link.as_ref().next.get().unwrap();
+            prev.as_ref().next.set(Some(next));
+            next.as_ref().prev.set(Some(prev));
+            if self.head == Some(link) {
+                self.head = Some(next);
+            }
+        }
+        self.len -= 1;
+        Self::consume(link)
+    }
+
+    fn tail(&self) -> Option<NonNull<Link>> {
+        self.head
+            .map(|head| unsafe { head.as_ref().prev.get().unwrap() })
+    }
+}
+
+impl<A: Adapter> IntrusiveList<A> {
+    pub fn new() -> Self {
+        Self {
+            head: None,
+            len: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    // panics if the value is already linked, here or in another list
+    pub fn push_back(&mut self, ptr: A::Pointer) {
+        if !A::link(&*ptr).claim() {
+            panic!("value is already linked")
+        }
+        let link = Self::link_of(ptr);
+        match self.head {
+            None => unsafe {
+                link.as_ref().prev.set(Some(link));
+                link.as_ref().next.set(Some(link));
+                self.head = Some(link);
+            },
+            Some(head) => unsafe { Self::link_before(link, head) },
+        }
+        self.len += 1;
+    }
+
+    // panics if the value is already linked
+    pub fn push_front(&mut self, ptr: A::Pointer) {
+        self.push_back(ptr);
+        self.head = self.tail();
+    }
+
+    pub fn pop_back(&mut self) -> Option<A::Pointer> {
+        let tail = self.tail()?;
+        Some(unsafe { self.unlink(tail) })
+    }
+
+    pub fn pop_front(&mut self) -> Option<A::Pointer> {
+        let head = self.head?;
+        Some(unsafe { self.unlink(head) })
+    }
+
+    /// Unlinks `value` in O(1).
+    ///
+    /// # Safety
+    /// `value` must be linked in `self`.
+    pub unsafe fn remove(&mut self, value: &A::Value) -> A::Pointer {
+        let link = A::link(value);
+        debug_assert!(link.is_linked());
+        self.unlink(NonNull::from(link))
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn front(&self) -> Option<&A::Value> {
+        self.head.map(|head| unsafe { Self::value(head) })
+    }
+
+    pub fn back(&self) -> Option<&A::Value> {
+        self.tail().map(|tail| unsafe { Self::value(tail) })
+    }
+
+    pub fn iter(&self) -> Iter<'_, A> {
+        Iter {
+            head: self.head,
+            tail: self.tail(),
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<A: Adapter> Default for IntrusiveList<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Adapter> Drop for IntrusiveList<A> {
+    fn drop(&mut self) {
+        self.clear()
+    }
+}
+
+// ------------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, A: Adapter> {
+    head: Option<NonNull<Link>>,
+    tail: Option<NonNull<Link>>,
+    len: usize,
+    _marker: PhantomData<&'a IntrusiveList<A>>,
+}
+
+impl<'a, A: Adapter> Iterator for Iter<'a, A> {
+    type Item = &'a A::Value;
+    fn next(&mut self) -> Option<&'a A::Value> {
+        if self.len == 0 {
+            return None;
+        }
+        let link = self.head?;
+        self.len -= 1;
+        unsafe {
+            self.head = link.as_ref().next.get();
+            Some(IntrusiveList::<A>::value(link))
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, A: Adapter> IntoIterator for &'a IntrusiveList<A> {
+    type Item = &'a A::Value;
+    type IntoIter = Iter<'a, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, A: Adapter> DoubleEndedIterator for Iter<'a, A> {
+    fn next_back(&mut self) -> Option<&'a A::Value> {
+        if self.len == 0 {
+            return None;
+        }
+        let link = self.tail?;
+        self.len -= 1;
+        unsafe {
+            self.tail = link.as_ref().prev.get();
+            Some(IntrusiveList::<A>::value(link))
+        }
+    }
+}
+
+impl<'a, A: Adapter> ExactSizeIterator for Iter<'a, A> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<'a, A: Adapter> FusedIterator for Iter<'a, A> {}
+
+// end: Iter
+// ------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::IntrusiveList;
+    use crate::intrusive::Link;
+    use crate::intrusive_adapter;
+
+    use std::cell::Cell;
+    use std::pin::Pin;
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    struct Task {
+        id: u32,
+        run_link: Link,
+        wait_link: Link,
+        dropped: Rc<Cell<u32>>,
+    }
+
+    impl Drop for Task {
+        fn drop(&mut self) {
+            self.dropped.set(self.dropped.get() + 1);
+        }
+    }
+
+    impl Task {
+        fn new(id: u32, dropped: &Rc<Cell<u32>>) -> Self {
+            Self {
+                id,
+                run_link: Link::new(),
+                wait_link: Link::new(),
+                dropped: dropped.clone(),
+            }
+        }
+    }
+
+    intrusive_adapter!(RunBox = Box<Task>: Task { run_link });
+    intrusive_adapter!(RunRc = Rc<Task>: Task { run_link });
+    intrusive_adapter!(WaitRc = Rc<Task>: Task { wait_link });
+    intrusive_adapter!(RunPin<'a> = Pin<&'a mut Task>: Task { run_link });
+
+    struct Job {
+        id: u32,
+        link: Link,
+    }
+
+    intrusive_adapter!(JobArc = Arc<Job>: Job { link });
+
+    fn ids<'a>(iter: impl Iterator<Item = &'a Task>) -> Vec<u32> {
+        iter.map(|task| task.id).collect()
+    }
+
+    #[test]
+    fn test_intrusive_list() {
+        let dropped = Rc::new(Cell::new(0));
+
+        let mut list = <IntrusiveList<RunBox>>::new();
+        assert!(list.is_empty());
+        for id in 1..=3 {
+            list.push_back(Box::new(Task::new(id, &dropped)));
+        }
+        list.push_front(Box::new(Task::new(0, &dropped)));
+        assert_eq!(list.len(), 4);
+        assert_eq!(ids(list.iter()), [0, 1, 2, 3]);
+        assert_eq!(ids(list.iter().rev()), [3, 2, 1, 0]);
+
+        let second = list.iter().nth(1).unwrap() as *const Task;
+        let task = unsafe { list.remove(&*second) };
+        assert_eq!(task.id, 1);
+        assert!(!task.run_link.is_linked());
+        drop(task);
+        assert_eq!(dropped.get(), 1);
+
+        assert_eq!(list.pop_back().unwrap().id, 3);
+        assert_eq!(list.front().unwrap().id, 0);
+        assert_eq!(list.back().unwrap().id, 2);
+        drop(list);
+        assert_eq!(dropped.get(), 4);
+    }
+
+    #[test]
+    fn test_multiple_lists() {
+        let dropped = Rc::new(Cell::new(0));
+        let mut run = <IntrusiveList<RunRc>>::new();
+        let mut wait = <IntrusiveList<WaitRc>>::new();
+
+        let tasks: Vec<_> = (0..4).map(|id| Rc::new(Task::new(id, &dropped))).collect();
+        for task in &tasks {
+            run.push_back(task.clone());
+            if task.id % 2 == 0 {
+                wait.push_front(task.clone());
+            }
+        }
+        assert_eq!(ids(run.iter()), [0, 1, 2, 3]);
+        assert_eq!(ids(wait.iter()), [2, 0]);
+
+        let task = unsafe { run.remove(&tasks[2]) };
+        assert!(!task.run_link.is_linked());
+        assert!(task.wait_link.is_linked());
+        assert_eq!(Rc::strong_count(&task), 3);
+        drop(task);
+
+        assert_eq!(ids(run.iter()), [0, 1, 3]);
+        assert_eq!(ids(wait.iter()), [2, 0]);
+
+        drop(tasks);
+        drop(run);
+        assert_eq!(dropped.get(), 2);
+        drop(wait);
+        assert_eq!(dropped.get(), 4);
+
+        let mut jobs = <IntrusiveList<JobArc>>::new();
+        let job = Arc::new(Job {
+            id: 7,
+            link: Link::new(),
+        });
+        jobs.push_back(job.clone());
+        let handle = std::thread::spawn(move || jobs.pop_front().map(|job| job.id));
+        assert_eq!(handle.join().unwrap(), Some(7));
+        assert!(!job.link.is_linked());
+        assert_eq!(Arc::strong_count(&job), 1);
+
+        let mut a = Task::new(10, &dropped);
+        let mut b = Task::new(11, &dropped);
+        {
+            let mut list = <IntrusiveList<RunPin<'_>>>::new();
+            list.push_back(Pin::new(&mut a));
+            list.push_back(Pin::new(&mut b));
+            assert_eq!(ids(list.iter()), [10, 11]);
+            assert_eq!(list.pop_front().unwrap().id, 10);
+        }
+        assert!(!a.run_link.is_linked());
+        assert!(!b.run_link.is_linked());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_double_link() {
+        let dropped = Rc::new(Cell::new(0));
+        let task = Rc::new(Task::new(0, &dropped));
+        let mut list = <IntrusiveList<RunRc>>::new();
+        list.push_back(task.clone());
+        list.push_back(task);
+    }
+
+    #[test]
+    fn test_shared_across_threads() {
+        use std::sync::Barrier;
+
+        // lists on several threads race to link the same value, only one may win
+        let job = Arc::new(Job {
+            id: 0,
+            link: Link::new(),
+        });
+        let barrier = Arc::new(Barrier::new(8));
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let job = job.clone();
+                let barrier = barrier.clone();
+                std::thread::spawn(move || {
+                    let mut jobs = <IntrusiveList<JobArc>>::new();
+                    barrier.wait();
+                    jobs.push_back(job);
+                    jobs
+                })
+            })
+            .collect();
+        let lists: Vec<_> = handles
+            .into_iter()
+            .filter_map(|handle| handle.join().ok())
+            .collect();
+        assert_eq!(lists.len(), 1);
+        assert!(job.link.is_linked());
+        drop(lists);
+        assert!(!job.link.is_linked());
+        assert_eq!(Arc::strong_count(&job), 1);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod adapter;
+pub mod link;
+pub mod list;
+pub use adapter::{Adapter, Pointer};
+pub use link::Link;
+pub use list::IntrusiveList;
@@ -1,5 +1,13 @@
+mod epoch;
 mod raw;
+mod rng;
 
 pub mod linear_list;
 pub mod stack;
 pub mod deque;
+pub mod intrusive;
+pub mod ordered;
+pub mod async_queue;
+pub mod hash;
+pub mod cache;
+pub mod tree;
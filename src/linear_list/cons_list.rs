@@ -0,0 +1,197 @@
+use std::iter::FromIterator;
+use std::iter::FusedIterator;
+use std::rc::Rc;
+use std::sync::Arc;
+
+// Persistent singly-linked lists. `cons` shares the tail with the old version,
+// so every version stays valid and cloning is O(1).
+// `ConsList` shares tails through `Rc`, `SyncConsList` through `Arc`.
+macro_rules! cons_list {
+    ($list:ident, $node:ident, $iter:ident, $ptr:ident) => {
+        struct $node<T> {
+            elem: T,
+            next: Option<$ptr<$node<T>>>,
+            len: usize,
+        }
+
+        pub struct $list<T> {
+            head: Option<$ptr<$node<T>>>,
+        }
+
+        impl<T> $list<T> {
+            pub fn new() -> Self {
+                Self { head: None }
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.head.is_none()
+            }
+
+            pub fn len(&self) -> usize {
+                self.head.as_ref().map_or(0, |node| node.len)
+            }
+
+            pub fn cons(&self, elem: T) -> Self {
+                let node = $node {
+                    elem,
+                    next: self.head.clone(),
+                    len: self.len() + 1,
+                };
+                Self {
+                    head: Some($ptr::new(node)),
+                }
+            }
+
+            pub fn head(&self) -> Option<&T> {
+                self.head.as_ref().map(|node| &node.elem)
+            }
+
+            pub fn tail(&self) -> Option<Self> {
+                self.head.as_ref().map(|node| Self {
+                    head: node.next.clone(),
+                })
+            }
+
+            // whether both lists are the same version
+            pub fn ptr_eq(&self, other: &Self) -> bool {
+                match (&self.head, &other.head) {
+                    (Some(lhs), Some(rhs)) => $ptr::ptr_eq(lhs, rhs),
+                    (None, None) => true,
+                    _ => false,
+                }
+            }
+
+            pub fn iter(&self) -> $iter<'_, T> {
+                $iter {
+                    next: self.head.as_deref(),
+                }
+            }
+        }
+
+        impl<T> Clone for $list<T> {
+            fn clone(&self) -> Self {
+                Self {
+                    head: self.head.clone(),
+                }
+            }
+        }
+
+        impl<T> Default for $list<T> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<T> Drop for $list<T> {
+            fn drop(&mut self) {
+                // unlinks the unshared nodes one by one to avoid recursive drops
+                let mut head = self.head.take();
+                while let Some(node) = head {
+                    match $ptr::try_unwrap(node) {
+                        Ok(mut node) => head = node.next.take(),
+                        Err(_) => break,
+                    }
+                }
+            }
+        }
+
+        impl<T> FromIterator<T> for $list<T> {
+            fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+                let elems: Vec<T> = iter.into_iter().collect();
+                let mut list = Self::new();
+                for elem in elems.into_iter().rev() {
+                    list = list.cons(elem);
+                }
+                list
+            }
+        }
+
+        pub struct $iter<'a, T> {
+            next: Option<&'a $node<T>>,
+        }
+
+        impl<'a, T> Iterator for $iter<'a, T> {
+            type Item = &'a T;
+            fn next(&mut self) -> Option<&'a T> {
+                self.next.map(|node| {
+                    self.next = node.next.as_deref();
+                    &node.elem
+                })
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let len = self.next.map_or(0, |node| node.len);
+                (len, Some(len))
+            }
+        }
+
+        impl<'a, T> IntoIterator for &'a $list<T> {
+            type Item = &'a T;
+            type IntoIter = $iter<'a, T>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.iter()
+            }
+        }
+
+        impl<'a, T> ExactSizeIterator for $iter<'a, T> {}
+
+        impl<'a, T> FusedIterator for $iter<'a, T> {}
+    };
+}
+
+cons_list!(ConsList, RcNode, Iter, Rc);
+cons_list!(SyncConsList, ArcNode, SyncIter, Arc);
+
+#[cfg(test)]
+mod test {
+    use super::{ConsList, SyncConsList};
+
+    #[test]
+    fn test_cons_list() {
+        let empty = <ConsList<&str>>::new();
+        assert!(empty.is_empty());
+        assert_eq!(empty.head(), None);
+        assert!(empty.tail().is_none());
+
+        let global = empty.cons("x").cons("y");
+        let local = global.cons("z");
+        let shadow = global.cons("w");
+
+        assert_eq!(global.len(), 2);
+        assert_eq!(local.len(), 3);
+        assert_eq!(local.head(), Some(&"z"));
+        assert_eq!(local.iter().copied().collect::<Vec<_>>(), ["z", "y", "x"]);
+        assert_eq!(shadow.iter().copied().collect::<Vec<_>>(), ["w", "y", "x"]);
+
+        let parent = local.tail().unwrap();
+        assert!(parent.ptr_eq(&global));
+        assert!(parent.ptr_eq(&shadow.tail().unwrap()));
+        assert!(!local.ptr_eq(&shadow));
+
+        drop(global);
+        drop(local);
+        assert_eq!(parent.iter().len(), 2);
+        assert_eq!(shadow.tail().unwrap().head(), Some(&"y"));
+
+        let list: ConsList<u32> = (0..100_000).collect();
+        assert_eq!(list.head(), Some(&0));
+        let shared = list.tail().unwrap();
+        drop(list);
+        assert_eq!(shared.len(), 99_999);
+    }
+
+    #[test]
+    fn test_sync_cons_list() {
+        let list: SyncConsList<i32> = (1..=3).collect();
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let list = list.clone();
+                std::thread::spawn(move || list.cons(i).iter().sum::<i32>())
+            })
+            .collect();
+        let sums: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(sums, [6, 7, 8, 9]);
+        assert_eq!(list.len(), 3);
+    }
+}
@@ -0,0 +1,896 @@
+use super::sequence_list::SequenceList;
+
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
+const NIL: u32 = u32::MAX;
+
+#[derive(Clone)]
+struct Node<T> {
+    elem: T,
+    prev: u32,
+    next: u32,
+}
+
+#[derive(Clone)]
+enum Slot<T> {
+    Occupied(Node<T>),
+    // index of the next vacant slot
+    Vacant(u32),
+}
+
+// Double-linked circular list whose nodes live in one contiguous array.
+// Nodes are linked by u32 indices and vacant slots are recycled through a free list.
+#[derive(Clone)]
+pub struct IndexLinkedList<T> {
+    slots: SequenceList<Slot<T>>,
+    head: u32,
+    free: u32,
+    len: usize,
+}
+
+impl<T> IndexLinkedList<T> {
+    fn node(&self, idx: u32) -> &Node<T> {
+        match self.slots[idx as usize] {
+            Slot::Occupied(ref node) => node,
+            Slot::Vacant(_) => unreachable!(),
+        }
+    }
+
+    fn node_mut(&mut self, idx: u32) -> &mut Node<T> {
+        match self.slots[idx as usize] {
+            Slot::Occupied(ref mut node) => node,
+            Slot::Vacant(_) => unreachable!(),
+        }
+    }
+
+    fn tail(&self) -> u32 {
+        if self.len == 0 {
+            NIL
+        } else {
+            self.node(self.head).prev
+        }
+    }
+
+    fn alloc(&mut self, elem: T, prev: u32, next: u32) -> u32 {
+        let slot = Slot::Occupied(Node { elem, prev, next });
+        if self.free != NIL {
+            let idx = self.free;
+            match std::mem::replace(&mut self.slots[idx as usize], slot) {
+                Slot::Vacant(next_free) => self.free = next_free,
+                Slot::Occupied(_) => unreachable!(),
+            }
+            return idx;
+        }
+
+        let idx = self.slots.len();
+        if idx >= NIL as usize {
+            panic!("index linked list is full")
+        }
+        if idx == self.slots.capacity() {
+            self.slots.reserve(idx.max(4));
+        }
+        self.slots.push(slot);
+        idx as u32
+    }
+
+    fn dealloc(&mut self, idx: u32) -> T {
+        let slot = Slot::Vacant(self.free);
+        match std::mem::replace(&mut self.slots[idx as usize], slot) {
+            Slot::Occupied(node) => {
+                self.free = idx;
+                node.elem
+            }
+            Slot::Vacant(_) => unreachable!(),
+        }
+    }
+
+    // cond: self.len == 0
+    fn init(&mut self, elem: T) -> u32 {
+        let idx = self.alloc(elem, NIL, NIL);
+        let node = self.node_mut(idx);
+        node.prev = idx;
+        node.next = idx;
+        self.head = idx;
+        self.len = 1;
+        idx
+    }
+
+    // cond: next is occupied
+    // the head is not moved, so inserting before the head appends to the tail
+    fn insert_before(&mut self, next: u32, elem: T) -> u32 {
+        let prev = self.node(next).prev;
+        let idx = self.alloc(elem, prev, next);
+        self.node_mut(prev).next = idx;
+        self.node_mut(next).prev = idx;
+        self.len += 1;
+        idx
+    }
+
+    // cond: idx is occupied
+    fn unlink(&mut self, idx: u32) -> T {
+        if self.len == 1 {
+            self.head = NIL;
+        } else {
+            let (prev, next) = {
+                let node = self.node(idx);
+                (node.prev, node.next)
+            };
+            self.node_mut(prev).next = next;
+            self.node_mut(next).prev = prev;
+            if idx == self.head {
+                self.head = next;
+            }
+        }
+        self.len -= 1;
+        self.dealloc(idx)
+    }
+
+    fn find_index<P>(&self, mut pred: P) -> Option<u32>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let mut iter = RawIndexIter::from_list(self);
+        while let Some(idx) = iter.next_front(self.slots.as_ptr()) {
+            if pred(&self.node(idx).elem) {
+                return Some(idx);
+            }
+        }
+        None
+    }
+}
+
+impl<T> IndexLinkedList<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: SequenceList::new(capacity),
+            head: NIL,
+            free: NIL,
+            len: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.capacity()
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        if self.is_empty() {
+            self.init(elem);
+        } else {
+            self.insert_before(self.head, elem);
+        }
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        self.push_back(elem);
+        self.head = self.node(self.head).prev;
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.unlink(self.tail()))
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.unlink(self.head))
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.head = NIL;
+        self.free = NIL;
+        self.len = 0;
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            iter: RawIndexIter::from_list(self),
+            slots: self.slots.as_mut_ptr(),
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            iter: RawIndexIter::from_list(self),
+            list: self,
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(&self.node(self.head).elem)
+        }
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(&self.node(self.tail()).elem)
+        }
+    }
+
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.head,
+            list: self,
+        }
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor {
+            current: self.tail(),
+            list: self,
+        }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.head,
+            list: self,
+        }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.tail(),
+            list: self,
+        }
+    }
+
+    // cond: self is sorted by cmp
+    // elem is placed after all elements that are not greater than it
+    pub fn ordered_insert_by<F>(&mut self, elem: T, mut cmp: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        if self.is_empty() {
+            self.init(elem);
+            return;
+        }
+
+        let mut idx = self.head;
+        let mut pos = 0;
+        while pos < self.len {
+            let node = self.node(idx);
+            if cmp(&elem, &node.elem) == Ordering::Less {
+                break;
+            }
+            idx = node.next;
+            pos += 1;
+        }
+
+        // idx wraps around to head when elem is the greatest one
+        let new_idx = self.insert_before(idx, elem);
+        if pos == 0 {
+            self.head = new_idx;
+        }
+    }
+
+    pub fn find<P>(&self, pred: P) -> Option<&T>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        self.find_index(pred).map(|idx| &self.node(idx).elem)
+    }
+
+    pub fn remove_first<P>(&mut self, pred: P) -> Option<T>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let idx = self.find_index(pred)?;
+        Some(self.unlink(idx))
+    }
+
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.retain_mut(|elem| f(elem))
+    }
+
+    pub fn retain_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        self.extract_if(|elem| !f(elem)).for_each(drop)
+    }
+
+    // unlinks the matched elements lazily, the rest are kept when it is dropped
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, T, F>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        ExtractIf {
+            idx: self.head,
+            remain: self.len,
+            list: self,
+            pred,
+        }
+    }
+
+    // removes consecutive elements for which same_bucket(elem, prev) returns true
+    pub fn dedup_by<F>(&mut self, mut same_bucket: F)
+    where
+        F: FnMut(&mut T, &mut T) -> bool,
+    {
+        if self.len < 2 {
+            return;
+        }
+
+        let mut prev = self.head;
+        let mut idx = self.node(prev).next;
+        for _ in 1..self.len {
+            let next = self.node(idx).next;
+            let same = unsafe {
+                let slots = self.slots.as_mut_ptr();
+                match (
+                    &mut *slots.add(idx as usize),
+                    &mut *slots.add(prev as usize),
+                ) {
+                    (Slot::Occupied(lhs), Slot::Occupied(rhs)) => {
+                        same_bucket(&mut lhs.elem, &mut rhs.elem)
+                    }
+                    _ => unreachable!(),
+                }
+            };
+            if same {
+                self.unlink(idx);
+            } else {
+                prev = idx;
+            }
+            idx = next;
+        }
+    }
+}
+
+impl<T> IndexLinkedList<T>
+where
+    T: PartialEq,
+{
+    pub fn contains(&self, elem: &T) -> bool {
+        self.iter().any(|e| e == elem)
+    }
+
+    pub fn dedup(&mut self) {
+        self.dedup_by(|a, b| a == b)
+    }
+}
+
+impl<T> IndexLinkedList<T>
+where
+    T: Ord,
+{
+    pub fn ordered_insert(&mut self, elem: T) {
+        self.ordered_insert_by(elem, T::cmp)
+    }
+}
+
+impl<T> Default for IndexLinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<T> for IndexLinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let mut list = Self::with_capacity(iter.size_hint().0);
+        for elem in iter {
+            list.push_back(elem);
+        }
+        list
+    }
+}
+
+// --------------------------------
+// begin: IterOwned
+
+pub struct IterOwned<T>(IndexLinkedList<T>);
+
+impl<T> Iterator for IterOwned<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> IntoIterator for IndexLinkedList<T> {
+    type Item = T;
+    type IntoIter = IterOwned<T>;
+    fn into_iter(self) -> IterOwned<T> {
+        IterOwned(self)
+    }
+}
+
+impl<T> DoubleEndedIterator for IterOwned<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IterOwned<T> {
+    fn len(&self) -> usize {
+        self.0.len
+    }
+}
+
+impl<T> FusedIterator for IterOwned<T> {}
+
+// end: IterOwned
+// ------------------------------------------
+
+struct RawIndexIter {
+    head: u32,
+    tail: u32,
+    len: usize,
+}
+
+impl RawIndexIter {
+    fn from_list<T>(list: &IndexLinkedList<T>) -> Self {
+        Self {
+            head: list.head,
+            tail: list.tail(),
+            len: list.len,
+        }
+    }
+
+    // cond: slots points to the slots of the list
+    fn next_front<T>(&mut self, slots: *const Slot<T>) -> Option<u32> {
+        if self.len == 0 {
+            return None;
+        }
+        let idx = self.head;
+        self.len -= 1;
+        if let Slot::Occupied(ref node) = unsafe { &*slots.add(idx as usize) } {
+            self.head = node.next;
+        }
+        Some(idx)
+    }
+
+    // cond: slots points to the slots of the list
+    fn next_back<T>(&mut self, slots: *const Slot<T>) -> Option<u32> {
+        if self.len == 0 {
+            return None;
+        }
+        let idx = self.tail;
+        self.len -= 1;
+        if let Slot::Occupied(ref node) = unsafe { &*slots.add(idx as usize) } {
+            self.tail = node.prev;
+        }
+        Some(idx)
+    }
+}
+
+// ------------------------------------------
+// begin: IterMut
+
+pub struct IterMut<'a, T> {
+    iter: RawIndexIter,
+    slots: *mut Slot<T>,
+    _marker: PhantomData<&'a mut IndexLinkedList<T>>,
+}
+
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+impl<'a, T> IterMut<'a, T> {
+    fn elem(&self, idx: u32) -> &'a mut T {
+        match unsafe { &mut *self.slots.add(idx as usize) } {
+            Slot::Occupied(node) => &mut node.elem,
+            Slot::Vacant(_) => unreachable!(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.iter.next_front(self.slots).map(|idx| self.elem(idx))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.iter.len, Some(self.iter.len))
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut IndexLinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        self.iter.next_back(self.slots).map(|idx| self.elem(idx))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {
+    fn len(&self) -> usize {
+        self.iter.len
+    }
+}
+
+impl<'a, T> FusedIterator for IterMut<'a, T> {}
+
+// end: IterMut
+// ------------------------------------------
+
+// ------------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, T> {
+    iter: RawIndexIter,
+    list: &'a IndexLinkedList<T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        let list = self.list;
+        self.iter
+            .next_front(list.slots.as_ptr())
+            .map(|idx| &list.node(idx).elem)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.iter.len, Some(self.iter.len))
+    }
+}
+
+impl<'a, T> IntoIterator for &'a IndexLinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        let list = self.list;
+        self.iter
+            .next_back(list.slots.as_ptr())
+            .map(|idx| &list.node(idx).elem)
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {
+    fn len(&self) -> usize {
+        self.iter.len
+    }
+}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+// end: Iter
+// ------------------------------------------
+
+// ------------------------------------------
+// begin: ExtractIf
+
+pub struct ExtractIf<'a, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    list: &'a mut IndexLinkedList<T>,
+    idx: u32,
+    remain: usize,
+    pred: F,
+}
+
+impl<T, F> Iterator for ExtractIf<'_, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        while self.remain > 0 {
+            let idx = self.idx;
+            let node = self.list.node_mut(idx);
+            self.idx = node.next;
+            self.remain -= 1;
+            if (self.pred)(&mut node.elem) {
+                return Some(self.list.unlink(idx));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remain))
+    }
+}
+
+impl<T, F> FusedIterator for ExtractIf<'_, T, F> where F: FnMut(&mut T) -> bool {}
+
+// end: ExtractIf
+// ------------------------------------------
+
+// ------------------------------------------
+// begin: Cursor
+//
+// A cursor points to an element or to the "ghost" position between the back and the front.
+
+pub struct Cursor<'a, T> {
+    list: &'a IndexLinkedList<T>,
+    current: u32,
+}
+
+impl<T> Clone for Cursor<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            list: self.list,
+            current: self.current,
+        }
+    }
+}
+
+impl<'a, T> Cursor<'a, T> {
+    pub fn current(&self) -> Option<&'a T> {
+        if self.current == NIL {
+            None
+        } else {
+            Some(&self.list.node(self.current).elem)
+        }
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = next_index(self.list, self.current);
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = prev_index(self.list, self.current);
+    }
+
+    pub fn peek_next(&self) -> Option<&'a T> {
+        let idx = next_index(self.list, self.current);
+        if idx == NIL {
+            None
+        } else {
+            Some(&self.list.node(idx).elem)
+        }
+    }
+
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        let idx = prev_index(self.list, self.current);
+        if idx == NIL {
+            None
+        } else {
+            Some(&self.list.node(idx).elem)
+        }
+    }
+}
+
+pub struct CursorMut<'a, T> {
+    list: &'a mut IndexLinkedList<T>,
+    current: u32,
+}
+
+impl<'a, T> CursorMut<'a, T> {
+    pub fn current(&mut self) -> Option<&mut T> {
+        if self.current == NIL {
+            None
+        } else {
+            Some(&mut self.list.node_mut(self.current).elem)
+        }
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = next_index(self.list, self.current);
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = prev_index(self.list, self.current);
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        let idx = next_index(self.list, self.current);
+        if idx == NIL {
+            None
+        } else {
+            Some(&mut self.list.node_mut(idx).elem)
+        }
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        let idx = prev_index(self.list, self.current);
+        if idx == NIL {
+            None
+        } else {
+            Some(&mut self.list.node_mut(idx).elem)
+        }
+    }
+
+    pub fn as_cursor(&self) -> Cursor<'_, T> {
+        Cursor {
+            list: self.list,
+            current: self.current,
+        }
+    }
+
+    // inserts at the back when the cursor is at the ghost position
+    pub fn insert_before(&mut self, elem: T) {
+        if self.current == NIL {
+            self.list.push_back(elem);
+            return;
+        }
+        let idx = self.list.insert_before(self.current, elem);
+        if self.current == self.list.head {
+            self.list.head = idx;
+        }
+    }
+
+    // inserts at the front when the cursor is at the ghost position
+    pub fn insert_after(&mut self, elem: T) {
+        if self.current == NIL {
+            self.list.push_front(elem);
+            return;
+        }
+        let next = self.list.node(self.current).next;
+        self.list.insert_before(next, elem);
+    }
+
+    // removes the current element and moves to the next one
+    pub fn remove_current(&mut self) -> Option<T> {
+        if self.current == NIL {
+            return None;
+        }
+        let next = next_index(self.list, self.current);
+        let elem = self.list.unlink(self.current);
+        self.current = next;
+        Some(elem)
+    }
+}
+
+fn next_index<T>(list: &IndexLinkedList<T>, idx: u32) -> u32 {
+    if idx == NIL {
+        list.head
+    } else if idx == list.tail() {
+        NIL
+    } else {
+        list.node(idx).next
+    }
+}
+
+fn prev_index<T>(list: &IndexLinkedList<T>, idx: u32) -> u32 {
+    if idx == NIL {
+        list.tail()
+    } else if idx == list.head {
+        NIL
+    } else {
+        list.node(idx).prev
+    }
+}
+
+// end: Cursor
+// ------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::IndexLinkedList;
+
+    #[test]
+    fn test_index_linked_list() {
+        let mut list = <IndexLinkedList<String>>::new();
+        assert!(list.is_empty());
+        assert_eq!(list.capacity(), 0);
+
+        list.push_back(String::from("b"));
+        list.push_front(String::from("a"));
+        list.push_back(String::from("c"));
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.front().unwrap(), "a");
+        assert_eq!(list.back().unwrap(), "c");
+        assert_eq!(list.iter().rev().cloned().collect::<String>(), "cba");
+
+        for elem in &mut list {
+            elem.push('!');
+        }
+        assert_eq!(list.pop_front().unwrap(), "a!");
+        assert_eq!(list.pop_back().unwrap(), "c!");
+
+        // vacant slots are reused before the array grows
+        let capacity = list.capacity();
+        list.push_back(String::from("d"));
+        list.push_front(String::from("e"));
+        assert_eq!(list.capacity(), capacity);
+
+        let cloned = list.clone();
+        assert_eq!(cloned.into_iter().collect::<Vec<_>>(), ["e", "b!", "d"]);
+
+        list.clear();
+        assert!(list.is_empty());
+        assert_eq!(list.pop_front(), None);
+
+        let mut list: IndexLinkedList<i32> = (0..10).collect();
+        assert!(list.contains(&3));
+        assert_eq!(list.find(|&e| e > 6), Some(&7));
+        list.retain(|&e| e % 3 != 0);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [1, 2, 4, 5, 7, 8]);
+        assert_eq!(list.extract_if(|e| *e > 4).collect::<Vec<_>>(), [5, 7, 8]);
+        assert_eq!(list.remove_first(|&e| e == 2), Some(2));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [1, 4]);
+
+        let mut list = <IndexLinkedList<i32>>::new();
+        for &x in &[3, 1, 2, 2, 5, 0, 4] {
+            list.ordered_insert(x);
+        }
+        list.dedup();
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut list: IndexLinkedList<i32> = (1..=3).collect();
+
+        let mut cursor = list.cursor_front();
+        assert_eq!(cursor.current(), Some(&1));
+        assert_eq!(cursor.peek_prev(), None);
+        cursor.move_next();
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&3));
+        cursor.move_next();
+        assert_eq!(cursor.current(), None);
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&1));
+        assert_eq!(list.cursor_back().peek_next(), None);
+
+        let mut cursor = list.cursor_front_mut();
+        cursor.insert_before(0);
+        cursor.insert_after(10);
+        cursor.move_next();
+        assert_eq!(cursor.current(), Some(&mut 10));
+        *cursor.peek_next().unwrap() *= 10;
+        assert_eq!(cursor.remove_current(), Some(10));
+        assert_eq!(cursor.current(), Some(&mut 20));
+        assert_eq!(cursor.as_cursor().peek_prev(), Some(&1));
+
+        cursor.move_next();
+        assert_eq!(cursor.remove_current(), Some(3));
+        assert_eq!(cursor.current(), None);
+        cursor.insert_before(4);
+        cursor.insert_after(-1);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [-1, 0, 1, 20, 4]);
+
+        let mut cursor = list.cursor_back_mut();
+        while cursor.remove_current().is_some() {
+            cursor.move_prev();
+        }
+        assert!(list.is_empty());
+    }
+}
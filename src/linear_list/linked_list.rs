@@ -1,16 +1,29 @@
+use super::node_pool::{NodePool, PoolStats};
+
 use std::alloc::Layout;
+use std::cmp::Ordering;
 use std::iter::FromIterator;
 use std::iter::FusedIterator;
 use std::marker::PhantomData;
 use std::ptr::NonNull;
 
-struct Node<T> {
+pub(crate) struct Node<T> {
     elem: T,
     prev: NonNull<Node<T>>,
     next: NonNull<Node<T>>,
 }
 
 impl<T> Node<T> {
+    // cond: ptr is a node of a list which outlives 'a
+    pub(crate) unsafe fn elem<'a>(ptr: NonNull<Self>) -> &'a T {
+        &(*ptr.as_ptr()).elem
+    }
+
+    // cond: ptr is a node of a list which outlives 'a, and the element is not aliased
+    pub(crate) unsafe fn elem_mut<'a>(ptr: NonNull<Self>) -> &'a mut T {
+        &mut (*ptr.as_ptr()).elem
+    }
+
     unsafe fn alloc(elem: T, prev: NonNull<Node<T>>, next: NonNull<Node<T>>) -> NonNull<Self> {
         let layout = Layout::new::<Node<T>>();
         let ptr = std::alloc::alloc(layout) as *mut Node<T>;
@@ -26,12 +39,6 @@ impl<T> Node<T> {
         std::alloc::dealloc(ptr.as_ptr() as *mut u8, layout);
     }
 
-    unsafe fn consume(ptr: NonNull<Self>) -> T {
-        let elem = std::ptr::read(&ptr.as_ref().elem);
-        Node::dealloc(ptr);
-        elem
-    }
-
     // cond: prev is valid and next is valid
     unsafe fn delete(ptr: NonNull<Self>) {
         let mut prev_ptr = ptr.as_ref().prev;
@@ -40,11 +47,12 @@ impl<T> Node<T> {
         next_ptr.as_mut().prev = prev_ptr;
     }
 
-    unsafe fn insert(elem: T, mut prev: NonNull<Self>, mut next: NonNull<Self>) -> NonNull<Self> {
-        let node_ptr = Node::alloc(elem, prev, next);
-        prev.as_mut().next = node_ptr;
-        next.as_mut().prev = node_ptr;
-        node_ptr
+    // cond: prev and next of ptr are valid
+    unsafe fn link(ptr: NonNull<Self>) {
+        let mut prev_ptr = ptr.as_ref().prev;
+        let mut next_ptr = ptr.as_ref().next;
+        prev_ptr.as_mut().next = ptr;
+        next_ptr.as_mut().prev = ptr;
     }
 }
 
@@ -52,15 +60,56 @@ impl<T> Node<T> {
 pub struct LinkedList<T> {
     head: NonNull<Node<T>>,
     len: usize,
+    pool: Option<NodePool<Node<T>>>,
 }
 
 unsafe impl<T: Send> Send for LinkedList<T> {}
 unsafe impl<T: Sync> Sync for LinkedList<T> {}
 
 impl<T> LinkedList<T> {
+    unsafe fn alloc_node(
+        &mut self,
+        elem: T,
+        prev: NonNull<Node<T>>,
+        next: NonNull<Node<T>>,
+    ) -> NonNull<Node<T>> {
+        match self.pool {
+            Some(ref mut pool) => {
+                let ptr = pool.alloc();
+                ptr.as_ptr().write(Node { elem, prev, next });
+                ptr
+            }
+            None => Node::alloc(elem, prev, next),
+        }
+    }
+
+    unsafe fn dealloc_node(&mut self, ptr: NonNull<Node<T>>) {
+        match self.pool {
+            Some(ref mut pool) => pool.dealloc(ptr),
+            None => Node::dealloc(ptr),
+        }
+    }
+
+    unsafe fn consume_node(&mut self, ptr: NonNull<Node<T>>) -> T {
+        let elem = std::ptr::read(&ptr.as_ref().elem);
+        self.dealloc_node(ptr);
+        elem
+    }
+
+    unsafe fn insert_node(
+        &mut self,
+        elem: T,
+        prev: NonNull<Node<T>>,
+        next: NonNull<Node<T>>,
+    ) -> NonNull<Node<T>> {
+        let node_ptr = self.alloc_node(elem, prev, next);
+        Node::link(node_ptr);
+        node_ptr
+    }
+
     // cond: self.len == 0
     unsafe fn init(&mut self, elem: T) {
-        let mut node_ptr = Node::alloc(elem, NonNull::dangling(), NonNull::dangling());
+        let mut node_ptr = self.alloc_node(elem, NonNull::dangling(), NonNull::dangling());
         node_ptr.as_mut().prev = node_ptr;
         node_ptr.as_mut().next = node_ptr;
         self.head = node_ptr;
@@ -72,7 +121,7 @@ impl<T> LinkedList<T> {
         let head_ptr = self.head;
         self.len = 0;
         self.head = NonNull::dangling();
-        Node::consume(head_ptr)
+        self.consume_node(head_ptr)
     }
 }
 
@@ -81,9 +130,24 @@ impl<T> LinkedList<T> {
         Self {
             head: NonNull::dangling(),
             len: 0,
+            pool: None,
         }
     }
 
+    // recycles nodes through a pool which grows in slabs,
+    // starting with one slab of `capacity` nodes
+    pub fn with_pool(capacity: usize) -> Self {
+        Self {
+            head: NonNull::dangling(),
+            len: 0,
+            pool: Some(NodePool::new(capacity)),
+        }
+    }
+
+    pub fn pool_stats(&self) -> Option<PoolStats> {
+        self.pool.as_ref().map(NodePool::stats)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.len == 0
     }
@@ -99,7 +163,7 @@ impl<T> LinkedList<T> {
             unsafe {
                 let head_ptr = self.head;
                 let tail_ptr = head_ptr.as_ref().prev;
-                Node::insert(elem, tail_ptr, head_ptr);
+                self.insert_node(elem, tail_ptr, head_ptr);
             }
             self.len += 1;
         }
@@ -118,7 +182,7 @@ impl<T> LinkedList<T> {
                 self.len -= 1;
                 let tail_ptr = self.head.as_ref().prev;
                 Node::delete(tail_ptr);
-                Some(Node::consume(tail_ptr))
+                Some(self.consume_node(tail_ptr))
             },
         }
     }
@@ -132,7 +196,7 @@ impl<T> LinkedList<T> {
                 self.len -= 1;
                 self.head = ptr.as_ref().next;
                 Node::delete(ptr);
-                Some(Node::consume(ptr))
+                Some(self.consume_node(ptr))
             },
         }
     }
@@ -146,20 +210,20 @@ impl<T> LinkedList<T> {
             unsafe {
                 let next = ptr.as_mut().next;
                 std::ptr::drop_in_place(&mut ptr.as_mut().elem);
-                Node::dealloc(ptr);
+                self.dealloc_node(ptr);
                 ptr = next;
             }
         }
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
             iter: RawNodeIter::from_linked_list(self),
             _marker: PhantomData,
         }
     }
 
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             iter: RawNodeIter::from_linked_list(self),
             _marker: PhantomData,
@@ -184,6 +248,62 @@ impl<T> LinkedList<T> {
             }
         }
     }
+
+    pub fn split_off(&mut self, at: usize) -> Self {
+        if at > self.len {
+            panic!("index out of bounds")
+        }
+        if at == self.len {
+            return Self::new();
+        }
+
+        if self.pool.is_some() {
+            let mut other = Self::new();
+            for _ in at..self.len {
+                other.push_front(self.pop_back().unwrap());
+            }
+            return other;
+        }
+        if at == 0 {
+            return std::mem::take(self);
+        }
+
+        let first = unsafe {
+            let mut first = self.head;
+            if at <= self.len / 2 {
+                for _ in 0..at {
+                    first = first.as_ref().next;
+                }
+            } else {
+                for _ in at..self.len {
+                    first = first.as_ref().prev;
+                }
+            }
+
+            let mut head = self.head;
+            let mut tail = head.as_ref().prev;
+            let mut last = first.as_ref().prev;
+            first.as_mut().prev = tail;
+            tail.as_mut().next = first;
+            last.as_mut().next = head;
+            head.as_mut().prev = last;
+            first
+        };
+
+        let other = Self {
+            head: first,
+            len: self.len - at,
+            pool: None,
+        };
+        self.len = at;
+        other
+    }
+}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T> Drop for LinkedList<T> {
@@ -192,32 +312,310 @@ impl<T> Drop for LinkedList<T> {
     }
 }
 
+impl<T> LinkedList<T>
+where
+    T: PartialEq,
+{
+    pub fn contains(&self, elem: &T) -> bool {
+        self.iter().any(|e| e == elem)
+    }
+
+    pub fn dedup(&mut self) {
+        self.dedup_by(|a, b| a == b)
+    }
+}
+
 impl<T> LinkedList<T>
 where
     T: Ord,
 {
     pub fn ordered_insert(&mut self, elem: T) {
+        self.ordered_insert_by(elem, T::cmp)
+    }
+}
+
+impl<T> LinkedList<T> {
+    // cond: self is sorted by cmp
+    // elem is placed after all elements that are not greater than it
+    pub fn ordered_insert_by<F>(&mut self, elem: T, mut cmp: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
         if self.is_empty() {
             unsafe { self.init(elem) };
-        } else {
-            let mut ptr = self.head;
-            for _ in 0..self.len {
-                let rhs = unsafe { &ptr.as_ref().elem };
-                if &elem > rhs {
-                    break;
-                }
-                ptr = unsafe { ptr.as_ref().next };
+            return;
+        }
+
+        let mut ptr = self.head;
+        let mut pos = 0;
+        while pos < self.len {
+            let rhs = unsafe { &ptr.as_ref().elem };
+            if cmp(&elem, rhs) == Ordering::Less {
+                break;
             }
+            ptr = unsafe { ptr.as_ref().next };
+            pos += 1;
+        }
+
+        // ptr wraps around to head when elem is the greatest one
+        unsafe {
+            let prev_ptr = ptr.as_ref().prev;
+            let node_ptr = self.insert_node(elem, prev_ptr, ptr);
+            if pos == 0 {
+                self.head = node_ptr;
+            }
+        }
+        self.len += 1;
+    }
+
+    pub fn find<P>(&self, pred: P) -> Option<&T>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        self.find_node(pred)
+            .map(|ptr| unsafe { &(*ptr.as_ptr()).elem })
+    }
 
+    pub fn remove_first<P>(&mut self, pred: P) -> Option<T>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let node = self.find_node(pred)?;
+        Some(unsafe { self.unlink_node(node) })
+    }
+
+    pub fn retain<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.retain_mut(|elem| f(elem))
+    }
+
+    pub fn retain_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        self.extract_if(|elem| !f(elem)).for_each(drop)
+    }
+
+    // unlinks the matched elements lazily, the rest are kept when it is dropped
+    pub fn extract_if<F>(&mut self, pred: F) -> ExtractIf<'_, T, F>
+    where
+        F: FnMut(&mut T) -> bool,
+    {
+        ExtractIf {
+            ptr: self.head,
+            remain: self.len,
+            list: self,
+            pred,
+        }
+    }
+
+    // removes consecutive elements for which same_bucket(elem, prev) returns true
+    pub fn dedup_by<F>(&mut self, mut same_bucket: F)
+    where
+        F: FnMut(&mut T, &mut T) -> bool,
+    {
+        if self.len < 2 {
+            return;
+        }
+
+        let mut prev = self.head;
+        let mut ptr = unsafe { prev.as_ref().next };
+        for _ in 1..self.len {
             unsafe {
-                let next_ptr = ptr.as_ref().next;
-                let prev_ptr = ptr;
-                Node::insert(elem, prev_ptr, next_ptr);
+                let next = ptr.as_ref().next;
+                if same_bucket(&mut (*ptr.as_ptr()).elem, &mut (*prev.as_ptr()).elem) {
+                    self.unlink_node(ptr);
+                } else {
+                    prev = ptr;
+                }
+                ptr = next;
             }
+        }
+    }
 
-            self.len += 1;
+    pub(crate) fn front_node(&self) -> Option<NonNull<Node<T>>> {
+        if self.len == 0 {
+            None
+        } else {
+            Some(self.head)
+        }
+    }
+
+    pub(crate) fn back_node(&self) -> Option<NonNull<Node<T>>> {
+        if self.len == 0 {
+            None
+        } else {
+            Some(unsafe { self.head.as_ref().prev })
         }
     }
+
+    // cond: node belongs to self
+    pub(crate) unsafe fn next_node(&self, node: NonNull<Node<T>>) -> Option<NonNull<Node<T>>> {
+        let next = node.as_ref().next;
+        if next == self.head {
+            None
+        } else {
+            Some(next)
+        }
+    }
+
+    // cond: node belongs to self
+    pub(crate) unsafe fn prev_node(&self, node: NonNull<Node<T>>) -> Option<NonNull<Node<T>>> {
+        if node == self.head {
+            None
+        } else {
+            Some(node.as_ref().prev)
+        }
+    }
+
+    // cond: node belongs to self
+    pub(crate) unsafe fn insert_after_node(
+        &mut self,
+        node: NonNull<Node<T>>,
+        elem: T,
+    ) -> NonNull<Node<T>> {
+        let next = node.as_ref().next;
+        let new_node = self.insert_node(elem, node, next);
+        self.len += 1;
+        new_node
+    }
+
+    pub(crate) fn find_node<P>(&self, mut pred: P) -> Option<NonNull<Node<T>>>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        let mut iter = RawNodeIter::from_linked_list(self);
+        while let Some(ptr) = iter.next_front() {
+            if pred(unsafe { &ptr.as_ref().elem }) {
+                return Some(ptr);
+            }
+        }
+        None
+    }
+
+    // cond: node belongs to self
+    pub(crate) unsafe fn unlink_node(&mut self, node: NonNull<Node<T>>) -> T {
+        if self.len == 1 {
+            return self.deinit();
+        }
+        if node == self.head {
+            self.head = node.as_ref().next;
+        }
+        self.len -= 1;
+        Node::delete(node);
+        self.consume_node(node)
+    }
+
+    pub(crate) fn push_front_node(&mut self, elem: T) -> NonNull<Node<T>> {
+        self.push_front(elem);
+        self.head
+    }
+
+    // relinks the node at the front without reallocating it
+    // cond: node belongs to self
+    pub(crate) unsafe fn move_to_front(&mut self, mut node: NonNull<Node<T>>) {
+        if node == self.head {
+            return;
+        }
+        Node::delete(node);
+        node.as_mut().prev = self.head.as_ref().prev;
+        node.as_mut().next = self.head;
+        Node::link(node);
+        self.head = node;
+    }
+
+    // moves the node of other to the front of self, it is relinked without
+    // reallocation unless one of the lists owns a pool
+    // cond: node belongs to other
+    pub(crate) unsafe fn transfer_to_front(
+        &mut self,
+        other: &mut Self,
+        mut node: NonNull<Node<T>>,
+    ) -> NonNull<Node<T>> {
+        if self.pool.is_some() || other.pool.is_some() {
+            let elem = other.unlink_node(node);
+            return self.push_front_node(elem);
+        }
+        if other.len == 1 {
+            other.head = NonNull::dangling();
+        } else if node == other.head {
+            other.head = node.as_ref().next;
+        }
+        other.len -= 1;
+        Node::delete(node);
+
+        if self.len == 0 {
+            node.as_mut().prev = node;
+            node.as_mut().next = node;
+        } else {
+            node.as_mut().prev = self.head.as_ref().prev;
+            node.as_mut().next = self.head;
+            Node::link(node);
+        }
+        self.head = node;
+        self.len += 1;
+        node
+    }
+
+    // cond: both self and other are sorted by cmp
+    // moves all elements of other into self, nodes are relinked without
+    // reallocation unless one of the lists owns a pool
+    pub(crate) fn merge_by<F>(&mut self, other: &mut Self, mut cmp: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let relink = self.pool.is_none() && other.pool.is_none();
+        if other.is_empty() {
+            return;
+        }
+        if self.is_empty() && relink {
+            std::mem::swap(self, other);
+            return;
+        }
+        if self.is_empty() {
+            let elem = other.pop_front().unwrap();
+            unsafe { self.init(elem) };
+        }
+
+        let mut lhs = self.head;
+        let mut lhs_len = self.len;
+        let mut rhs = other.head;
+        let rhs_len = other.len;
+
+        for _ in 0..rhs_len {
+            unsafe {
+                while lhs_len > 0 && cmp(&rhs.as_ref().elem, &lhs.as_ref().elem) != Ordering::Less {
+                    lhs = lhs.as_ref().next;
+                    lhs_len -= 1;
+                }
+
+                // lhs is the head again once all nodes of self are passed
+                let next = lhs;
+                let prev = next.as_ref().prev;
+                let node = if relink {
+                    let mut node = rhs;
+                    rhs = rhs.as_ref().next;
+                    node.as_mut().prev = prev;
+                    node.as_mut().next = next;
+                    Node::link(node);
+                    node
+                } else {
+                    let elem = other.pop_front().unwrap();
+                    rhs = other.head;
+                    self.insert_node(elem, prev, next)
+                };
+                if lhs_len > 0 && lhs == self.head {
+                    self.head = node;
+                }
+            }
+        }
+
+        self.len += rhs_len;
+        other.head = NonNull::dangling();
+        other.len = 0;
+    }
 }
 
 impl<T> FromIterator<T> for LinkedList<T> {
@@ -434,6 +832,48 @@ impl<'a, T> FusedIterator for Iter<'a, T> {}
 // end: RefIter
 // ------------------------------------------
 
+// ------------------------------------------
+// begin: ExtractIf
+
+pub struct ExtractIf<'a, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    list: &'a mut LinkedList<T>,
+    ptr: NonNull<Node<T>>,
+    remain: usize,
+    pred: F,
+}
+
+impl<T, F> Iterator for ExtractIf<'_, T, F>
+where
+    F: FnMut(&mut T) -> bool,
+{
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        while self.remain > 0 {
+            unsafe {
+                let ptr = self.ptr;
+                self.ptr = ptr.as_ref().next;
+                self.remain -= 1;
+                if (self.pred)(&mut (*ptr.as_ptr()).elem) {
+                    return Some(self.list.unlink_node(ptr));
+                }
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remain))
+    }
+}
+
+impl<T, F> FusedIterator for ExtractIf<'_, T, F> where F: FnMut(&mut T) -> bool {}
+
+// end: ExtractIf
+// ------------------------------------------
+
 #[cfg(test)]
 mod test {
     use super::LinkedList;
@@ -482,4 +922,131 @@ mod test {
         }
         drop(list);
     }
+
+    #[test]
+    fn test_ordered_insert() {
+        let mut list = <LinkedList<i32>>::new();
+        for &x in &[3, 1, 2, 5, 0, 4, 5] {
+            list.ordered_insert(x);
+        }
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4, 5, 5]
+        );
+        assert_eq!(list.front(), Some(&0));
+        assert_eq!(list.back(), Some(&5));
+
+        let mut list = <LinkedList<i32>>::new();
+        for x in (0..5).rev() {
+            list.ordered_insert(x);
+        }
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut list: LinkedList<i32> = (0..10).collect();
+        assert!(list.contains(&3));
+        assert_eq!(list.find(|&e| e > 6), Some(&7));
+        assert_eq!(list.find(|&e| e > 9), None);
+
+        list.retain(|&e| e != 0 && e != 5);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            [1, 2, 3, 4, 6, 7, 8, 9]
+        );
+        assert_eq!(list.front(), Some(&1));
+
+        list.retain_mut(|e| {
+            *e *= 10;
+            *e != 90
+        });
+        assert_eq!(list.back(), Some(&80));
+
+        assert_eq!(list.extract_if(|e| *e % 20 == 0).next(), Some(20));
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            [10, 30, 40, 60, 70, 80]
+        );
+
+        let odd: Vec<_> = list.extract_if(|e| *e % 20 != 0).collect();
+        assert_eq!(odd, [10, 30, 70]);
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [40, 60, 80]);
+
+        assert_eq!(list.remove_first(|&e| e > 50), Some(60));
+        assert_eq!(list.remove_first(|&e| e > 90), None);
+        assert_eq!(list.len(), 2);
+
+        list.retain(|_| false);
+        assert!(list.is_empty());
+
+        let mut list: LinkedList<i32> = vec![1, 1, 2, 3, 3, 3, 1, 4, 4].into_iter().collect();
+        list.dedup();
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), [1, 2, 3, 1, 4]);
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut list: LinkedList<i32> = (0..10).collect();
+        let mut tail = list.split_off(7);
+        assert_eq!(tail.iter().copied().collect::<Vec<_>>(), [7, 8, 9]);
+        let mid = list.split_off(2);
+        assert_eq!(mid.iter().copied().collect::<Vec<_>>(), [2, 3, 4, 5, 6]);
+        assert_eq!(list.iter().rev().copied().collect::<Vec<_>>(), [1, 0]);
+        assert!(list.split_off(2).is_empty());
+        let all = tail.split_off(0);
+        assert!(tail.is_empty());
+        assert_eq!(all.len(), 3);
+
+        let mut pooled = <LinkedList<i32>>::with_pool(4);
+        for i in 0..4 {
+            pooled.push_back(i);
+        }
+        let other = pooled.split_off(1);
+        assert_eq!(other.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(pooled.pool_stats().unwrap().in_use, 1);
+    }
+
+    #[test]
+    fn test_pool() {
+        assert_eq!(<LinkedList<i32>>::new().pool_stats(), None);
+
+        let mut list = <LinkedList<String>>::with_pool(4);
+        for i in 0..4 {
+            list.push_back(i.to_string());
+        }
+        let stats = list.pool_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses), (4, 0));
+        assert_eq!((stats.slabs, stats.capacity, stats.in_use), (1, 4, 4));
+
+        list.push_front(String::from("-1"));
+        let stats = list.pool_stats().unwrap();
+        assert_eq!((stats.misses, stats.slabs, stats.capacity), (1, 2, 8));
+
+        for _ in 0..1000 {
+            let elem = list.pop_front().unwrap();
+            list.push_back(elem);
+        }
+        let stats = list.pool_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses), (1004, 1));
+        assert_eq!(stats.in_use, 5);
+
+        list.retain(|e| e != "2");
+        assert_eq!(list.pool_stats().unwrap().in_use, 4);
+        list.clear();
+        assert_eq!(list.pool_stats().unwrap().in_use, 0);
+
+        let mut lhs = <LinkedList<i32>>::with_pool(0);
+        let mut rhs: LinkedList<i32> = vec![1, 3, 5].into_iter().collect();
+        lhs.merge_by(&mut rhs, i32::cmp);
+        assert!(rhs.is_empty());
+        lhs.push_back(6);
+        let mut rhs: LinkedList<i32> = vec![0, 4, 7].into_iter().collect();
+        lhs.merge_by(&mut rhs, i32::cmp);
+        assert_eq!(
+            lhs.iter().copied().collect::<Vec<_>>(),
+            [0, 1, 3, 4, 5, 6, 7]
+        );
+        assert_eq!(lhs.pool_stats().unwrap().in_use, 7);
+    }
 }
@@ -1,4 +1,16 @@
+pub mod cons_list;
+pub mod index_linked_list;
 pub mod linked_list;
+mod node_pool;
 pub mod sequence_list;
+pub mod singly_linked_list;
+pub mod sorted_linked_list;
+pub mod unrolled_list;
+pub use cons_list::{ConsList, SyncConsList};
+pub use index_linked_list::IndexLinkedList;
 pub use linked_list::LinkedList;
+pub use node_pool::PoolStats;
 pub use sequence_list::SequenceList;
+pub use singly_linked_list::SinglyLinkedList;
+pub use sorted_linked_list::SortedLinkedList;
+pub use unrolled_list::UnrolledList;
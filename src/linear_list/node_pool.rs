@@ -0,0 +1,97 @@
+use crate::raw::RawArray;
+
+use std::mem::ManuallyDrop;
+use std::ptr::NonNull;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    // allocations served by the free list
+    pub hits: u64,
+    // allocations that had to grow the pool by a new slab
+    pub misses: u64,
+    pub slabs: usize,
+    pub capacity: usize,
+    pub in_use: usize,
+}
+
+#[repr(C)]
+union Slot<N> {
+    // only used for the size and alignment of the slot
+    _node: ManuallyDrop<N>,
+    next: Option<NonNull<Slot<N>>>,
+}
+
+// Fixed-size allocator recycling freed nodes through an intrusive free list.
+// Slabs are never returned to the global allocator until the pool is dropped.
+pub(crate) struct NodePool<N> {
+    slabs: Vec<RawArray<Slot<N>>>,
+    free: Option<NonNull<Slot<N>>>,
+    stats: PoolStats,
+}
+
+impl<N> NodePool<N> {
+    pub fn new(capacity: usize) -> Self {
+        let mut pool = Self {
+            slabs: Vec::new(),
+            free: None,
+            stats: PoolStats::default(),
+        };
+        if capacity > 0 {
+            pool.grow(capacity);
+        }
+        pool
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.stats
+    }
+
+    fn grow(&mut self, slab_size: usize) {
+        let slab = unsafe { RawArray::<Slot<N>>::alloc(slab_size) };
+        for i in (0..slab_size).rev() {
+            unsafe {
+                let slot = slab.offset(i);
+                slot.write(Slot { next: self.free });
+                self.free = Some(NonNull::new_unchecked(slot));
+            }
+        }
+        self.slabs.push(slab);
+        self.stats.slabs += 1;
+        self.stats.capacity += slab_size;
+    }
+
+    // the returned node is uninitialized
+    pub fn alloc(&mut self) -> NonNull<N> {
+        let slot = match self.free {
+            Some(slot) => {
+                self.stats.hits += 1;
+                slot
+            }
+            None => {
+                self.stats.misses += 1;
+                // each new slab doubles the capacity of the pool
+                self.grow(self.stats.capacity.max(1));
+                self.free.unwrap()
+            }
+        };
+        self.free = unsafe { slot.as_ref().next };
+        self.stats.in_use += 1;
+        slot.cast()
+    }
+
+    // cond: node is allocated by self and its content is dropped or moved out
+    pub unsafe fn dealloc(&mut self, node: NonNull<N>) {
+        let slot = node.cast::<Slot<N>>();
+        slot.as_ptr().write(Slot { next: self.free });
+        self.free = Some(slot);
+        self.stats.in_use -= 1;
+    }
+}
+
+impl<N> Drop for NodePool<N> {
+    fn drop(&mut self) {
+        for slab in &mut self.slabs {
+            unsafe { slab.dealloc() }
+        }
+    }
+}
@@ -7,7 +7,7 @@ use std::ptr::{drop_in_place, NonNull};
 pub struct SequenceList<T> {
     raw: RawArray<T>,
     len: usize,
-    // invariant: len <= cap <= isize::max_value()
+    // invariant: len <= cap <= isize::MAX
 }
 
 unsafe impl<T: Send> Send for SequenceList<T> {}
@@ -31,6 +31,15 @@ impl<T> SequenceList<T> {
         self.raw.cap
     }
 
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len.checked_add(additional).expect("capacity overflow");
+        if required <= self.raw.cap {
+            return;
+        }
+        let new_cap = required.max(self.raw.cap.saturating_mul(2));
+        unsafe { self.raw.realloc(new_cap) };
+    }
+
     pub fn push(&mut self, elem: T) {
         if self.len == self.raw.cap {
             panic!("sequence list is full")
@@ -52,7 +61,12 @@ impl<T> SequenceList<T> {
     pub fn clear(&mut self) {
         let len = self.len;
         self.len = 0;
-        unsafe { drop_in_place(std::slice::from_raw_parts_mut(self.raw.arr.as_ptr(), len)) };
+        unsafe {
+            drop_in_place(std::ptr::slice_from_raw_parts_mut(
+                self.raw.arr.as_ptr(),
+                len,
+            ))
+        };
     }
 
     pub fn insert(&mut self, index: usize, elem: T) {
@@ -69,6 +83,35 @@ impl<T> SequenceList<T> {
         }
     }
 
+    // moves all elements of other to the end of self
+    pub fn append(&mut self, other: &mut Self) {
+        if self.raw.cap - self.len < other.len {
+            panic!("sequence list is full")
+        }
+        unsafe {
+            let dst = self.raw.offset(self.len);
+            std::ptr::copy_nonoverlapping(other.raw.arr.as_ptr(), dst, other.len);
+        }
+        self.len += other.len;
+        other.len = 0;
+    }
+
+    // the returned list has the same capacity as self
+    pub fn split_off(&mut self, at: usize) -> Self {
+        if at > self.len {
+            panic!("index out of bounds")
+        }
+        let mut other = Self::new(self.raw.cap);
+        let count = self.len - at;
+        unsafe {
+            let src = self.raw.offset(at);
+            std::ptr::copy_nonoverlapping(src, other.raw.arr.as_ptr(), count);
+        }
+        self.len = at;
+        other.len = count;
+        other
+    }
+
     pub fn remove(&mut self, index: usize) -> T {
         if index >= self.len {
             panic!("index out of bounds")
@@ -88,22 +131,35 @@ impl<T> SequenceList<T> {
 impl<T> Drop for SequenceList<T> {
     fn drop(&mut self) {
         unsafe {
-            drop_in_place(self.raw.as_slice_mut());
+            drop_in_place(std::ptr::slice_from_raw_parts_mut(
+                self.raw.arr.as_ptr(),
+                self.len,
+            ));
             self.raw.dealloc();
         }
     }
 }
 
+impl<T: Clone> Clone for SequenceList<T> {
+    fn clone(&self) -> Self {
+        let mut list = Self::new(self.capacity());
+        for elem in self.iter() {
+            list.push(elem.clone());
+        }
+        list
+    }
+}
+
 impl<T> Deref for SequenceList<T> {
     type Target = [T];
     fn deref(&self) -> &[T] {
-        unsafe { self.raw.as_slice() }
+        unsafe { std::slice::from_raw_parts(self.raw.arr.as_ptr(), self.len) }
     }
 }
 
 impl<T> DerefMut for SequenceList<T> {
     fn deref_mut(&mut self) -> &mut [T] {
-        unsafe { self.raw.as_slice_mut() }
+        unsafe { std::slice::from_raw_parts_mut(self.raw.arr.as_ptr(), self.len) }
     }
 }
 
@@ -139,7 +195,10 @@ pub struct IterOwned<T> {
 impl<T> Drop for IterOwned<T> {
     fn drop(&mut self) {
         unsafe {
-            drop_in_place(std::slice::from_raw_parts_mut(self.head.as_ptr(), self.len));
+            drop_in_place(std::ptr::slice_from_raw_parts_mut(
+                self.head.as_ptr(),
+                self.len,
+            ));
             self.raw.dealloc()
         }
     }
@@ -149,13 +208,13 @@ impl<T> IntoIterator for SequenceList<T> {
     type Item = T;
     type IntoIter = IterOwned<T>;
     fn into_iter(self) -> IterOwned<T> {
-        let raw = unsafe{ self.raw.shadow_clone() };
+        let raw = unsafe { self.raw.shadow_clone() };
         let len = self.len;
         std::mem::forget(self);
 
         IterOwned {
             head: raw.arr,
-            tail: unsafe { NonNull::new_unchecked(raw.arr.as_ptr().offset(len as isize)) },
+            tail: unsafe { NonNull::new_unchecked(raw.arr.as_ptr().add(len)) },
             raw,
             len,
         }
@@ -188,10 +247,10 @@ impl<T> DoubleEndedIterator for IterOwned<T> {
             None
         } else {
             unsafe {
-                let ptr = self.tail;
-                self.tail = NonNull::new_unchecked(ptr.as_ptr().offset(-1));
+                // tail points past the last element
+                self.tail = NonNull::new_unchecked(self.tail.as_ptr().offset(-1));
                 self.len -= 1;
-                Some(ptr.as_ptr().read())
+                Some(self.tail.as_ptr().read())
             }
         }
     }
@@ -247,4 +306,85 @@ mod test {
 
         drop(list); // drop [3]
     }
+
+    #[test]
+    fn test_len_not_capacity() {
+        use std::rc::Rc;
+
+        // only the pushed elements are visible and dropped, never the spare capacity
+        let counter = Rc::new(());
+        let mut list = <SequenceList<Rc<()>>>::new(8);
+        list.push(counter.clone());
+        list.push(counter.clone());
+        assert_eq!(list.len(), 2);
+        assert_eq!((*list).len(), 2);
+        assert_eq!(list.iter_mut().count(), 2);
+        drop(list);
+        assert_eq!(Rc::strong_count(&counter), 1);
+
+        drop(<SequenceList<String>>::new(0));
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let mut list = <SequenceList<()>>::new(3);
+        assert_eq!(list.capacity(), 3);
+        list.push(());
+        list.insert(0, ());
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.remove(1), ());
+        list.reserve(10);
+        assert_eq!(list.capacity(), 11);
+        assert_eq!(list.into_iter().count(), 1);
+
+        let list = <SequenceList<()>>::new(0);
+        assert!(list.is_empty());
+    }
+
+    #[test]
+    fn test_into_iter_next_back() {
+        let mut list = <SequenceList<String>>::new(4);
+        for s in &["a", "b", "c"] {
+            list.push(String::from(*s));
+        }
+        let mut iter = list.into_iter();
+        assert_eq!(iter.next_back().unwrap(), "c");
+        assert_eq!(iter.next().unwrap(), "a");
+        assert_eq!(iter.next_back().unwrap(), "b");
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut list = <SequenceList<String>>::new(0);
+        assert_eq!(list.capacity(), 0);
+        assert!(list.is_empty());
+
+        list.reserve(2);
+        assert_eq!(list.capacity(), 2);
+        list.push(String::from("a"));
+        list.push(String::from("b"));
+
+        list.reserve(1);
+        assert_eq!(list.capacity(), 4);
+        list.push(String::from("c"));
+        assert_eq!(list.len(), 3);
+        assert_eq!(&*list, ["a", "b", "c"]);
+
+        let cloned = list.clone();
+        assert_eq!(&*cloned, &*list);
+
+        let mut tail = list.split_off(1);
+        assert_eq!(tail.capacity(), 4);
+        assert_eq!(&*tail, ["b", "c"]);
+        list.push(String::from("d"));
+        tail.append(&mut list);
+        assert!(list.is_empty());
+        assert_eq!(&*tail, ["b", "c", "a", "d"]);
+
+        let mut iter = cloned.into_iter();
+        assert_eq!(iter.next_back().unwrap(), "c");
+        assert_eq!(iter.next().unwrap(), "a");
+        assert_eq!(iter.len(), 1);
+    }
 }
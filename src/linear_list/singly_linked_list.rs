@@ -0,0 +1,271 @@
+use std::iter::FromIterator;
+use std::iter::FusedIterator;
+
+struct Node<T> {
+    elem: T,
+    next: Option<Box<Node<T>>>,
+}
+
+// Singly-linked list with O(1) operations at the front
+pub struct SinglyLinkedList<T> {
+    head: Option<Box<Node<T>>>,
+    len: usize,
+}
+
+impl<T> SinglyLinkedList<T> {
+    pub fn new() -> Self {
+        Self { head: None, len: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        let next = self.head.take();
+        self.head = Some(Box::new(Node { elem, next }));
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.head.take().map(|node| {
+            let node = *node;
+            self.head = node.next;
+            self.len -= 1;
+            node.elem
+        })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn front_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut node.elem)
+    }
+
+    pub fn clear(&mut self) {
+        // unlinks the nodes one by one to avoid recursive drops
+        let mut head = self.head.take();
+        while let Some(mut node) = head {
+            head = node.next.take();
+        }
+        self.len = 0;
+    }
+
+    pub fn reverse(&mut self) {
+        let mut prev = None;
+        let mut head = self.head.take();
+        while let Some(mut node) = head {
+            head = node.next.take();
+            node.next = prev;
+            prev = Some(node);
+        }
+        self.head = prev;
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            next: self.head.as_deref(),
+            len: self.len,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            next: self.head.as_deref_mut(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T> Default for SinglyLinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for SinglyLinkedList<T> {
+    fn drop(&mut self) {
+        self.clear()
+    }
+}
+
+impl<T> FromIterator<T> for SinglyLinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        for elem in iter {
+            list.push_front(elem);
+        }
+        list.reverse();
+        list
+    }
+}
+
+// --------------------------------
+// begin: IterOwned
+
+pub struct IterOwned<T>(SinglyLinkedList<T>);
+
+impl<T> Iterator for IterOwned<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> IntoIterator for SinglyLinkedList<T> {
+    type Item = T;
+    type IntoIter = IterOwned<T>;
+    fn into_iter(self) -> IterOwned<T> {
+        IterOwned(self)
+    }
+}
+
+impl<T> ExactSizeIterator for IterOwned<T> {
+    fn len(&self) -> usize {
+        self.0.len
+    }
+}
+
+impl<T> FusedIterator for IterOwned<T> {}
+
+// end: IterOwned
+// ------------------------------------------
+
+// ------------------------------------------
+// begin: IterMut
+
+pub struct IterMut<'a, T> {
+    next: Option<&'a mut Node<T>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<&'a mut T> {
+        self.next.take().map(|node| {
+            self.next = node.next.as_deref_mut();
+            self.len -= 1;
+            &mut node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut SinglyLinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<'a, T> FusedIterator for IterMut<'a, T> {}
+
+// end: IterMut
+// ------------------------------------------
+
+// ------------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, T> {
+    next: Option<&'a Node<T>>,
+    len: usize,
+}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            next: self.next,
+            len: self.len,
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        self.next.map(|node| {
+            self.next = node.next.as_deref();
+            self.len -= 1;
+            &node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SinglyLinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+// end: Iter
+// ------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::SinglyLinkedList;
+
+    #[test]
+    fn test_singly_linked_list() {
+        let mut list = <SinglyLinkedList<String>>::new();
+        assert!(list.is_empty());
+        assert_eq!(list.pop_front(), None);
+
+        list.push_front(String::from("c"));
+        list.push_front(String::from("b"));
+        list.push_front(String::from("a"));
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.front().unwrap(), "a");
+        assert_eq!(list.iter().cloned().collect::<String>(), "abc");
+
+        list.front_mut().unwrap().push('!');
+        for elem in &mut list {
+            elem.push('?');
+        }
+        list.reverse();
+        assert_eq!(list.iter().len(), 3);
+        assert_eq!(list.pop_front().unwrap(), "c?");
+        assert_eq!(list.into_iter().collect::<Vec<_>>(), ["b?", "a!?"]);
+
+        let mut list: SinglyLinkedList<u32> = (0..100_000).collect();
+        assert_eq!(list.front(), Some(&0));
+        list.reverse();
+        assert_eq!(list.front(), Some(&99_999));
+        drop(list);
+    }
+}
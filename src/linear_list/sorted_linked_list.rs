@@ -0,0 +1,235 @@
+use super::linked_list::{Iter, IterOwned, LinkedList, Node};
+
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::ptr::NonNull;
+
+// Double-linked circular list kept in ascending order
+pub struct SortedLinkedList<T>(LinkedList<T>);
+
+impl<T: Ord> SortedLinkedList<T> {
+    pub fn new() -> Self {
+        Self(LinkedList::new())
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.0.front()
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.0.back()
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    pub fn pop_last(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+
+    pub fn insert(&mut self, elem: T) {
+        self.0.ordered_insert(elem)
+    }
+
+    // cond: cmp refines the order of T, it may only tell apart elements which Ord
+    // considers equal, e.g. to break ties, so the list stays sorted for the other methods
+    // elem is placed after all elements that are not greater than it by cmp
+    pub fn insert_by<F>(&mut self, elem: T, cmp: F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.0.ordered_insert_by(elem, cmp);
+        debug_assert!(
+            self.iter().zip(self.iter().skip(1)).all(|(a, b)| a <= b),
+            "comparator disagrees with Ord"
+        );
+    }
+
+    pub fn contains(&self, elem: &T) -> bool {
+        self.find(elem).is_some()
+    }
+
+    // removes the first element equal to elem
+    pub fn remove(&mut self, elem: &T) -> Option<T> {
+        let node = self.find(elem)?;
+        Some(unsafe { self.0.unlink_node(node) })
+    }
+
+    // moves all elements of other into self, leaving other empty
+    pub fn merge(&mut self, other: &mut Self) {
+        self.0.merge_by(&mut other.0, T::cmp)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.0.iter()
+    }
+
+    pub fn into_linked_list(self) -> LinkedList<T> {
+        self.0
+    }
+
+    fn find(&self, elem: &T) -> Option<NonNull<Node<T>>> {
+        let mut passed = false;
+        let node = self.0.find_node(|e| match e.cmp(elem) {
+            Ordering::Less => false,
+            Ordering::Equal => true,
+            Ordering::Greater => {
+                passed = true;
+                true
+            }
+        })?;
+        if passed {
+            None
+        } else {
+            Some(node)
+        }
+    }
+}
+
+impl<T: Ord> Default for SortedLinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for SortedLinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut elems: Vec<T> = iter.into_iter().collect();
+        elems.sort();
+        Self(elems.into_iter().collect())
+    }
+}
+
+impl<T> IntoIterator for SortedLinkedList<T> {
+    type Item = T;
+    type IntoIter = IterOwned<T>;
+    fn into_iter(self) -> IterOwned<T> {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SortedLinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SortedLinkedList;
+
+    use std::cmp::Ordering;
+
+    #[test]
+    fn test_sorted_linked_list() {
+        let mut list = <SortedLinkedList<i32>>::new();
+        assert!(list.is_empty());
+        assert_eq!(list.first(), None);
+
+        for &x in &[5, 1, 4, 1, 9, 2, 6, 0, 9] {
+            list.insert(x);
+        }
+        assert_eq!(list.len(), 9);
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            [0, 1, 1, 2, 4, 5, 6, 9, 9]
+        );
+        assert_eq!(list.first(), Some(&0));
+        assert_eq!(list.last(), Some(&9));
+
+        assert!(list.contains(&4));
+        assert!(!list.contains(&3));
+        assert!(!list.contains(&10));
+
+        assert_eq!(list.remove(&1), Some(1));
+        assert_eq!(list.remove(&3), None);
+        assert_eq!(list.remove(&0), Some(0));
+        assert_eq!(list.remove(&9), Some(9));
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), [1, 2, 4, 5, 6, 9]);
+
+        let mut other: SortedLinkedList<i32> = vec![7, -1, 3, 10, 4].into_iter().collect();
+        list.merge(&mut other);
+        assert!(other.is_empty());
+        assert_eq!(list.len(), 11);
+        assert_eq!(
+            list.into_iter().collect::<Vec<_>>(),
+            [-1, 1, 2, 3, 4, 4, 5, 6, 7, 9, 10]
+        );
+    }
+
+    // ordered by the key only
+    #[derive(Debug)]
+    struct Entry(i32, char);
+
+    impl PartialEq for Entry {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+
+    impl Eq for Entry {}
+
+    impl PartialOrd for Entry {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Entry {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn test_equal_elements() {
+        // equal elements keep their insertion order
+        let mut list = <SortedLinkedList<Entry>>::new();
+        for &(k, c) in &[(2, 'a'), (1, 'b'), (2, 'c'), (3, 'd'), (0, 'e')] {
+            list.insert(Entry(k, c));
+        }
+        assert_eq!(list.iter().map(|e| e.1).collect::<String>(), "ebacd");
+        assert_eq!(list.remove(&Entry(2, 'x')).map(|e| e.1), Some('a'));
+        assert_eq!(list.iter().map(|e| e.1).collect::<String>(), "ebcd");
+    }
+
+    #[test]
+    fn test_insert_by() {
+        // equal keys ordered by descending char
+        let by_key_then_char = |a: &Entry, b: &Entry| a.0.cmp(&b.0).then(b.1.cmp(&a.1));
+        let mut list = <SortedLinkedList<Entry>>::new();
+        for &(k, c) in &[(2, 'a'), (1, 'b'), (2, 'c'), (3, 'd'), (2, 'b'), (0, 'e')] {
+            list.insert_by(Entry(k, c), by_key_then_char);
+        }
+        assert_eq!(list.iter().map(|e| e.1).collect::<String>(), "ebcbad");
+        assert!(list.contains(&Entry(3, 'x')));
+        assert_eq!(list.remove(&Entry(2, 'x')).map(|e| e.1), Some('c'));
+
+        // insert keeps working next to elements placed by insert_by
+        list.insert(Entry(2, 'f'));
+        assert_eq!(list.iter().map(|e| e.1).collect::<String>(), "ebbafd");
+    }
+
+    #[test]
+    #[should_panic(expected = "comparator disagrees with Ord")]
+    fn test_insert_by_disagreeing() {
+        let mut list: SortedLinkedList<i32> = vec![1, 2, 3].into_iter().collect();
+        list.insert_by(0, |a, b| b.cmp(a));
+    }
+}
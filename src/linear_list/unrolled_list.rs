@@ -0,0 +1,643 @@
+use super::linked_list::{self, LinkedList, Node};
+use super::sequence_list::{self, SequenceList};
+
+use std::iter::FromIterator;
+use std::iter::FusedIterator;
+use std::ops::{Index, IndexMut};
+use std::ptr::NonNull;
+
+type ChunkNode<T> = NonNull<Node<SequenceList<T>>>;
+
+// Linked list of chunks holding at most B elements each.
+// A chunk which drops below B / 2 elements is merged with or refilled from its neighbour.
+pub struct UnrolledList<T, const B: usize> {
+    chunks: LinkedList<SequenceList<T>>,
+    len: usize,
+}
+
+impl<T, const B: usize> UnrolledList<T, B> {
+    fn new_chunk() -> SequenceList<T> {
+        SequenceList::new(B)
+    }
+
+    // cond: index < self.len
+    // returns the chunk containing index, its position and the offset in it
+    fn locate(&self, index: usize) -> (ChunkNode<T>, usize, usize) {
+        unsafe {
+            if index < self.len / 2 {
+                let mut node = self.chunks.front_node().unwrap();
+                let mut pos = 0;
+                let mut offset = index;
+                loop {
+                    let len = Node::elem(node).len();
+                    if offset < len {
+                        return (node, pos, offset);
+                    }
+                    offset -= len;
+                    node = self.chunks.next_node(node).unwrap();
+                    pos += 1;
+                }
+            } else {
+                let mut node = self.chunks.back_node().unwrap();
+                let mut pos = self.chunks.len() - 1;
+                let mut rev_offset = self.len - index;
+                loop {
+                    let len = Node::elem(node).len();
+                    if rev_offset <= len {
+                        return (node, pos, len - rev_offset);
+                    }
+                    rev_offset -= len;
+                    node = self.chunks.prev_node(node).unwrap();
+                    pos -= 1;
+                }
+            }
+        }
+    }
+
+    // cond: node belongs to self.chunks
+    unsafe fn rebalance(&mut self, node: ChunkNode<T>) {
+        let min = B / 2;
+        let chunk = Node::elem_mut(node);
+        if chunk.len() >= min {
+            return;
+        }
+
+        if let Some(next) = self.chunks.next_node(node) {
+            let next_chunk = Node::elem_mut(next);
+            if chunk.len() + next_chunk.len() <= B {
+                chunk.append(next_chunk);
+                self.chunks.unlink_node(next);
+            } else {
+                let count = min - chunk.len();
+                let rest = next_chunk.split_off(count);
+                let mut moved = std::mem::replace(next_chunk, rest);
+                chunk.append(&mut moved);
+            }
+        } else if let Some(prev) = self.chunks.prev_node(node) {
+            let prev_chunk = Node::elem_mut(prev);
+            if prev_chunk.len() + chunk.len() <= B {
+                prev_chunk.append(chunk);
+                self.chunks.unlink_node(node);
+            } else {
+                let count = min - chunk.len();
+                let mut moved = prev_chunk.split_off(prev_chunk.len() - count);
+                moved.append(chunk);
+                *chunk = moved;
+            }
+        } else if chunk.is_empty() {
+            self.chunks.unlink_node(node);
+        }
+    }
+}
+
+impl<T, const B: usize> UnrolledList<T, B> {
+    pub fn new() -> Self {
+        assert!(B >= 2, "chunk capacity must be at least 2");
+        Self {
+            chunks: LinkedList::new(),
+            len: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.len = 0;
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        let (node, _, offset) = self.locate(index);
+        Some(unsafe { &Node::elem(node)[offset] })
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len {
+            return None;
+        }
+        let (node, _, offset) = self.locate(index);
+        Some(unsafe { &mut Node::elem_mut(node)[offset] })
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.chunks.front().map(|chunk| &chunk[0])
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.chunks.back().map(|chunk| &chunk[chunk.len() - 1])
+    }
+
+    pub fn insert(&mut self, index: usize, elem: T) {
+        if index > self.len {
+            panic!("index out of bounds")
+        }
+
+        let (node, offset) = if index == self.len {
+            match self.chunks.back_node() {
+                Some(node) => (node, unsafe { Node::elem(node).len() }),
+                None => {
+                    let mut chunk = Self::new_chunk();
+                    chunk.push(elem);
+                    self.chunks.push_back(chunk);
+                    self.len += 1;
+                    return;
+                }
+            }
+        } else {
+            let (node, _, offset) = self.locate(index);
+            (node, offset)
+        };
+
+        unsafe {
+            let chunk = Node::elem_mut(node);
+            if chunk.len() < B {
+                chunk.insert(offset, elem);
+            } else if offset == B {
+                // appending to a full chunk starts a new one instead of splitting it
+                let mut new_chunk = Self::new_chunk();
+                new_chunk.push(elem);
+                self.chunks.insert_after_node(node, new_chunk);
+            } else {
+                let half = B / 2;
+                let new_chunk = chunk.split_off(half);
+                let new_node = self.chunks.insert_after_node(node, new_chunk);
+                if offset <= half {
+                    chunk.insert(offset, elem);
+                } else {
+                    Node::elem_mut(new_node).insert(offset - half, elem);
+                }
+            }
+        }
+        self.len += 1;
+    }
+
+    pub fn remove(&mut self, index: usize) -> T {
+        if index >= self.len {
+            panic!("index out of bounds")
+        }
+        let (node, _, offset) = self.locate(index);
+        self.len -= 1;
+        unsafe {
+            let elem = Node::elem_mut(node).remove(offset);
+            self.rebalance(node);
+            elem
+        }
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        self.insert(self.len, elem)
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        self.insert(0, elem)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.remove(self.len - 1))
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.remove(0))
+        }
+    }
+
+    pub fn split_off(&mut self, at: usize) -> Self {
+        if at > self.len {
+            panic!("index out of bounds")
+        }
+        if at == self.len {
+            return Self::new();
+        }
+
+        let (node, pos, offset) = self.locate(at);
+        let mut other = Self::new();
+        unsafe {
+            if offset == 0 {
+                other.chunks = self.chunks.split_off(pos);
+            } else {
+                let tail = Node::elem_mut(node).split_off(offset);
+                other.chunks = self.chunks.split_off(pos + 1);
+                other.chunks.push_front(tail);
+            }
+        }
+        other.len = self.len - at;
+        self.len = at;
+
+        if let Some(node) = self.chunks.back_node() {
+            unsafe { self.rebalance(node) };
+        }
+        if let Some(node) = other.chunks.front_node() {
+            unsafe { other.rebalance(node) };
+        }
+        other
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            chunks: self.chunks.iter(),
+            front: [].iter(),
+            back: [].iter(),
+            len: self.len,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            chunks: self.chunks.iter_mut(),
+            front: [].iter_mut(),
+            back: [].iter_mut(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T, const B: usize> Default for UnrolledList<T, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const B: usize> Index<usize> for UnrolledList<T, B> {
+    type Output = T;
+    fn index(&self, idx: usize) -> &T {
+        match self.get(idx) {
+            Some(elem) => elem,
+            None => panic!("index out of bounds"),
+        }
+    }
+}
+
+impl<T, const B: usize> IndexMut<usize> for UnrolledList<T, B> {
+    fn index_mut(&mut self, idx: usize) -> &mut T {
+        match self.get_mut(idx) {
+            Some(elem) => elem,
+            None => panic!("index out of bounds"),
+        }
+    }
+}
+
+impl<T, const B: usize> FromIterator<T> for UnrolledList<T, B> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        let mut chunk = Self::new_chunk();
+        for elem in iter {
+            if chunk.len() == B {
+                list.chunks
+                    .push_back(std::mem::replace(&mut chunk, Self::new_chunk()));
+            }
+            chunk.push(elem);
+            list.len += 1;
+        }
+        if !chunk.is_empty() {
+            list.chunks.push_back(chunk);
+        }
+        list
+    }
+}
+
+// --------------------------------
+// begin: IterOwned
+
+pub struct IterOwned<T> {
+    chunks: linked_list::IterOwned<SequenceList<T>>,
+    front: Option<sequence_list::IterOwned<T>>,
+    back: Option<sequence_list::IterOwned<T>>,
+    len: usize,
+}
+
+impl<T> Iterator for IterOwned<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        loop {
+            if let Some(elem) = self.front.as_mut().and_then(Iterator::next) {
+                self.len -= 1;
+                return Some(elem);
+            }
+            match self.chunks.next() {
+                Some(chunk) => self.front = Some(chunk.into_iter()),
+                None => {
+                    let elem = self.back.as_mut().and_then(Iterator::next);
+                    if elem.is_some() {
+                        self.len -= 1;
+                    }
+                    return elem;
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T, const B: usize> IntoIterator for UnrolledList<T, B> {
+    type Item = T;
+    type IntoIter = IterOwned<T>;
+    fn into_iter(self) -> IterOwned<T> {
+        IterOwned {
+            chunks: self.chunks.into_iter(),
+            front: None,
+            back: None,
+            len: self.len,
+        }
+    }
+}
+
+impl<T> DoubleEndedIterator for IterOwned<T> {
+    fn next_back(&mut self) -> Option<T> {
+        loop {
+            if let Some(elem) = self.back.as_mut().and_then(DoubleEndedIterator::next_back) {
+                self.len -= 1;
+                return Some(elem);
+            }
+            match self.chunks.next_back() {
+                Some(chunk) => self.back = Some(chunk.into_iter()),
+                None => {
+                    let elem = self.front.as_mut().and_then(DoubleEndedIterator::next_back);
+                    if elem.is_some() {
+                        self.len -= 1;
+                    }
+                    return elem;
+                }
+            }
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IterOwned<T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<T> FusedIterator for IterOwned<T> {}
+
+// end: IterOwned
+// ------------------------------------------
+
+// ------------------------------------------
+// begin: IterMut
+
+pub struct IterMut<'a, T> {
+    chunks: linked_list::IterMut<'a, SequenceList<T>>,
+    front: std::slice::IterMut<'a, T>,
+    back: std::slice::IterMut<'a, T>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+    fn next(&mut self) -> Option<&'a mut T> {
+        loop {
+            if let Some(elem) = self.front.next() {
+                self.len -= 1;
+                return Some(elem);
+            }
+            match self.chunks.next() {
+                Some(chunk) => self.front = chunk.iter_mut(),
+                None => {
+                    let elem = self.back.next();
+                    if elem.is_some() {
+                        self.len -= 1;
+                    }
+                    return elem;
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T, const B: usize> IntoIterator for &'a mut UnrolledList<T, B> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        loop {
+            if let Some(elem) = self.back.next_back() {
+                self.len -= 1;
+                return Some(elem);
+            }
+            match self.chunks.next_back() {
+                Some(chunk) => self.back = chunk.iter_mut(),
+                None => {
+                    let elem = self.front.next_back();
+                    if elem.is_some() {
+                        self.len -= 1;
+                    }
+                    return elem;
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for IterMut<'a, T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<'a, T> FusedIterator for IterMut<'a, T> {}
+
+// end: IterMut
+// ------------------------------------------
+
+// ------------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, T> {
+    chunks: linked_list::Iter<'a, SequenceList<T>>,
+    front: std::slice::Iter<'a, T>,
+    back: std::slice::Iter<'a, T>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some(elem) = self.front.next() {
+                self.len -= 1;
+                return Some(elem);
+            }
+            match self.chunks.next() {
+                Some(chunk) => self.front = chunk.iter(),
+                None => {
+                    let elem = self.back.next();
+                    if elem.is_some() {
+                        self.len -= 1;
+                    }
+                    return elem;
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T, const B: usize> IntoIterator for &'a UnrolledList<T, B> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        loop {
+            if let Some(elem) = self.back.next_back() {
+                self.len -= 1;
+                return Some(elem);
+            }
+            match self.chunks.next_back() {
+                Some(chunk) => self.back = chunk.iter(),
+                None => {
+                    let elem = self.front.next_back();
+                    if elem.is_some() {
+                        self.len -= 1;
+                    }
+                    return elem;
+                }
+            }
+        }
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<'a, T> FusedIterator for Iter<'a, T> {}
+
+// end: Iter
+// ------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::UnrolledList;
+
+    fn check<const B: usize>(list: &UnrolledList<i32, B>, expected: &[i32]) {
+        assert_eq!(list.len(), expected.len());
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), expected);
+        assert_eq!(
+            list.iter().rev().copied().collect::<Vec<_>>(),
+            expected.iter().rev().copied().collect::<Vec<_>>()
+        );
+        for (i, elem) in expected.iter().enumerate() {
+            assert_eq!(list.get(i), Some(elem));
+        }
+        assert_eq!(list.get(expected.len()), None);
+    }
+
+    #[test]
+    fn test_unrolled_list() {
+        let mut list = <UnrolledList<i32, 4>>::new();
+        let mut expected = Vec::new();
+        assert!(list.is_empty());
+
+        // deterministic pseudo-random positions
+        let mut seed = 7u32;
+        for i in 0..200 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let index = (seed >> 8) as usize % (expected.len() + 1);
+            list.insert(index, i);
+            expected.insert(index, i);
+        }
+        check(&list, &expected);
+        assert!(list.chunk_count() <= 200 / 2 + 1);
+
+        for _ in 0..150 {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            let index = (seed >> 8) as usize % expected.len();
+            assert_eq!(list.remove(index), expected.remove(index));
+        }
+        check(&list, &expected);
+        // every chunk but the last one is at least half full
+        assert!(list.chunk_count() <= 50 / 2 + 1);
+
+        for elem in &mut list {
+            *elem *= 2;
+        }
+        list[0] += 1;
+        expected.iter_mut().for_each(|e| *e *= 2);
+        expected[0] += 1;
+        check(&list, &expected);
+
+        while let Some(elem) = list.pop_front() {
+            assert_eq!(elem, expected.remove(0));
+        }
+        assert_eq!(list.chunk_count(), 0);
+    }
+
+    #[test]
+    fn test_split_off() {
+        let mut list: UnrolledList<i32, 8> = (0..50).collect();
+        assert_eq!(list.chunk_count(), 7);
+        assert_eq!(list.front(), Some(&0));
+        assert_eq!(list.back(), Some(&49));
+
+        let tail = list.split_off(21);
+        check(&list, &(0..21).collect::<Vec<_>>());
+        check(&tail, &(21..50).collect::<Vec<_>>());
+
+        let tail2 = list.split_off(16);
+        check(&list, &(0..16).collect::<Vec<_>>());
+        check(&tail2, &(16..21).collect::<Vec<_>>());
+
+        let all = list.split_off(0);
+        assert!(list.is_empty());
+        assert_eq!(all.len(), 16);
+
+        let mut iter = tail.into_iter();
+        assert_eq!(iter.next(), Some(21));
+        assert_eq!(iter.next_back(), Some(49));
+        assert_eq!(iter.len(), 27);
+        assert_eq!(iter.sum::<i32>(), (22..49).sum());
+
+        let mut list = <UnrolledList<String, 2>>::new();
+        for i in 0..5 {
+            list.push_back(i.to_string());
+            list.push_front(i.to_string());
+        }
+        assert_eq!(list.pop_back().unwrap(), "4");
+        assert_eq!(list.into_iter().collect::<String>(), "432100123");
+    }
+}
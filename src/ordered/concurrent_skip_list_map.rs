@@ -0,0 +1,858 @@
+use crate::epoch::{self, Guard};
+use crate::rng::Rng;
+
+use std::borrow::Borrow;
+use std::iter::FusedIterator;
+use std::ops::{Bound, RangeBounds};
+use std::ptr;
+use std::sync::atomic::{AtomicIsize, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+
+const MAX_LEVEL: usize = 32;
+// optimistic collections a range query tries before it holds off the writers
+const RANGE_ATTEMPTS: usize = 16;
+
+// the lowest bit of a tower pointer marks the owner of the tower as deleted on that level
+fn is_marked<T>(ptr: *mut T) -> bool {
+    ptr as usize & 1 == 1
+}
+
+fn marked<T>(ptr: *mut T) -> *mut T {
+    (ptr as usize | 1) as *mut T
+}
+
+fn unmarked<T>(ptr: *mut T) -> *mut T {
+    (ptr as usize & !1) as *mut T
+}
+
+struct Node<K, V> {
+    key: K,
+    // null once the entry is removed
+    value: AtomicPtr<V>,
+    // number of levels the node has been unlinked from, it is retired when all of them are
+    unlinked: AtomicUsize,
+    tower: Box<[AtomicPtr<Node<K, V>>]>,
+}
+
+// counts the write as finished when dropped
+struct Writing<'a>(&'a AtomicUsize);
+
+impl Drop for Writing<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+// lets new writes start again when dropped, also if the range query panics
+struct Blocking<'a>(&'a AtomicUsize);
+
+impl Drop for Blocking<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct Position<'g, K, V> {
+    preds: [&'g [AtomicPtr<Node<K, V>>]; MAX_LEVEL],
+    succs: [*mut Node<K, V>; MAX_LEVEL],
+}
+
+// Lock-free ordered map. Removal first clears the value of an entry, which is when it takes effect,
+// then marks the tower of its node, which is unlinked by whoever walks past it.
+// Nodes are reclaimed through epochs once they are unlinked from every level.
+// Range queries validate their snapshot against the counts of started and finished writes,
+// when that keeps failing new writes wait until the query has collected its entries.
+pub struct ConcurrentSkipListMap<K, V> {
+    head: [AtomicPtr<Node<K, V>>; MAX_LEVEL],
+    len: AtomicIsize,
+    seed: AtomicU64,
+    // invariant: finished <= started, they are equal when no write is in progress
+    started: AtomicUsize,
+    finished: AtomicUsize,
+    // range queries which wait for the writes in progress and keep new ones from starting
+    blocking: AtomicUsize,
+}
+
+unsafe impl<K: Send + Sync, V: Send + Sync> Send for ConcurrentSkipListMap<K, V> {}
+unsafe impl<K: Send + Sync, V: Send + Sync> Sync for ConcurrentSkipListMap<K, V> {}
+
+impl<K, V> ConcurrentSkipListMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_seed(Rng::from_entropy().next_u64())
+    }
+
+    // the seed determines the heights of the nodes
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            head: Default::default(),
+            len: AtomicIsize::new(0),
+            seed: AtomicU64::new(seed),
+            started: AtomicUsize::new(0),
+            finished: AtomicUsize::new(0),
+            blocking: AtomicUsize::new(0),
+        }
+    }
+
+    // the number of entries may be briefly off while other threads are writing
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed).max(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn random_height(&self) -> usize {
+        // each node reaches the next level with probability 1/4
+        let bits = Rng::new(self.seed.fetch_add(1, Ordering::Relaxed)).next_u64();
+        1 + (bits.trailing_zeros() as usize / 2).min(MAX_LEVEL - 1)
+    }
+
+    fn begin_write(&self) -> Writing<'_> {
+        loop {
+            self.started.fetch_add(1, Ordering::SeqCst);
+            if self.blocking.load(Ordering::SeqCst) == 0 {
+                return Writing(&self.finished);
+            }
+            // backs out without writing until the blocking range queries are done
+            self.finished.fetch_add(1, Ordering::SeqCst);
+            while self.blocking.load(Ordering::SeqCst) != 0 {
+                thread::yield_now();
+            }
+        }
+    }
+
+    // the number of started writes if none is in progress,
+    // finished is read first since it never passes started
+    fn quiescent(&self) -> Option<usize> {
+        let finished = self.finished.load(Ordering::SeqCst);
+        let started = self.started.load(Ordering::SeqCst);
+        if finished == started {
+            Some(started)
+        } else {
+            None
+        }
+    }
+
+    fn mark_tower(node: &Node<K, V>) {
+        for link in node.tower.iter().rev() {
+            let mut next = link.load(Ordering::SeqCst);
+            while !is_marked(next) {
+                match link.compare_exchange(next, marked(next), Ordering::SeqCst, Ordering::SeqCst)
+                {
+                    Ok(_) => break,
+                    Err(curr) => next = curr,
+                }
+            }
+        }
+    }
+
+    // first node on level 0 which is not removed and whose key is not before,
+    // it only reads the map and never retries
+    fn seek<'g, F>(&'g self, before: F, _guard: &'g Guard) -> Option<&'g Node<K, V>>
+    where
+        F: Fn(&K) -> bool,
+    {
+        let mut pred: &'g [AtomicPtr<Node<K, V>>] = &self.head;
+        let mut curr = ptr::null_mut();
+        for level in (0..MAX_LEVEL).rev() {
+            curr = unmarked(pred[level].load(Ordering::SeqCst));
+            while let Some(node) = unsafe { curr.as_ref() } {
+                let succ = node.tower[level].load(Ordering::SeqCst);
+                if is_marked(succ) {
+                    curr = unmarked(succ);
+                } else if before(&node.key) {
+                    pred = &node.tower;
+                    curr = succ;
+                } else {
+                    break;
+                }
+            }
+        }
+        unsafe { curr.as_ref() }
+    }
+}
+
+impl<K, V> ConcurrentSkipListMap<K, V>
+where
+    K: Ord + Send + 'static,
+    V: Send + 'static,
+{
+    // cond: node is no longer reachable on n more levels
+    unsafe fn retire_levels(&self, node: *mut Node<K, V>, n: usize, guard: &Guard) {
+        let height = (&*node).tower.len();
+        if (*node).unlinked.fetch_add(n, Ordering::SeqCst) + n == height {
+            guard.defer_destroy(node);
+        }
+    }
+
+    // returns the neighbours of key on every level and unlinks the marked nodes on the way
+    fn find<'g, Q>(&'g self, key: &Q, guard: &'g Guard) -> Position<'g, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        'retry: loop {
+            let mut pos = Position {
+                preds: [&self.head[..]; MAX_LEVEL],
+                succs: [ptr::null_mut(); MAX_LEVEL],
+            };
+            let mut pred: &'g [AtomicPtr<Node<K, V>>] = &self.head;
+            for level in (0..MAX_LEVEL).rev() {
+                let mut curr = pred[level].load(Ordering::SeqCst);
+                if is_marked(curr) {
+                    continue 'retry;
+                }
+                while let Some(node) = unsafe { curr.as_ref() } {
+                    let succ = node.tower[level].load(Ordering::SeqCst);
+                    if is_marked(succ) {
+                        let next = unmarked(succ);
+                        let snip = pred[level].compare_exchange(
+                            curr,
+                            next,
+                            Ordering::SeqCst,
+                            Ordering::SeqCst,
+                        );
+                        if snip.is_err() {
+                            continue 'retry;
+                        }
+                        unsafe { self.retire_levels(curr, 1, guard) };
+                        curr = next;
+                    } else if node.key.borrow() < key {
+                        pred = &node.tower;
+                        curr = succ;
+                    } else {
+                        break;
+                    }
+                }
+                pos.preds[level] = pred;
+                pos.succs[level] = curr;
+            }
+            return pos;
+        }
+    }
+
+    // returns false and replaces the value if the map already contains key
+    pub fn insert(&self, key: K, value: V) -> bool {
+        let _writing = self.begin_write();
+        let guard = epoch::pin();
+
+        let height = self.random_height();
+        let node = Box::into_raw(Box::new(Node {
+            key,
+            value: AtomicPtr::new(Box::into_raw(Box::new(value))),
+            unlinked: AtomicUsize::new(0),
+            tower: (0..height).map(|_| AtomicPtr::default()).collect(),
+        }));
+        let new = unsafe { &*node };
+
+        let mut pos = loop {
+            let pos = self.find(&new.key, &guard);
+            if let Some(found) = unsafe { pos.succs[0].as_ref() } {
+                if found.key == new.key {
+                    let value = new.value.load(Ordering::Relaxed);
+                    let mut old = found.value.load(Ordering::SeqCst);
+                    while !old.is_null() {
+                        let swap = found.value.compare_exchange(
+                            old,
+                            value,
+                            Ordering::SeqCst,
+                            Ordering::SeqCst,
+                        );
+                        match swap {
+                            Ok(_) => unsafe {
+                                guard.defer_destroy(old);
+                                drop(Box::from_raw(node));
+                                return false;
+                            },
+                            Err(curr) => old = curr,
+                        }
+                    }
+                    // the entry is being removed, helps to unlink it and retries
+                    Self::mark_tower(found);
+                    continue;
+                }
+            }
+
+            for (link, &succ) in new.tower.iter().zip(pos.succs.iter()) {
+                link.store(succ, Ordering::Relaxed);
+            }
+            let link = pos.preds[0][0].compare_exchange(
+                pos.succs[0],
+                node,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            );
+            if link.is_ok() {
+                break pos;
+            }
+        };
+        self.len.fetch_add(1, Ordering::Relaxed);
+
+        'link: for level in 1..height {
+            loop {
+                let next = new.tower[level].load(Ordering::SeqCst);
+                if is_marked(next) {
+                    // removed already, the upper levels will never be linked
+                    unsafe { self.retire_levels(node, height - level, &guard) };
+                    break 'link;
+                }
+                let succ = pos.succs[level];
+                if next != succ
+                    && new.tower[level]
+                        .compare_exchange(next, succ, Ordering::SeqCst, Ordering::SeqCst)
+                        .is_err()
+                {
+                    continue;
+                }
+                let link = pos.preds[level][level].compare_exchange(
+                    succ,
+                    node,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                );
+                if link.is_ok() {
+                    break;
+                }
+                pos = self.find(&new.key, &guard);
+                if pos.succs[0] != node {
+                    unsafe { self.retire_levels(node, height - level, &guard) };
+                    break 'link;
+                }
+            }
+        }
+
+        // a removal may have finished unlinking before some level was linked
+        if is_marked(new.tower[0].load(Ordering::SeqCst)) {
+            self.find(&new.key, &guard);
+        }
+        true
+    }
+
+    pub fn remove<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let _writing = self.begin_write();
+        let guard = epoch::pin();
+
+        let node = match self.seek(|k| k.borrow() < key, &guard) {
+            Some(node) if node.key.borrow() == key => node,
+            _ => return false,
+        };
+        let mut value = node.value.load(Ordering::SeqCst);
+        loop {
+            if value.is_null() {
+                return false;
+            }
+            let take = node.value.compare_exchange(
+                value,
+                ptr::null_mut(),
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            );
+            match take {
+                Ok(_) => break,
+                Err(curr) => value = curr,
+            }
+        }
+        self.len.fetch_sub(1, Ordering::Relaxed);
+
+        Self::mark_tower(node);
+        self.find(key, &guard);
+        unsafe { guard.defer_destroy(value) };
+        true
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let guard = epoch::pin();
+        match self.seek(|k| k.borrow() < key, &guard) {
+            Some(node) if node.key.borrow() == key => !node.value.load(Ordering::SeqCst).is_null(),
+            _ => false,
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        V: Clone,
+    {
+        let guard = epoch::pin();
+        match self.seek(|k| k.borrow() < key, &guard) {
+            Some(node) if node.key.borrow() == key => {
+                unsafe { node.value.load(Ordering::SeqCst).as_ref() }.cloned()
+            }
+            _ => None,
+        }
+    }
+
+    // Collects the entries in range between two reads of the write counts which show that
+    // no write took effect meanwhile, so the collection is the content of the map at one instant.
+    // After RANGE_ATTEMPTS failed collections it makes new writes wait, waits for the ones
+    // in progress and collects once more.
+    pub fn range<Q, R>(&self, range: R) -> Range<K, V>
+    where
+        K: Borrow<Q> + Clone,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+        V: Clone,
+    {
+        let guard = epoch::pin();
+        let before = |key: &K| match range.start_bound() {
+            Bound::Included(start) => key.borrow() < start,
+            Bound::Excluded(start) => key.borrow() <= start,
+            Bound::Unbounded => false,
+        };
+        let within = |key: &K| match range.end_bound() {
+            Bound::Included(end) => key.borrow() <= end,
+            Bound::Excluded(end) => key.borrow() < end,
+            Bound::Unbounded => true,
+        };
+        // the guard keeps the nodes and values alive, so their addresses are not reused
+        let collect = || {
+            let mut entries = Vec::new();
+            let mut curr = self.seek(before, &guard);
+            while let Some(node) = curr {
+                if !within(&node.key) {
+                    break;
+                }
+                let value = node.value.load(Ordering::SeqCst);
+                if !value.is_null() {
+                    entries.push((node as *const Node<K, V>, value));
+                }
+                curr = unsafe { unmarked(node.tower[0].load(Ordering::SeqCst)).as_ref() };
+            }
+            entries
+        };
+
+        let mut snapshot = None;
+        for _ in 0..RANGE_ATTEMPTS {
+            if let Some(started) = self.quiescent() {
+                let entries = collect();
+                if self.started.load(Ordering::SeqCst) == started {
+                    snapshot = Some(entries);
+                    break;
+                }
+            }
+            thread::yield_now();
+        }
+        let entries = snapshot.unwrap_or_else(|| {
+            self.blocking.fetch_add(1, Ordering::SeqCst);
+            let _blocking = Blocking(&self.blocking);
+            while self.quiescent().is_none() {
+                thread::yield_now();
+            }
+            collect()
+        });
+        let entries = entries
+            .into_iter()
+            .map(|(node, value)| unsafe { ((*node).key.clone(), (*value).clone()) })
+            .collect::<Vec<_>>();
+        Range(entries.into_iter())
+    }
+
+    pub fn iter(&self) -> Range<K, V>
+    where
+        K: Clone,
+        V: Clone,
+    {
+        self.range::<K, _>(..)
+    }
+}
+
+impl<K, V> Default for ConcurrentSkipListMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Drop for ConcurrentSkipListMap<K, V> {
+    fn drop(&mut self) {
+        // nodes unlinked from level 0 belong to the epoch garbage already
+        let mut curr = unmarked(*self.head[0].get_mut());
+        while !curr.is_null() {
+            let node = unsafe { Box::from_raw(curr) };
+            let value = node.value.load(Ordering::Relaxed);
+            if !value.is_null() {
+                drop(unsafe { Box::from_raw(value) });
+            }
+            curr = unmarked(node.tower[0].load(Ordering::Relaxed));
+        }
+    }
+}
+
+// ------------------------------------------
+// begin: Range
+
+pub struct Range<K, V>(std::vec::IntoIter<(K, V)>);
+
+impl<K, V> Iterator for Range<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<(K, V)> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Range<K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        self.0.next_back()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Range<K, V> {}
+
+impl<K, V> FusedIterator for Range<K, V> {}
+
+// end: Range
+// ------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::{ConcurrentSkipListMap, RANGE_ATTEMPTS};
+    use crate::rng::Rng;
+
+    use std::cell::RefCell;
+    use std::cmp;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    // compared against u32::MAX, the end of the ranges in the tests, it calls the hook
+    // of the thread, which lets a test write to the map at a chosen point of a range query
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    struct Hooked(u32);
+
+    type Hook = Box<dyn FnMut(u32)>;
+
+    thread_local! {
+        static HOOK: RefCell<Option<Hook>> = RefCell::new(None);
+    }
+
+    impl Ord for Hooked {
+        fn cmp(&self, other: &Self) -> cmp::Ordering {
+            if other.0 == u32::MAX {
+                HOOK.with(|hook| {
+                    // writes from the hook compare keys too
+                    if let Ok(mut hook) = hook.try_borrow_mut() {
+                        if let Some(hook) = hook.as_mut() {
+                            hook(self.0);
+                        }
+                    }
+                });
+            }
+            self.0.cmp(&other.0)
+        }
+    }
+
+    impl PartialOrd for Hooked {
+        fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    enum Op {
+        Insert(u32, bool),
+        Remove(bool),
+        Get(Option<u32>),
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    struct Event {
+        op: Op,
+        invoke: u64,
+        response: u64,
+    }
+
+    // Searches for an order of the events on one key which respects their real time order
+    // and the sequential behaviour of a map. Keys are independent, so checking each key
+    // on its own checks the whole history.
+    fn linearizable(events: &[Event], done: &mut Vec<bool>, state: Option<u32>) -> bool {
+        let pending = || (0..events.len()).filter(|&i| !done[i]);
+        let first_response = match pending().map(|i| events[i].response).min() {
+            Some(response) => response,
+            None => return true,
+        };
+        let candidates: Vec<usize> = pending()
+            .filter(|&i| events[i].invoke < first_response)
+            .collect();
+        for i in candidates {
+            let next = match (events[i].op, state) {
+                (Op::Insert(value, true), None) | (Op::Insert(value, false), Some(_)) => {
+                    Some(value)
+                }
+                (Op::Remove(true), Some(_)) | (Op::Remove(false), None) => None,
+                (Op::Get(seen), _) if seen == state => state,
+                _ => continue,
+            };
+            done[i] = true;
+            if linearizable(events, done, next) {
+                return true;
+            }
+            done[i] = false;
+        }
+        false
+    }
+
+    #[test]
+    fn test_concurrent_skip_list_map() {
+        let map = <ConcurrentSkipListMap<u32, String>>::with_seed(3);
+        assert!(map.is_empty());
+        assert!(map.insert(2, String::from("b")));
+        assert!(map.insert(1, String::from("a")));
+        assert!(map.insert(3, String::from("c")));
+        assert!(!map.insert(2, String::from("B")));
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&2).unwrap(), "B");
+        assert_eq!(map.get(&4), None);
+        assert!(map.contains_key(&1));
+
+        assert!(map.remove(&1));
+        assert!(!map.remove(&1));
+        assert!(!map.contains_key(&1));
+        assert_eq!(map.len(), 2);
+        assert!(map.insert(1, String::from("A")));
+
+        let entries: Vec<_> = map.range(2..).collect();
+        assert_eq!(entries, [(2, String::from("B")), (3, String::from("c"))]);
+        assert_eq!(map.iter().next_back().unwrap().0, 3);
+        assert_eq!(map.range(..=1).len(), 1);
+    }
+
+    #[test]
+    fn test_disjoint_writers() {
+        let map = Arc::new(<ConcurrentSkipListMap<u32, u32>>::new());
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let map = map.clone();
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        assert!(map.insert(i * 4 + t, t));
+                    }
+                    for i in (0..1000).step_by(2) {
+                        assert!(map.remove(&(i * 4 + t)));
+                    }
+                })
+            })
+            .collect();
+        handles.into_iter().for_each(|h| h.join().unwrap());
+
+        assert_eq!(map.len(), 2000);
+        let keys: Vec<_> = map.iter().map(|(k, _)| k).collect();
+        let expected: Vec<_> = (0..4000).filter(|k| (k / 4) % 2 == 1).collect();
+        assert_eq!(keys, expected);
+    }
+
+    #[test]
+    fn test_linearizable() {
+        const THREADS: u64 = 4;
+        const KEYS: u64 = 32;
+
+        let map = Arc::new(<ConcurrentSkipListMap<u64, u32>>::with_seed(5));
+        let clock = Arc::new(AtomicU64::new(0));
+        let barrier = Arc::new(Barrier::new(THREADS as usize));
+
+        let handles: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let (map, clock, barrier) = (map.clone(), clock.clone(), barrier.clone());
+                thread::spawn(move || {
+                    let mut rng = Rng::new(t);
+                    let mut history = Vec::new();
+                    barrier.wait();
+                    for i in 0..300 {
+                        let key = rng.next_u64() % KEYS;
+                        let value = (t * 1000 + i) as u32;
+                        let choice = rng.next_u64() % 3;
+                        let invoke = clock.fetch_add(1, Ordering::SeqCst);
+                        let op = match choice {
+                            0 => Op::Insert(value, map.insert(key, value)),
+                            1 => Op::Remove(map.remove(&key)),
+                            _ => Op::Get(map.get(&key)),
+                        };
+                        let response = clock.fetch_add(1, Ordering::SeqCst);
+                        history.push((
+                            key,
+                            Event {
+                                op,
+                                invoke,
+                                response,
+                            },
+                        ));
+                    }
+                    history
+                })
+            })
+            .collect();
+
+        let mut history = Vec::new();
+        for handle in handles {
+            history.extend(handle.join().unwrap());
+        }
+        for key in 0..KEYS {
+            let events: Vec<Event> = history
+                .iter()
+                .filter(|&&(k, _)| k == key)
+                .map(|&(_, event)| event)
+                .collect();
+            let mut done = vec![false; events.len()];
+            assert!(
+                linearizable(&events, &mut done, None),
+                "history of key {} is not linearizable: {:?}",
+                key,
+                events
+            );
+        }
+    }
+
+    #[test]
+    fn test_snapshot() {
+        // the writer keeps one or two adjacent keys in the map at any time
+        let map = Arc::new(<ConcurrentSkipListMap<u32, ()>>::new());
+        let stop = Arc::new(AtomicBool::new(false));
+        map.insert(0, ());
+
+        let writer = {
+            let (map, stop) = (map.clone(), stop.clone());
+            thread::spawn(move || {
+                let mut key = 0;
+                while !stop.load(Ordering::SeqCst) {
+                    map.insert(key + 1, ());
+                    map.remove(&key);
+                    key += 1;
+                }
+            })
+        };
+
+        for _ in 0..2000 {
+            let keys: Vec<_> = map.iter().map(|(k, _)| k).collect();
+            match keys[..] {
+                [_] => {}
+                [a, b] => assert_eq!(a + 1, b),
+                _ => panic!("inconsistent snapshot: {:?}", keys),
+            }
+        }
+        stop.store(true, Ordering::SeqCst);
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn test_snapshot_write_during_range() {
+        // while a walk of the range is at key 0 the odd key moves ahead of it from 1 to 999,
+        // at key 500 it moves back behind it, so the walk sees no odd key although one
+        // is in the map at any time, until every optimistic collection has failed
+        let map = Rc::new(<ConcurrentSkipListMap<Hooked, ()>>::with_seed(13));
+        for key in (0..1000).step_by(2) {
+            map.insert(Hooked(key), ());
+        }
+        map.insert(Hooked(1), ());
+
+        let mut moves = 0;
+        let writer = map.clone();
+        HOOK.with(|hook| {
+            *hook.borrow_mut() = Some(Box::new(move |key| match key {
+                _ if moves == RANGE_ATTEMPTS => {}
+                0 => {
+                    writer.insert(Hooked(999), ());
+                    writer.remove(&Hooked(1));
+                }
+                500 => {
+                    writer.insert(Hooked(1), ());
+                    writer.remove(&Hooked(999));
+                    moves += 1;
+                }
+                _ => {}
+            }))
+        });
+        let odd: Vec<_> = map
+            .range(..Hooked(u32::MAX))
+            .map(|(k, _)| k.0)
+            .filter(|k| k % 2 == 1)
+            .collect();
+        HOOK.with(|hook| hook.borrow_mut().take());
+        assert_eq!(odd, [1]);
+        assert_eq!(map.len(), 501);
+    }
+
+    #[test]
+    fn test_snapshot_behind_and_ahead() {
+        // the writer moves an odd key back by inserting it behind the reader before removing
+        // the one ahead, so the odd keys in the map are one key or a key and the one before
+        const KEYS: u32 = 2000;
+        const STEP: u32 = 400;
+
+        let map = Arc::new(<ConcurrentSkipListMap<u32, ()>>::with_seed(11));
+        for key in (0..KEYS).step_by(2) {
+            map.insert(key, ());
+        }
+        map.insert(KEYS - 1, ());
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let before = |key: u32| if key < STEP { KEYS - 1 } else { key - STEP };
+        let writer = {
+            let (map, stop) = (map.clone(), stop.clone());
+            thread::spawn(move || {
+                let mut key = KEYS - 1;
+                while !stop.load(Ordering::SeqCst) {
+                    map.insert(before(key), ());
+                    map.remove(&key);
+                    key = before(key);
+                }
+            })
+        };
+
+        for _ in 0..300 {
+            let odd: Vec<_> = map.iter().map(|(k, _)| k).filter(|k| k % 2 == 1).collect();
+            match odd[..] {
+                [_] => {}
+                [a, b] if before(a) == b || before(b) == a => {}
+                _ => panic!("inconsistent snapshot: {:?}", odd),
+            }
+        }
+        stop.store(true, Ordering::SeqCst);
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn test_range_under_writes() {
+        // range queries finish and stay sorted while writers keep changing every key they cover
+        let map = Arc::new(<ConcurrentSkipListMap<u32, u32>>::with_seed(7));
+        let stop = Arc::new(AtomicBool::new(false));
+        let writers: Vec<_> = (0..2)
+            .map(|t| {
+                let (map, stop) = (map.clone(), stop.clone());
+                thread::spawn(move || {
+                    let mut rng = Rng::new(t);
+                    while !stop.load(Ordering::SeqCst) {
+                        let key = (rng.next_u64() % 64) as u32;
+                        if rng.next_u64() & 1 == 0 {
+                            map.insert(key, key);
+                        } else {
+                            map.remove(&key);
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for _ in 0..2000 {
+            let entries: Vec<_> = map.range(16..48).collect();
+            assert!(entries.windows(2).all(|w| w[0].0 < w[1].0));
+            assert!(entries
+                .iter()
+                .all(|&(k, v)| (16..48).contains(&k) && k == v));
+        }
+        stop.store(true, Ordering::SeqCst);
+        writers.into_iter().for_each(|h| h.join().unwrap());
+    }
+}
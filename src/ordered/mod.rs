@@ -0,0 +1,7 @@
+pub mod concurrent_skip_list_map;
+pub mod skip_list_map;
+pub mod skip_list_set;
+
+pub use concurrent_skip_list_map::ConcurrentSkipListMap;
+pub use skip_list_map::SkipListMap;
+pub use skip_list_set::SkipListSet;
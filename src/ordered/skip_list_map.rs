@@ -0,0 +1,700 @@
+use crate::rng::Rng;
+
+use std::borrow::Borrow;
+use std::iter::FromIterator;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::ptr::NonNull;
+
+const MAX_LEVEL: usize = 32;
+
+type NodePtr<K, V> = NonNull<Node<K, V>>;
+
+// the search path of a key, None stands for the head
+type Path<K, V> = [Option<NodePtr<K, V>>; MAX_LEVEL];
+
+struct Link<K, V> {
+    next: Option<NodePtr<K, V>>,
+    // number of level 0 steps to next, or to the end when next is None
+    span: usize,
+}
+
+impl<K, V> Clone for Link<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for Link<K, V> {}
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    prev: Option<NodePtr<K, V>>,
+    links: Box<[Link<K, V>]>,
+}
+
+// Ordered map balanced by coin flips. Every link records how many elements it skips,
+// so rank queries take the same O(log n) expected time as lookups.
+pub struct SkipListMap<K, V> {
+    head: [Link<K, V>; MAX_LEVEL],
+    tail: Option<NodePtr<K, V>>,
+    level: usize,
+    len: usize,
+    rng: Rng,
+}
+
+unsafe impl<K: Send, V: Send> Send for SkipListMap<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for SkipListMap<K, V> {}
+
+impl<K, V> SkipListMap<K, V> {
+    fn links(&self, node: Option<NodePtr<K, V>>) -> &[Link<K, V>] {
+        match node {
+            Some(node) => unsafe { &(*node.as_ptr()).links },
+            None => &self.head,
+        }
+    }
+
+    // cond: node belongs to self
+    unsafe fn links_mut(&mut self, node: Option<NodePtr<K, V>>) -> &mut [Link<K, V>] {
+        match node {
+            Some(node) => &mut (*node.as_ptr()).links,
+            None => &mut self.head,
+        }
+    }
+
+    fn random_level(&mut self) -> usize {
+        // each node reaches the next level with probability 1/4
+        let mut level = 1;
+        while level < MAX_LEVEL && self.rng.next_u64() & 3 == 0 {
+            level += 1;
+        }
+        level
+    }
+
+    // cond: before is true for a prefix of the nodes
+    // returns the last node for which before is true and its 1-based rank
+    fn find_last<F>(&self, mut before: F) -> (Option<NodePtr<K, V>>, usize)
+    where
+        F: FnMut(&Node<K, V>) -> bool,
+    {
+        let mut x = None;
+        let mut rank = 0;
+        for i in (0..self.level).rev() {
+            loop {
+                let link = self.links(x)[i];
+                match link.next {
+                    Some(next) if before(unsafe { next.as_ref() }) => {
+                        rank += link.span;
+                        x = Some(next);
+                    }
+                    _ => break,
+                }
+            }
+        }
+        (x, rank)
+    }
+
+    // like find_last, but records the last node of every level
+    fn find_path<F>(&self, mut before: F) -> (Path<K, V>, [usize; MAX_LEVEL])
+    where
+        F: FnMut(&Node<K, V>) -> bool,
+    {
+        let mut path = [None; MAX_LEVEL];
+        let mut ranks = [0; MAX_LEVEL];
+        let mut x = None;
+        let mut rank = 0;
+        for i in (0..self.level).rev() {
+            loop {
+                let link = self.links(x)[i];
+                match link.next {
+                    Some(next) if before(unsafe { next.as_ref() }) => {
+                        rank += link.span;
+                        x = Some(next);
+                    }
+                    _ => break,
+                }
+            }
+            path[i] = x;
+            ranks[i] = rank;
+        }
+        (path, ranks)
+    }
+
+    // cond: path is the search path of node
+    unsafe fn unlink(&mut self, path: &Path<K, V>, ptr: NodePtr<K, V>) -> Node<K, V> {
+        let node = *Box::from_raw(ptr.as_ptr());
+        for (i, &pred) in path.iter().enumerate().take(self.level) {
+            let link = &mut self.links_mut(pred)[i];
+            if link.next == Some(ptr) {
+                link.span = link.span + node.links[i].span - 1;
+                link.next = node.links[i].next;
+            } else {
+                link.span -= 1;
+            }
+        }
+
+        match node.links[0].next {
+            Some(mut next) => next.as_mut().prev = node.prev,
+            None => self.tail = node.prev,
+        }
+        while self.level > 1 && self.head[self.level - 1].next.is_none() {
+            self.level -= 1;
+        }
+        self.len -= 1;
+        node
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<NodePtr<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (x, _) = self.find_last(|node| node.key.borrow() < key);
+        let next = self.links(x)[0].next?;
+        if unsafe { next.as_ref() }.key.borrow() == key {
+            Some(next)
+        } else {
+            None
+        }
+    }
+}
+
+impl<K, V> SkipListMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_rng(Rng::from_entropy())
+    }
+
+    // the same seed and the same operations always build the same skip list
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(Rng::new(seed))
+    }
+
+    fn with_rng(rng: Rng) -> Self {
+        Self {
+            head: [Link {
+                next: None,
+                span: 0,
+            }; MAX_LEVEL],
+            tail: None,
+            level: 1,
+            len: 0,
+            rng,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn clear(&mut self) {
+        let mut ptr = self.head[0].next;
+        while let Some(node) = ptr {
+            let node = unsafe { Box::from_raw(node.as_ptr()) };
+            ptr = node.links[0].next;
+        }
+        for link in self.head.iter_mut() {
+            link.next = None;
+            link.span = 0;
+        }
+        self.tail = None;
+        self.level = 1;
+        self.len = 0;
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.head[0].next.map(|node| unsafe {
+            let node = &*node.as_ptr();
+            (&node.key, &node.value)
+        })
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.tail.map(|node| unsafe {
+            let node = &*node.as_ptr();
+            (&node.key, &node.value)
+        })
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let first = self.head[0].next?;
+        // the head precedes the first node on every level
+        let node = unsafe { self.unlink(&[None; MAX_LEVEL], first) };
+        Some((node.key, node.value))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let last = self.tail?;
+        let (path, _) = self.find_path(|node| NonNull::from(node) != last);
+        let node = unsafe { self.unlink(&path, last) };
+        Some((node.key, node.value))
+    }
+
+    // returns the entry at the 0-based position n in key order
+    pub fn nth(&self, n: usize) -> Option<(&K, &V)> {
+        if n >= self.len {
+            return None;
+        }
+        let mut x = None;
+        let mut rank = 0;
+        for i in (0..self.level).rev() {
+            loop {
+                let link = self.links(x)[i];
+                match link.next {
+                    Some(next) if rank + link.span <= n + 1 => {
+                        rank += link.span;
+                        x = Some(next);
+                    }
+                    _ => break,
+                }
+            }
+            if rank == n + 1 {
+                break;
+            }
+        }
+        x.map(|node| unsafe {
+            let node = &*node.as_ptr();
+            (&node.key, &node.value)
+        })
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            iter: RawIter {
+                front: self.head[0].next,
+                back: self.tail,
+            },
+            len: self.len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K: Ord, V> SkipListMap<K, V> {
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (mut path, mut ranks) = self.find_path(|node| node.key < key);
+        if let Some(mut next) = self.links(path[0])[0].next {
+            let next = unsafe { next.as_mut() };
+            if next.key == key {
+                return Some(std::mem::replace(&mut next.value, value));
+            }
+        }
+
+        let level = self.random_level();
+        if level > self.level {
+            for i in self.level..level {
+                path[i] = None;
+                ranks[i] = 0;
+                self.head[i].span = self.len;
+            }
+            self.level = level;
+        }
+
+        let links = vec![
+            Link {
+                next: None,
+                span: 0,
+            };
+            level
+        ];
+        let node = Box::new(Node {
+            key,
+            value,
+            prev: path[0],
+            links: links.into_boxed_slice(),
+        });
+        let ptr = NonNull::from(Box::leak(node));
+
+        unsafe {
+            for i in 0..level {
+                let pred = &mut self.links_mut(path[i])[i];
+                let link = &mut (*ptr.as_ptr()).links[i];
+                link.next = pred.next;
+                link.span = pred.span - (ranks[0] - ranks[i]);
+                pred.next = Some(ptr);
+                pred.span = ranks[0] - ranks[i] + 1;
+            }
+            for (i, &pred) in path.iter().enumerate().take(self.level).skip(level) {
+                self.links_mut(pred)[i].span += 1;
+            }
+            match (*ptr.as_ptr()).links[0].next {
+                Some(mut next) => next.as_mut().prev = Some(ptr),
+                None => self.tail = Some(ptr),
+            }
+        }
+        self.len += 1;
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key)
+            .map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key)
+            .map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (path, _) = self.find_path(|node| node.key.borrow() < key);
+        let next = self.links(path[0])[0].next?;
+        if unsafe { next.as_ref() }.key.borrow() != key {
+            return None;
+        }
+        let node = unsafe { self.unlink(&path, next) };
+        Some((node.key, node.value))
+    }
+
+    // returns the 0-based position of key in key order
+    pub fn rank_of<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (x, rank) = self.find_last(|node| node.key.borrow() <= key);
+        let node = x?;
+        if unsafe { node.as_ref() }.key.borrow() == key {
+            Some(rank - 1)
+        } else {
+            None
+        }
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let front = match range.start_bound() {
+            Bound::Included(start) => {
+                let (x, _) = self.find_last(|node| node.key.borrow() < start);
+                self.links(x)[0].next
+            }
+            Bound::Excluded(start) => {
+                let (x, _) = self.find_last(|node| node.key.borrow() <= start);
+                self.links(x)[0].next
+            }
+            Bound::Unbounded => self.head[0].next,
+        };
+        let back = match range.end_bound() {
+            Bound::Included(end) => self.find_last(|node| node.key.borrow() <= end).0,
+            Bound::Excluded(end) => self.find_last(|node| node.key.borrow() < end).0,
+            Bound::Unbounded => self.tail,
+        };
+
+        let iter = match (front, back) {
+            (Some(front), Some(back)) if unsafe { front.as_ref().key <= back.as_ref().key } => {
+                RawIter {
+                    front: Some(front),
+                    back: Some(back),
+                }
+            }
+            _ => RawIter {
+                front: None,
+                back: None,
+            },
+        };
+        Range {
+            iter,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V> Drop for SkipListMap<K, V> {
+    fn drop(&mut self) {
+        self.clear()
+    }
+}
+
+impl<K, V> Default for SkipListMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SkipListMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+// --------------------------------
+// begin: IntoIter
+
+pub struct IntoIter<K, V>(SkipListMap<K, V>);
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<(K, V)> {
+        self.0.pop_first()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<K, V> IntoIterator for SkipListMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter(self)
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        self.0.pop_last()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {
+    fn len(&self) -> usize {
+        self.0.len
+    }
+}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+// end: IntoIter
+// ------------------------------------------
+
+struct RawIter<K, V> {
+    front: Option<NodePtr<K, V>>,
+    back: Option<NodePtr<K, V>>,
+}
+
+impl<K, V> RawIter<K, V> {
+    fn next_front(&mut self) -> Option<NodePtr<K, V>> {
+        let node = self.front?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.front = unsafe { node.as_ref().links[0].next };
+        }
+        Some(node)
+    }
+
+    fn next_back(&mut self) -> Option<NodePtr<K, V>> {
+        let node = self.back?;
+        if self.front == self.back {
+            self.front = None;
+            self.back = None;
+        } else {
+            self.back = unsafe { node.as_ref().prev };
+        }
+        Some(node)
+    }
+}
+
+// ------------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, K, V> {
+    iter: RawIter<K, V>,
+    len: usize,
+    _marker: PhantomData<&'a SkipListMap<K, V>>,
+}
+
+unsafe impl<K: Sync, V: Sync> Send for Iter<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Iter<'_, K, V> {}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.iter.next_front().map(|node| unsafe {
+            self.len -= 1;
+            let node = &*node.as_ptr();
+            (&node.key, &node.value)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SkipListMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        self.iter.next_back().map(|node| unsafe {
+            self.len -= 1;
+            let node = &*node.as_ptr();
+            (&node.key, &node.value)
+        })
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl<'a, K, V> FusedIterator for Iter<'a, K, V> {}
+
+// end: Iter
+// ------------------------------------------
+
+// ------------------------------------------
+// begin: Range
+
+pub struct Range<'a, K, V> {
+    iter: RawIter<K, V>,
+    _marker: PhantomData<&'a SkipListMap<K, V>>,
+}
+
+unsafe impl<K: Sync, V: Sync> Send for Range<'_, K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for Range<'_, K, V> {}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.iter.next_front().map(|node| unsafe {
+            let node = &*node.as_ptr();
+            (&node.key, &node.value)
+        })
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Range<'a, K, V> {
+    fn next_back(&mut self) -> Option<(&'a K, &'a V)> {
+        self.iter.next_back().map(|node| unsafe {
+            let node = &*node.as_ptr();
+            (&node.key, &node.value)
+        })
+    }
+}
+
+impl<'a, K, V> FusedIterator for Range<'a, K, V> {}
+
+// end: Range
+// ------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::SkipListMap;
+    use crate::rng::Rng;
+
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_skip_list_map() {
+        let mut map = <SkipListMap<i32, String>>::with_seed(42);
+        assert!(map.is_empty());
+        assert_eq!(map.first(), None);
+        assert_eq!(map.nth(0), None);
+
+        for &k in &[5, 1, 9, 3, 7] {
+            assert_eq!(map.insert(k, k.to_string()), None);
+        }
+        assert_eq!(
+            map.insert(3, String::from("three")),
+            Some(String::from("3"))
+        );
+        assert_eq!(map.len(), 5);
+        assert_eq!(map.get(&3).unwrap(), "three");
+        assert_eq!(map.get(&4), None);
+        map.get_mut(&9).unwrap().push('!');
+
+        assert_eq!(map.first().map(|(k, _)| *k), Some(1));
+        assert_eq!(map.last().map(|(_, v)| v.as_str()), Some("9!"));
+        assert_eq!(map.nth(2).map(|(k, _)| *k), Some(5));
+        assert_eq!(map.rank_of(&7), Some(3));
+        assert_eq!(map.rank_of(&6), None);
+
+        let keys = |iter: &mut dyn Iterator<Item = (&i32, &String)>| {
+            iter.map(|(k, _)| *k).collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&mut map.range(3..=7)), [3, 5, 7]);
+        assert_eq!(keys(&mut map.range(2..7)), [3, 5]);
+        assert_eq!(keys(&mut map.range(..4).rev()), [3, 1]);
+        assert_eq!(keys(&mut map.range(8..)), [9]);
+        assert_eq!(keys(&mut map.range(6..6)), []);
+        assert_eq!(keys(&mut map.range(10..)), []);
+        assert_eq!(keys(&mut map.iter().rev()), [9, 7, 5, 3, 1]);
+
+        assert_eq!(map.remove(&5).unwrap(), "5");
+        assert_eq!(map.remove(&5), None);
+        assert_eq!(map.pop_first().unwrap().0, 1);
+        assert_eq!(map.pop_last().unwrap().0, 9);
+        assert_eq!(
+            map.into_iter().map(|(_, v)| v).collect::<Vec<_>>(),
+            ["three", "7"]
+        );
+    }
+
+    #[test]
+    fn test_against_btree_map() {
+        let mut rng = Rng::new(7);
+        let mut map = <SkipListMap<u32, u32>>::with_seed(7);
+        let mut expected = BTreeMap::new();
+
+        for i in 0..2000 {
+            let key = (rng.next_u64() % 500) as u32;
+            if rng.next_u64() % 3 == 1 {
+                assert_eq!(map.remove(&key), expected.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, i), expected.insert(key, i));
+            }
+        }
+
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected.iter()));
+        for (rank, (key, value)) in expected.iter().enumerate() {
+            assert_eq!(map.nth(rank), Some((key, value)));
+            assert_eq!(map.rank_of(key), Some(rank));
+        }
+        assert!(map.range(100..=300).eq(expected.range(100..=300)));
+        assert!(map.range(..250).rev().eq(expected.range(..250).rev()));
+    }
+}
@@ -0,0 +1,249 @@
+use super::skip_list_map::{self, SkipListMap};
+
+use std::borrow::Borrow;
+use std::iter::FromIterator;
+use std::iter::FusedIterator;
+use std::ops::RangeBounds;
+
+pub struct SkipListSet<K>(SkipListMap<K, ()>);
+
+impl<K> SkipListSet<K> {
+    pub fn new() -> Self {
+        Self(SkipListMap::new())
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self(SkipListMap::with_seed(seed))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear()
+    }
+
+    pub fn first(&self) -> Option<&K> {
+        self.0.first().map(|(key, _)| key)
+    }
+
+    pub fn last(&self) -> Option<&K> {
+        self.0.last().map(|(key, _)| key)
+    }
+
+    pub fn pop_first(&mut self) -> Option<K> {
+        self.0.pop_first().map(|(key, _)| key)
+    }
+
+    pub fn pop_last(&mut self) -> Option<K> {
+        self.0.pop_last().map(|(key, _)| key)
+    }
+
+    pub fn nth(&self, n: usize) -> Option<&K> {
+        self.0.nth(n).map(|(key, _)| key)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K> {
+        Iter(self.0.iter())
+    }
+}
+
+impl<K: Ord> SkipListSet<K> {
+    // returns false if the set already contains key
+    pub fn insert(&mut self, key: K) -> bool {
+        self.0.insert(key, ()).is_none()
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.0.contains_key(key)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.0.remove(key).is_some()
+    }
+
+    pub fn take<Q>(&mut self, key: &Q) -> Option<K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.0.remove_entry(key).map(|(key, _)| key)
+    }
+
+    pub fn rank_of<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.0.rank_of(key)
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        Range(self.0.range(range))
+    }
+}
+
+impl<K> Default for SkipListSet<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord> FromIterator<K> for SkipListSet<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        Self(iter.into_iter().map(|key| (key, ())).collect())
+    }
+}
+
+// --------------------------------
+// begin: IntoIter
+
+pub struct IntoIter<K>(skip_list_map::IntoIter<K, ()>);
+
+impl<K> Iterator for IntoIter<K> {
+    type Item = K;
+    fn next(&mut self) -> Option<K> {
+        self.0.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K> IntoIterator for SkipListSet<K> {
+    type Item = K;
+    type IntoIter = IntoIter<K>;
+    fn into_iter(self) -> IntoIter<K> {
+        IntoIter(self.0.into_iter())
+    }
+}
+
+impl<K> DoubleEndedIterator for IntoIter<K> {
+    fn next_back(&mut self) -> Option<K> {
+        self.0.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<K> ExactSizeIterator for IntoIter<K> {}
+
+impl<K> FusedIterator for IntoIter<K> {}
+
+// end: IntoIter
+// ------------------------------------------
+
+// ------------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, K>(skip_list_map::Iter<'a, K, ()>);
+
+impl<'a, K> Iterator for Iter<'a, K> {
+    type Item = &'a K;
+    fn next(&mut self) -> Option<&'a K> {
+        self.0.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a, K> IntoIterator for &'a SkipListSet<K> {
+    type Item = &'a K;
+    type IntoIter = Iter<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K> DoubleEndedIterator for Iter<'a, K> {
+    fn next_back(&mut self) -> Option<&'a K> {
+        self.0.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<'a, K> ExactSizeIterator for Iter<'a, K> {}
+
+impl<'a, K> FusedIterator for Iter<'a, K> {}
+
+// end: Iter
+// ------------------------------------------
+
+// ------------------------------------------
+// begin: Range
+
+pub struct Range<'a, K>(skip_list_map::Range<'a, K, ()>);
+
+impl<'a, K> Iterator for Range<'a, K> {
+    type Item = &'a K;
+    fn next(&mut self) -> Option<&'a K> {
+        self.0.next().map(|(key, _)| key)
+    }
+}
+
+impl<'a, K> DoubleEndedIterator for Range<'a, K> {
+    fn next_back(&mut self) -> Option<&'a K> {
+        self.0.next_back().map(|(key, _)| key)
+    }
+}
+
+impl<'a, K> FusedIterator for Range<'a, K> {}
+
+// end: Range
+// ------------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::SkipListSet;
+
+    #[test]
+    fn test_skip_list_set() {
+        let mut set = <SkipListSet<u32>>::with_seed(1);
+        for key in (0..100).rev().step_by(3) {
+            assert!(set.insert(key));
+        }
+        assert!(!set.insert(99));
+        assert_eq!(set.len(), 34);
+        assert_eq!(set.first(), Some(&0));
+        assert_eq!(set.last(), Some(&99));
+        assert!(set.contains(&42));
+        assert!(!set.contains(&43));
+        assert_eq!(set.nth(10), Some(&30));
+        assert_eq!(set.rank_of(&30), Some(10));
+
+        assert_eq!(set.range(10..20).copied().collect::<Vec<_>>(), [12, 15, 18]);
+        assert!(set.remove(&15));
+        assert!(!set.remove(&15));
+        assert_eq!(set.take(&18), Some(18));
+        assert_eq!(set.range(10..20).rev().copied().collect::<Vec<_>>(), [12]);
+
+        assert_eq!(set.pop_first(), Some(0));
+        assert_eq!(set.pop_last(), Some(99));
+        assert_eq!(set.iter().len(), 30);
+        assert!(set
+            .into_iter()
+            .eq((3..97).step_by(3).filter(|&k| k != 15 && k != 18)));
+
+        let set: SkipListSet<char> = "skip list".chars().collect();
+        assert_eq!(set.iter().collect::<String>(), " iklpst");
+    }
+}
@@ -2,27 +2,25 @@ use std::alloc::Layout;
 use std::mem::{align_of, size_of};
 use std::ptr::NonNull;
 
+// zero-sized types take no memory, their arrays are dangling with any capacity
 pub struct RawArray<T> {
     pub arr: NonNull<T>,
     pub cap: usize,
 }
 
-
 impl<T> RawArray<T> {
     pub unsafe fn alloc(capacity: usize) -> Self {
-        assert!(size_of::<T>() != 0);
-
-        if capacity == 0 {
+        if capacity == 0 || size_of::<T>() == 0 {
             return Self {
                 arr: NonNull::dangling(),
-                cap: 0,
+                cap: capacity,
             };
         }
 
         let alloc_size = capacity
             .checked_mul(size_of::<T>())
             .and_then(|size| {
-                if size > isize::max_value() as usize {
+                if size > isize::MAX as usize {
                     None
                 } else {
                     Some(size)
@@ -43,6 +41,10 @@ impl<T> RawArray<T> {
     }
 
     pub unsafe fn dealloc(&mut self) {
+        if self.cap == 0 || size_of::<T>() == 0 {
+            self.cap = 0;
+            return;
+        }
         let alloc_size = self.cap * size_of::<T>();
         let layout = Layout::from_size_align_unchecked(alloc_size, align_of::<T>());
         let ptr = self.arr.as_ptr();
@@ -50,22 +52,40 @@ impl<T> RawArray<T> {
         self.cap = 0;
     }
 
-    pub unsafe fn offset(&self, index: usize)->*mut T{
-        self.arr.as_ptr().offset(index as isize)
-    }
+    // cond: capacity > 0
+    pub unsafe fn realloc(&mut self, capacity: usize) {
+        if self.cap == 0 || size_of::<T>() == 0 {
+            *self = Self::alloc(capacity);
+            return;
+        }
 
-    pub unsafe fn shadow_clone(&self)->Self{
-        Self{
-            arr: self.arr,
-            cap: self.cap
+        let alloc_size = capacity
+            .checked_mul(size_of::<T>())
+            .filter(|&size| size <= isize::MAX as usize)
+            .expect("capacity overflow");
+        let old_layout =
+            Layout::from_size_align_unchecked(self.cap * size_of::<T>(), align_of::<T>());
+
+        let ptr =
+            std::alloc::realloc(self.arr.as_ptr() as *mut u8, old_layout, alloc_size) as *mut T;
+        if ptr.is_null() {
+            std::alloc::handle_alloc_error(Layout::from_size_align_unchecked(
+                alloc_size,
+                align_of::<T>(),
+            ));
         }
+        self.arr = NonNull::new_unchecked(ptr);
+        self.cap = capacity;
     }
 
-    pub unsafe fn as_slice_mut(&mut self)->&mut [T]{
-        std::slice::from_raw_parts_mut(self.arr.as_ptr(), self.cap)
+    pub unsafe fn offset(&self, index: usize) -> *mut T {
+        self.arr.as_ptr().add(index)
     }
 
-    pub unsafe fn as_slice(&self)->&[T]{
-        std::slice::from_raw_parts(self.arr.as_ptr(), self.cap)
+    pub unsafe fn shadow_clone(&self) -> Self {
+        Self {
+            arr: self.arr,
+            cap: self.cap,
+        }
     }
 }
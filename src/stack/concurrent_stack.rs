@@ -0,0 +1,333 @@
+use super::VecStack;
+use crate::epoch::{self, Guard};
+use crate::rng::Rng;
+
+use std::cell::RefCell;
+use std::hint;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+struct Node<T> {
+    // moved out by the popper, the node itself is freed later
+    elem: ManuallyDrop<T>,
+    next: *mut Node<T>,
+}
+
+impl<T> Node<T> {
+    fn alloc(elem: T) -> *mut Self {
+        Box::into_raw(Box::new(Self {
+            elem: ManuallyDrop::new(elem),
+            next: ptr::null_mut(),
+        }))
+    }
+}
+
+// Treiber stack. Popped nodes are reclaimed through epochs, so a node can not be
+// freed and reused while another thread still compares against its address.
+pub struct ConcurrentStack<T> {
+    head: AtomicPtr<Node<T>>,
+}
+
+unsafe impl<T: Send> Send for ConcurrentStack<T> {}
+unsafe impl<T: Send> Sync for ConcurrentStack<T> {}
+
+impl<T> ConcurrentStack<T> {
+    pub fn new() -> Self {
+        Self {
+            head: AtomicPtr::default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.load(Ordering::Acquire).is_null()
+    }
+
+    fn try_push(&self, node: *mut Node<T>) -> bool {
+        let head = self.head.load(Ordering::Relaxed);
+        unsafe { (*node).next = head };
+        self.head
+            .compare_exchange(head, node, Ordering::Release, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    // fails if another thread changed the head in between
+    fn try_pop(&self, guard: &Guard) -> Result<Option<T>, ()> {
+        let head = self.head.load(Ordering::Acquire);
+        let next = match unsafe { head.as_ref() } {
+            Some(node) => node.next,
+            None => return Ok(None),
+        };
+        self.head
+            .compare_exchange(head, next, Ordering::Acquire, Ordering::Relaxed)
+            .map_err(|_| ())?;
+        unsafe {
+            let elem = ptr::read(&(*head).elem);
+            guard.defer_destroy(head);
+            Ok(Some(ManuallyDrop::into_inner(elem)))
+        }
+    }
+
+    pub fn push(&self, elem: T) {
+        let node = Node::alloc(elem);
+        while !self.try_push(node) {
+            hint::spin_loop();
+        }
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+        loop {
+            if let Ok(elem) = self.try_pop(&guard) {
+                return elem;
+            }
+            hint::spin_loop();
+        }
+    }
+
+    // takes every element at once, the top of the stack stays on the top
+    pub fn pop_all(&self) -> VecStack<T> {
+        let guard = epoch::pin();
+        let mut curr = self.head.swap(ptr::null_mut(), Ordering::Acquire);
+        let mut elems = Vec::new();
+        while !curr.is_null() {
+            unsafe {
+                // other poppers may still read the links
+                elems.push(ManuallyDrop::into_inner(ptr::read(&(*curr).elem)));
+                let next = (*curr).next;
+                guard.defer_destroy(curr);
+                curr = next;
+            }
+        }
+
+        let mut stack = VecStack::new();
+        for elem in elems.into_iter().rev() {
+            stack.push(elem);
+        }
+        stack
+    }
+}
+
+impl<T> Default for ConcurrentStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for ConcurrentStack<T> {
+    fn drop(&mut self) {
+        let mut curr = *self.head.get_mut();
+        while !curr.is_null() {
+            let mut node = unsafe { Box::from_raw(curr) };
+            unsafe { ManuallyDrop::drop(&mut node.elem) };
+            curr = node.next;
+        }
+    }
+}
+
+const ELIMINATION_SLOTS: usize = 8;
+const ELIMINATION_SPINS: usize = 64;
+
+thread_local! {
+    static SLOT_RNG: RefCell<Rng> = RefCell::new(Rng::from_entropy());
+}
+
+// marks a slot whose offered node has been taken by a popper
+fn taken<T>() -> *mut Node<T> {
+    1 as *mut Node<T>
+}
+
+// Treiber stack with an elimination array. When the head is contended, a push offers
+// its node in a random slot for a while, and a pop takes an offered node instead of
+// touching the head, so both complete without the stack.
+pub struct EliminationStack<T> {
+    stack: ConcurrentStack<T>,
+    slots: Box<[AtomicPtr<Node<T>>]>,
+}
+
+unsafe impl<T: Send> Send for EliminationStack<T> {}
+unsafe impl<T: Send> Sync for EliminationStack<T> {}
+
+impl<T> EliminationStack<T> {
+    pub fn new() -> Self {
+        Self::with_slots(ELIMINATION_SLOTS)
+    }
+
+    // panics if slots is 0
+    pub fn with_slots(slots: usize) -> Self {
+        assert!(slots > 0, "elimination stack needs a slot");
+        Self {
+            stack: ConcurrentStack::new(),
+            slots: (0..slots).map(|_| AtomicPtr::default()).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    fn random_slot(&self) -> &AtomicPtr<Node<T>> {
+        let r = SLOT_RNG.with(|rng| rng.borrow_mut().next_u64());
+        &self.slots[(r % self.slots.len() as u64) as usize]
+    }
+
+    fn offer(&self, node: *mut Node<T>) -> bool {
+        let slot = self.random_slot();
+        let offered = slot
+            .compare_exchange(ptr::null_mut(), node, Ordering::Release, Ordering::Relaxed)
+            .is_ok();
+        if !offered {
+            return false;
+        }
+        for _ in 0..ELIMINATION_SPINS {
+            if slot.load(Ordering::Acquire) == taken() {
+                slot.store(ptr::null_mut(), Ordering::Release);
+                return true;
+            }
+            hint::spin_loop();
+        }
+        match slot.compare_exchange(node, ptr::null_mut(), Ordering::Relaxed, Ordering::Acquire) {
+            Ok(_) => false,
+            Err(_) => {
+                // taken at the last moment
+                slot.store(ptr::null_mut(), Ordering::Release);
+                true
+            }
+        }
+    }
+
+    fn take(&self) -> Option<T> {
+        let slot = self.random_slot();
+        let node = slot.load(Ordering::Acquire);
+        if node.is_null() || node == taken() {
+            return None;
+        }
+        // the node is only read after it has been taken
+        slot.compare_exchange(node, taken(), Ordering::Acquire, Ordering::Relaxed)
+            .ok()?;
+        let node = unsafe { Box::from_raw(node) };
+        Some(ManuallyDrop::into_inner(node.elem))
+    }
+
+    pub fn push(&self, elem: T) {
+        let node = Node::alloc(elem);
+        while !self.stack.try_push(node) && !self.offer(node) {}
+    }
+
+    pub fn pop(&self) -> Option<T> {
+        let guard = epoch::pin();
+        loop {
+            if let Ok(elem) = self.stack.try_pop(&guard) {
+                return elem;
+            }
+            if let Some(elem) = self.take() {
+                return Some(elem);
+            }
+        }
+    }
+
+    pub fn pop_all(&self) -> VecStack<T> {
+        self.stack.pop_all()
+    }
+}
+
+impl<T> Default for EliminationStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ConcurrentStack, EliminationStack};
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    const THREADS: usize = 8;
+    const PER_THREAD: usize = 10_000;
+
+    // every thread pushes its own range and pops as many elements as it pushed
+    macro_rules! stress {
+        ($stack:expr) => {{
+            let stack = Arc::new($stack);
+            let barrier = Arc::new(Barrier::new(THREADS));
+            let handles: Vec<_> = (0..THREADS)
+                .map(|t| {
+                    let (stack, barrier) = (stack.clone(), barrier.clone());
+                    thread::spawn(move || {
+                        barrier.wait();
+                        let mut popped = Vec::new();
+                        for i in 0..PER_THREAD {
+                            stack.push(t * PER_THREAD + i);
+                            if i % 2 == 1 {
+                                popped.extend(stack.pop());
+                                popped.extend(stack.pop());
+                            }
+                        }
+                        popped
+                    })
+                })
+                .collect();
+
+            let mut popped: Vec<usize> = handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect();
+            popped.extend(stack.pop_all().into_vec());
+            assert!(stack.is_empty());
+            popped.sort_unstable();
+            assert!(popped.into_iter().eq(0..THREADS * PER_THREAD));
+        }};
+    }
+
+    #[test]
+    fn test_concurrent_stack() {
+        let stack = ConcurrentStack::new();
+        assert!(stack.is_empty());
+        assert_eq!(stack.pop(), None);
+        for i in 0..5 {
+            stack.push(i);
+        }
+        assert_eq!(stack.pop(), Some(4));
+
+        let all = stack.pop_all();
+        assert!(stack.is_empty());
+        assert_eq!(all.top(), Some(&3));
+        assert_eq!(all.into_vec(), [0, 1, 2, 3]);
+
+        let dropped = Arc::new(AtomicUsize::new(0));
+        struct Counted(Arc<AtomicUsize>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        let stack = ConcurrentStack::new();
+        for _ in 0..10 {
+            stack.push(Counted(dropped.clone()));
+        }
+        drop(stack.pop());
+        assert_eq!(dropped.load(Ordering::SeqCst), 1);
+        drop(stack);
+        assert_eq!(dropped.load(Ordering::SeqCst), 10);
+    }
+
+    #[test]
+    fn test_stress() {
+        stress!(ConcurrentStack::new());
+    }
+
+    #[test]
+    fn test_elimination_stack() {
+        let stack = EliminationStack::with_slots(2);
+        stack.push("a");
+        stack.push("b");
+        assert_eq!(stack.pop(), Some("b"));
+        assert_eq!(stack.pop_all().into_vec(), ["a"]);
+        assert_eq!(stack.pop(), None);
+
+        stress!(EliminationStack::new());
+    }
+}
@@ -1,2 +1,4 @@
+pub mod concurrent_stack;
 pub mod vec_stack;
-pub use vec_stack::VecStack;
\ No newline at end of file
+pub use concurrent_stack::{ConcurrentStack, EliminationStack};
+pub use vec_stack::VecStack;
@@ -43,3 +43,9 @@ impl<T> VecStack<T> {
         self.0
     }
 }
+
+impl<T> Default for VecStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
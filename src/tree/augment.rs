@@ -0,0 +1,149 @@
+use std::cmp;
+use std::ops::Add;
+
+/// A summary kept for every subtree of an augmented tree, computed from the entries of the subtree.
+pub trait Augment<K, V> {
+    type Summary;
+
+    /// The summary of a single entry.
+    fn summarize(key: &K, value: &V) -> Self::Summary;
+
+    /// The summary of two runs of entries, `left` coming before `right` in key order.
+    fn combine(left: &Self::Summary, right: &Self::Summary) -> Self::Summary;
+}
+
+// keeps no summary
+pub struct NoAugment;
+
+impl<K, V> Augment<K, V> for NoAugment {
+    type Summary = ();
+
+    fn summarize(_: &K, _: &V) {}
+
+    fn combine(_: &(), _: &()) {}
+}
+
+// the sum of the values or elements
+pub struct Sum;
+
+impl<K, V: Clone + Add<Output = V>> Augment<K, V> for Sum {
+    type Summary = V;
+
+    fn summarize(_: &K, value: &V) -> V {
+        value.clone()
+    }
+
+    fn combine(left: &V, right: &V) -> V {
+        left.clone() + right.clone()
+    }
+}
+
+// the least value or element
+pub struct Min;
+
+impl<K, V: Ord + Clone> Augment<K, V> for Min {
+    type Summary = V;
+
+    fn summarize(_: &K, value: &V) -> V {
+        value.clone()
+    }
+
+    fn combine(left: &V, right: &V) -> V {
+        cmp::min(left, right).clone()
+    }
+}
+
+// the greatest value or element
+pub struct Max;
+
+impl<K, V: Ord + Clone> Augment<K, V> for Max {
+    type Summary = V;
+
+    fn summarize(_: &K, value: &V) -> V {
+        value.clone()
+    }
+
+    fn combine(left: &V, right: &V) -> V {
+        cmp::max(left, right).clone()
+    }
+}
+
+/// An associative operation with an identity, summarizing runs of elements of a sequence.
+pub trait Monoid<T> {
+    type Summary: Clone;
+
+    /// The summary of no elements.
+    fn identity() -> Self::Summary;
+
+    /// The summary of a single element.
+    fn summarize(elem: &T) -> Self::Summary;
+
+    /// The summary of two runs of elements, `left` coming before `right`.
+    fn combine(left: &Self::Summary, right: &Self::Summary) -> Self::Summary;
+}
+
+impl<T> Monoid<T> for NoAugment {
+    type Summary = ();
+
+    fn identity() {}
+
+    fn summarize(_: &T) {}
+
+    fn combine(_: &(), _: &()) {}
+}
+
+impl<T: Clone + Default + Add<Output = T>> Monoid<T> for Sum {
+    type Summary = T;
+
+    fn identity() -> T {
+        T::default()
+    }
+
+    fn summarize(elem: &T) -> T {
+        elem.clone()
+    }
+
+    fn combine(left: &T, right: &T) -> T {
+        left.clone() + right.clone()
+    }
+}
+
+// None for no elements
+impl<T: Ord + Clone> Monoid<T> for Min {
+    type Summary = Option<T>;
+
+    fn identity() -> Option<T> {
+        None
+    }
+
+    fn summarize(elem: &T) -> Option<T> {
+        Some(elem.clone())
+    }
+
+    fn combine(left: &Option<T>, right: &Option<T>) -> Option<T> {
+        match (left, right) {
+            (Some(left), Some(right)) => Some(cmp::min(left, right).clone()),
+            (left, right) => left.clone().or_else(|| right.clone()),
+        }
+    }
+}
+
+// None for no elements
+impl<T: Ord + Clone> Monoid<T> for Max {
+    type Summary = Option<T>;
+
+    fn identity() -> Option<T> {
+        None
+    }
+
+    fn summarize(elem: &T) -> Option<T> {
+        Some(elem.clone())
+    }
+
+    fn combine(left: &Option<T>, right: &Option<T>) -> Option<T> {
+        match (left, right) {
+            (Some(left), Some(right)) => Some(cmp::max(left, right).clone()),
+            (left, right) => left.clone().or_else(|| right.clone()),
+        }
+    }
+}
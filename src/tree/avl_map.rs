@@ -0,0 +1,864 @@
+use super::binary_iter::{after_start, before_end, BinaryNode, RawIter};
+
+use std::borrow::Borrow;
+use std::cmp::{self, Ordering};
+use std::fmt::Debug;
+use std::iter::FromIterator;
+use std::iter::FusedIterator;
+use std::mem;
+use std::ops::RangeBounds;
+use std::ptr::{self, NonNull};
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // the height of a leaf is 1
+    height: u8,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K, V> Node<K, V> {
+    fn new(key: K, value: V) -> Box<Self> {
+        Box::new(Self {
+            key,
+            value,
+            height: 1,
+            left: None,
+            right: None,
+        })
+    }
+
+    fn update(&mut self) {
+        self.height = cmp::max(height(&self.left), height(&self.right)) + 1;
+    }
+
+    fn balance_factor(&self) -> i32 {
+        height(&self.left) as i32 - height(&self.right) as i32
+    }
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+fn height<K, V>(link: &Link<K, V>) -> u8 {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+// cond: link and its left child are not None
+fn rotate_right<K, V>(link: &mut Link<K, V>) {
+    let mut node = link.take().unwrap();
+    let mut left = node.left.take().unwrap();
+    node.left = left.right.take();
+    node.update();
+    left.right = Some(node);
+    left.update();
+    *link = Some(left);
+}
+
+// cond: link and its right child are not None
+fn rotate_left<K, V>(link: &mut Link<K, V>) {
+    let mut node = link.take().unwrap();
+    let mut right = node.right.take().unwrap();
+    node.right = right.left.take();
+    node.update();
+    right.left = Some(node);
+    right.update();
+    *link = Some(right);
+}
+
+// restores the balance of a node whose subtrees differ in height by at most 2
+fn rebalance<K, V>(link: &mut Link<K, V>) {
+    let node = match link {
+        Some(node) => node,
+        None => return,
+    };
+    node.update();
+    let factor = node.balance_factor();
+    if factor > 1 {
+        if node.left.as_ref().unwrap().balance_factor() < 0 {
+            rotate_left(&mut node.left);
+        }
+        rotate_right(link);
+    } else if factor < -1 {
+        if node.right.as_ref().unwrap().balance_factor() > 0 {
+            rotate_right(&mut node.right);
+        }
+        rotate_left(link);
+    }
+}
+
+// returns the node of the key and the old value
+fn insert<K: Ord, V>(link: &mut Link<K, V>, key: K, value: V) -> (NonNull<Node<K, V>>, Option<V>) {
+    let node = match link {
+        Some(node) => node,
+        None => {
+            let mut node = Node::new(key, value);
+            let ptr = NonNull::from(&mut *node);
+            *link = Some(node);
+            return (ptr, None);
+        }
+    };
+    let ret = match key.cmp(&node.key) {
+        Ordering::Less => insert(&mut node.left, key, value),
+        Ordering::Greater => insert(&mut node.right, key, value),
+        Ordering::Equal => {
+            let old = mem::replace(&mut node.value, value);
+            return (NonNull::from(&mut **node), Some(old));
+        }
+    };
+    rebalance(link);
+    ret
+}
+
+// cond: link is not None
+fn pop_min<K, V>(link: &mut Link<K, V>) -> Box<Node<K, V>> {
+    let node = link.as_mut().unwrap();
+    if node.left.is_some() {
+        let min = pop_min(&mut node.left);
+        rebalance(link);
+        min
+    } else {
+        let mut node = link.take().unwrap();
+        *link = node.right.take();
+        node
+    }
+}
+
+// cond: link is not None
+fn pop_max<K, V>(link: &mut Link<K, V>) -> Box<Node<K, V>> {
+    let node = link.as_mut().unwrap();
+    if node.right.is_some() {
+        let max = pop_max(&mut node.right);
+        rebalance(link);
+        max
+    } else {
+        let mut node = link.take().unwrap();
+        *link = node.left.take();
+        node
+    }
+}
+
+// removes the node for which cmp returns Equal, cmp orders the wanted key against a node
+fn remove_by<K, V, F>(link: &mut Link<K, V>, mut cmp: F) -> Option<Box<Node<K, V>>>
+where
+    F: FnMut(&Node<K, V>) -> Ordering,
+{
+    let node = link.as_mut()?;
+    let removed = match cmp(node) {
+        Ordering::Less => remove_by(&mut node.left, cmp),
+        Ordering::Greater => remove_by(&mut node.right, cmp),
+        Ordering::Equal => {
+            let mut node = link.take().unwrap();
+            *link = match (node.left.take(), node.right.take()) {
+                (left, None) => left,
+                (None, right) => right,
+                (left, mut right) => {
+                    let mut succ = pop_min(&mut right);
+                    succ.left = left;
+                    succ.right = right;
+                    Some(succ)
+                }
+            };
+            Some(node)
+        }
+    };
+    if removed.is_some() {
+        rebalance(link);
+    }
+    removed
+}
+
+// AVL tree, the heights of the two subtrees of every node differ by at most one,
+// so the height is at most 1.44 log2(n + 2).
+pub struct AvlMap<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+impl<K, V> AvlMap<K, V> {
+    pub fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    // the number of nodes on the longest path from the root, 0 if the map is empty
+    pub fn height(&self) -> usize {
+        height(&self.root) as usize
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.root.as_ref()?;
+        let node = pop_min(&mut self.root);
+        self.len -= 1;
+        Some((node.key, node.value))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.root.as_ref()?;
+        let node = pop_max(&mut self.root);
+        self.len -= 1;
+        Some((node.key, node.value))
+    }
+
+    // in key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            iter: RawIter::new(self.root.as_deref()),
+            len: self.len,
+        }
+    }
+
+    // every node before its subtrees
+    pub fn preorder(&self) -> PreOrder<'_, K, V> {
+        PreOrder {
+            stack: self.root.as_deref().into_iter().collect(),
+        }
+    }
+
+    // every node after its subtrees
+    pub fn postorder(&self) -> PostOrder<'_, K, V> {
+        let mut iter = PostOrder { stack: Vec::new() };
+        iter.descend(self.root.as_deref());
+        iter
+    }
+}
+
+impl<K: Ord, V> AvlMap<K, V> {
+    fn find<Q>(&self, key: &Q) -> Option<&Node<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut link = self.root.as_deref();
+        while let Some(node) = link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return Some(node),
+            };
+        }
+        None
+    }
+
+    fn find_mut<Q>(&mut self, key: &Q) -> Option<&mut Node<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut link = self.root.as_deref_mut();
+        while let Some(node) = link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Less => node.left.as_deref_mut(),
+                Ordering::Greater => node.right.as_deref_mut(),
+                Ordering::Equal => return Some(node),
+            };
+        }
+        None
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (_, old) = insert(&mut self.root, key, value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        match self.find_mut(&key).map(NonNull::from) {
+            Some(node) => Entry::Occupied(OccupiedEntry { map: self, node }),
+            None => Entry::Vacant(VacantEntry { map: self, key }),
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|node| &node.value)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|node| (&node.key, &node.value))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_mut(key).map(|node| &mut node.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let node = remove_by(&mut self.root, |node| key.cmp(node.key.borrow()))?;
+        self.len -= 1;
+        Some((node.key, node.value))
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let iter = RawIter::range(
+            self.root.as_deref(),
+            |node| after_start(node.key.borrow(), range.start_bound()),
+            |node| before_end(node.key.borrow(), range.end_bound()),
+        );
+        Range { iter }
+    }
+
+    // panics if the keys are out of order, a height is stale or a balance factor is off
+    pub fn check_invariants(&self)
+    where
+        K: Debug,
+    {
+        fn check<K: Ord + Debug, V>(
+            link: &Link<K, V>,
+            lower: Option<&K>,
+            upper: Option<&K>,
+        ) -> (u8, usize) {
+            let node = match link {
+                Some(node) => node,
+                None => return (0, 0),
+            };
+            if let Some(lower) = lower {
+                assert!(lower < &node.key, "{:?} is out of order", node.key);
+            }
+            if let Some(upper) = upper {
+                assert!(&node.key < upper, "{:?} is out of order", node.key);
+            }
+            let (left, left_len) = check(&node.left, lower, Some(&node.key));
+            let (right, right_len) = check(&node.right, Some(&node.key), upper);
+            assert_eq!(
+                node.height,
+                cmp::max(left, right) + 1,
+                "stale height at {:?}",
+                node.key
+            );
+            assert!(
+                (left as i32 - right as i32).abs() <= 1,
+                "unbalanced at {:?}",
+                node.key
+            );
+            (node.height, left_len + right_len + 1)
+        }
+
+        let (_, len) = check(&self.root, None, None);
+        assert_eq!(len, self.len, "wrong length");
+    }
+}
+
+impl<K, V> Default for AvlMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for AvlMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for AvlMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+// ------------------------------------
+// begin: Entry
+
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    map: &'a mut AvlMap<K, V>,
+    node: NonNull<Node<K, V>>,
+}
+
+pub struct VacantEntry<'a, K, V> {
+    map: &'a mut AvlMap<K, V>,
+    key: K,
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        unsafe { &self.node.as_ref().key }
+    }
+
+    pub fn get(&self) -> &V {
+        unsafe { &self.node.as_ref().value }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { &mut self.node.as_mut().value }
+    }
+
+    pub fn into_mut(mut self) -> &'a mut V {
+        unsafe { &mut self.node.as_mut().value }
+    }
+
+    // returns the old value
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        // the node is recognized by its address, so its key is never read while it is removed
+        let target = self.node.as_ptr() as *const Node<K, V>;
+        let key: *const K = self.key();
+        let node = remove_by(&mut self.map.root, |node| {
+            if ptr::eq(node, target) {
+                Ordering::Equal
+            } else {
+                unsafe { (*key).cmp(&node.key) }
+            }
+        })
+        .unwrap();
+        self.map.len -= 1;
+        (node.key, node.value)
+    }
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let (node, _) = insert(&mut self.map.root, self.key, value);
+        self.map.len += 1;
+        unsafe { &mut (*node.as_ptr()).value }
+    }
+}
+
+// end: Entry
+// ------------------------------------
+
+// ------------------------------------
+// begin: IntoIter
+
+pub struct IntoIter<K, V>(AvlMap<K, V>);
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_first()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<K, V> IntoIterator for AvlMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_last()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+// end: IntoIter
+// ------------------------------------
+
+// ------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, K, V> {
+    iter: RawIter<'a, Node<K, V>>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.iter.next_front()?;
+        self.len -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a AvlMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.iter.next_back()?;
+        self.len -= 1;
+        Some((&node.key, &node.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+// end: Iter
+// ------------------------------------
+
+// ------------------------------------
+// begin: Range
+
+pub struct Range<'a, K, V> {
+    iter: RawIter<'a, Node<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next_front().map(|node| (&node.key, &node.value))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Range<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|node| (&node.key, &node.value))
+    }
+}
+
+impl<K, V> FusedIterator for Range<'_, K, V> {}
+
+// end: Range
+// ------------------------------------
+
+// ------------------------------------
+// begin: PreOrder
+
+pub struct PreOrder<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iterator for PreOrder<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.right.as_deref());
+        self.stack.extend(node.left.as_deref());
+        Some((&node.key, &node.value))
+    }
+}
+
+impl<K, V> FusedIterator for PreOrder<'_, K, V> {}
+
+// end: PreOrder
+// ------------------------------------
+
+// ------------------------------------
+// begin: PostOrder
+
+// the stack holds the path to the next node
+pub struct PostOrder<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> PostOrder<'a, K, V> {
+    // pushes the path to the first node of the subtree in post-order
+    fn descend(&mut self, mut link: Option<&'a Node<K, V>>) {
+        while let Some(node) = link {
+            self.stack.push(node);
+            link = node.left.as_deref().or(node.right.as_deref());
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for PostOrder<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        // the right sibling comes next if the node is a left child
+        if let Some(&parent) = self.stack.last() {
+            if parent
+                .left
+                .as_deref()
+                .is_some_and(|left| ptr::eq(left, node))
+            {
+                self.descend(parent.right.as_deref());
+            }
+        }
+        Some((&node.key, &node.value))
+    }
+}
+
+impl<K, V> FusedIterator for PostOrder<'_, K, V> {}
+
+// end: PostOrder
+// ------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::{AvlMap, Entry};
+    use crate::rng::Rng;
+
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+
+    #[test]
+    fn test_avl_map() {
+        let mut map = AvlMap::new();
+        assert_eq!(map.height(), 0);
+        assert_eq!(map.first(), None);
+        // ascending inserts rotate into a perfect tree
+        for k in 1..=7 {
+            assert_eq!(map.insert(k, k * 10), None);
+        }
+        map.check_invariants();
+        assert_eq!(map.height(), 3);
+        assert_eq!(map.insert(4, 0), Some(40));
+        assert_eq!(map.get(&4), Some(&0));
+        assert_eq!(map.get(&8), None);
+        *map.get_mut(&5).unwrap() += 1;
+
+        let keys = |iter: &mut dyn Iterator<Item = (&i32, &i32)>| {
+            iter.map(|(k, _)| *k).collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&mut map.iter()), [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(keys(&mut map.iter().rev()), [7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(keys(&mut map.preorder()), [4, 2, 1, 3, 6, 5, 7]);
+        assert_eq!(keys(&mut map.postorder()), [1, 3, 2, 5, 7, 6, 4]);
+        assert_eq!(keys(&mut map.range(3..6)), [3, 4, 5]);
+        assert_eq!(keys(&mut map.range(..=2).rev()), [2, 1]);
+        assert_eq!(keys(&mut map.range(8..)), []);
+        assert_eq!(keys(&mut map.range(5..5)), []);
+
+        let mut iter = map.iter();
+        assert_eq!(iter.next(), Some((&1, &10)));
+        assert_eq!(iter.next_back(), Some((&7, &70)));
+        assert_eq!(iter.len(), 5);
+        assert_eq!(keys(&mut iter), [2, 3, 4, 5, 6]);
+
+        *map.entry(9).or_insert(0) += 90;
+        *map.entry(9).or_default() += 1;
+        map.entry(1).and_modify(|v| *v = 11).or_insert(0);
+        assert_eq!(map.get(&1), Some(&11));
+        assert_eq!(map.get(&9), Some(&91));
+        match map.entry(4) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove_entry(), (4, 0)),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        match map.entry(4) {
+            Entry::Occupied(_) => unreachable!(),
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), 4),
+        }
+        map.check_invariants();
+
+        assert_eq!(map.remove(&5), Some(51));
+        assert_eq!(map.remove(&5), None);
+        assert_eq!(map.pop_first(), Some((1, 11)));
+        assert_eq!(map.pop_last(), Some((9, 91)));
+        map.check_invariants();
+        assert_eq!(
+            map.into_iter().collect::<Vec<_>>(),
+            [(2, 20), (3, 30), (6, 60), (7, 70)]
+        );
+    }
+
+    #[test]
+    fn test_rotations() {
+        let preorder = |map: &AvlMap<i32, ()>| map.preorder().map(|(k, _)| *k).collect::<Vec<_>>();
+        // the left-left, right-right, right-left and left-right cases of an insertion
+        for order in &[[1, 2, 3], [3, 2, 1], [1, 3, 2], [3, 1, 2]] {
+            let map: AvlMap<_, _> = order.iter().map(|&k| (k, ())).collect();
+            assert_eq!(preorder(&map), [2, 1, 3]);
+        }
+
+        // a removal which leaves a right subtree of balance 0 rotates once
+        let mut map: AvlMap<_, _> = (1..=7).map(|k| (k, ())).collect();
+        for k in &[1, 3, 2] {
+            map.remove(k);
+        }
+        map.check_invariants();
+        assert_eq!(preorder(&map), [6, 4, 5, 7]);
+
+        // and one leaning the other way rotates twice
+        let mut map: AvlMap<_, _> = [2, 1, 4, 3].iter().map(|&k| (k, ())).collect();
+        map.remove(&1);
+        map.check_invariants();
+        assert_eq!(preorder(&map), [3, 2, 4]);
+    }
+
+    #[test]
+    fn test_random_operations() {
+        let mut rng = Rng::new(46);
+        let mut map = AvlMap::new();
+        let mut expected = BTreeMap::new();
+
+        for i in 0..5000 {
+            let key = (rng.next_u64() % 1000) as u32;
+            if rng.next_u64() % 3 == 1 {
+                assert_eq!(map.remove(&key), expected.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, i), expected.insert(key, i));
+            }
+            if i % 100 == 0 {
+                map.check_invariants();
+                // the AVL height bound holds after every operation
+                let bound = 1.4405 * ((map.len() + 2) as f64).log2() - 0.3277;
+                assert!(map.height() as f64 <= bound);
+            }
+        }
+
+        map.check_invariants();
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected.iter()));
+        assert!(map.iter().rev().eq(expected.iter().rev()));
+        // the root comes first in pre-order and last in post-order
+        assert_eq!(map.preorder().next(), map.postorder().last());
+        let mut preorder: Vec<_> = map.preorder().collect();
+        let mut postorder: Vec<_> = map.postorder().collect();
+        preorder.sort_unstable();
+        postorder.sort_unstable();
+        assert!(preorder.into_iter().eq(expected.iter()));
+        assert!(postorder.into_iter().eq(expected.iter()));
+
+        // ranges bounded by keys of the map, which start and end at inner nodes
+        let keys: Vec<u32> = expected.keys().copied().collect();
+        for _ in 0..100 {
+            let lo = keys[rng.next_u64() as usize % keys.len()];
+            let hi = keys[rng.next_u64() as usize % keys.len()];
+            let bounds = (Bound::Excluded(lo), Bound::Included(hi));
+            if lo < hi {
+                assert!(map.range(bounds).eq(expected.range(bounds)));
+                assert!(map.range(lo..hi).rev().eq(expected.range(lo..hi).rev()));
+            } else {
+                assert_eq!(map.range(bounds).count(), 0);
+            }
+        }
+    }
+}
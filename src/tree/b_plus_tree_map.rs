@@ -0,0 +1,1237 @@
+use super::inline_list::InlineList;
+
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::iter::FromIterator;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::ptr::NonNull;
+
+type LeafLink<K, V, const B: usize> = Option<NonNull<Leaf<K, V, B>>>;
+
+enum Node<K, V, const B: usize> {
+    Internal(Internal<K, V, B>),
+    Leaf(Leaf<K, V, B>),
+}
+
+// child i holds the keys from keys[i - 1] up to keys[i]
+struct Internal<K, V, const B: usize> {
+    keys: InlineList<K, B>,
+    children: InlineList<Box<Node<K, V, B>>, B>,
+}
+
+struct Leaf<K, V, const B: usize> {
+    entries: InlineList<(K, V), B>,
+    prev: LeafLink<K, V, B>,
+    next: LeafLink<K, V, B>,
+}
+
+impl<K, V, const B: usize> Node<K, V, B> {
+    fn leaf() -> Box<Self> {
+        Box::new(Node::Leaf(Leaf {
+            entries: InlineList::new(),
+            prev: None,
+            next: None,
+        }))
+    }
+
+    fn internal() -> Box<Self> {
+        Box::new(Node::Internal(Internal {
+            keys: InlineList::new(),
+            children: InlineList::new(),
+        }))
+    }
+
+    // the number of keys
+    fn len(&self) -> usize {
+        match self {
+            Node::Internal(node) => node.keys.len(),
+            Node::Leaf(leaf) => leaf.entries.len(),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.len() == 2 * B - 1
+    }
+
+    // the leftmost leaf, or the rightmost one
+    fn edge(&self, last: bool) -> &Leaf<K, V, B> {
+        match self {
+            Node::Internal(node) => {
+                let child = if last {
+                    node.children.last()
+                } else {
+                    node.children.first()
+                };
+                child.unwrap().edge(last)
+            }
+            Node::Leaf(leaf) => leaf,
+        }
+    }
+}
+
+impl<K, V, const B: usize> Leaf<K, V, B> {
+    fn search<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.entries.binary_search_by(|(k, _)| k.borrow().cmp(key))
+    }
+}
+
+impl<K, V, const B: usize> Internal<K, V, B> {
+    // the child whose range holds the key
+    fn child_index<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.keys.partition_point(|k| k.borrow() <= key)
+    }
+
+    // cond: self is not full and child i is full
+    fn split_child(&mut self, i: usize)
+    where
+        K: Clone,
+    {
+        let (separator, right) = match &mut *self.children[i] {
+            Node::Leaf(leaf) => {
+                // the first key of the right half is copied up
+                let entries = leaf.entries.split_off(B);
+                let separator = entries[0].0.clone();
+                let mut right = Box::new(Node::Leaf(Leaf {
+                    entries,
+                    prev: Some(NonNull::from(&mut *leaf)),
+                    next: leaf.next,
+                }));
+                let link = match &mut *right {
+                    Node::Leaf(right) => NonNull::from(right),
+                    Node::Internal(_) => unreachable!(),
+                };
+                if let Some(mut next) = leaf.next {
+                    unsafe { next.as_mut().prev = Some(link) };
+                }
+                leaf.next = Some(link);
+                (separator, right)
+            }
+            Node::Internal(node) => {
+                // the median key moves up
+                let keys = node.keys.split_off(B);
+                let children = node.children.split_off(B);
+                let separator = node.keys.pop().unwrap();
+                let right = Box::new(Node::Internal(Internal { keys, children }));
+                (separator, right)
+            }
+        };
+        self.keys.insert(i, separator);
+        self.children.insert(i + 1, right);
+    }
+
+    // moves the last key of child i into child i + 1
+    fn rotate_right(&mut self, i: usize)
+    where
+        K: Clone,
+    {
+        let (left, right) = pair(&mut self.children, i);
+        match (&mut **left, &mut **right) {
+            (Node::Leaf(left), Node::Leaf(right)) => {
+                let entry = left.entries.pop().unwrap();
+                self.keys[i] = entry.0.clone();
+                right.entries.insert(0, entry);
+            }
+            (Node::Internal(left), Node::Internal(right)) => {
+                let key = left.keys.pop().unwrap();
+                right.keys.insert(0, mem::replace(&mut self.keys[i], key));
+                right.children.insert(0, left.children.pop().unwrap());
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // moves the first key of child i + 1 into child i
+    fn rotate_left(&mut self, i: usize)
+    where
+        K: Clone,
+    {
+        let (left, right) = pair(&mut self.children, i);
+        match (&mut **left, &mut **right) {
+            (Node::Leaf(left), Node::Leaf(right)) => {
+                left.entries.push(right.entries.remove(0));
+                self.keys[i] = right.entries[0].0.clone();
+            }
+            (Node::Internal(left), Node::Internal(right)) => {
+                let key = right.keys.remove(0);
+                left.keys.push(mem::replace(&mut self.keys[i], key));
+                left.children.push(right.children.remove(0));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // cond: children i and i + 1 have B - 1 keys
+    fn merge(&mut self, i: usize) {
+        let right = self.children.remove(i + 1);
+        let separator = self.keys.remove(i);
+        match (&mut *self.children[i], *right) {
+            (Node::Leaf(left), Node::Leaf(mut right)) => {
+                left.entries.append(&mut right.entries);
+                left.next = right.next;
+                if let Some(mut next) = right.next {
+                    unsafe { next.as_mut().prev = Some(NonNull::from(left)) };
+                }
+            }
+            (Node::Internal(left), Node::Internal(mut right)) => {
+                left.keys.push(separator);
+                left.keys.append(&mut right.keys);
+                left.children.append(&mut right.children);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // makes child i hold at least B keys, so that one can be removed from its subtree,
+    // and returns the index of the child which now covers its keys
+    fn fill(&mut self, i: usize) -> usize
+    where
+        K: Clone,
+    {
+        let count = |node: &Self, i: usize| node.children.get(i).map_or(0, |c| c.len());
+        if count(self, i) >= B {
+            i
+        } else if i > 0 && count(self, i - 1) >= B {
+            self.rotate_right(i - 1);
+            i
+        } else if count(self, i + 1) >= B {
+            self.rotate_left(i);
+            i
+        } else if i + 1 < self.children.len() {
+            self.merge(i);
+            i
+        } else {
+            self.merge(i - 1);
+            i - 1
+        }
+    }
+}
+
+fn pair<T>(list: &mut [T], i: usize) -> (&mut T, &mut T) {
+    let (left, right) = list.split_at_mut(i + 1);
+    (&mut left[i], &mut right[0])
+}
+
+// returns the value of the key and the old value
+// cond: node is not full
+fn insert<K: Ord + Clone, V, const B: usize>(
+    node: &mut Node<K, V, B>,
+    key: K,
+    value: V,
+) -> (&mut V, Option<V>) {
+    match node {
+        Node::Leaf(leaf) => match leaf.search(&key) {
+            Ok(i) => {
+                let old = mem::replace(&mut leaf.entries[i].1, value);
+                (&mut leaf.entries[i].1, Some(old))
+            }
+            Err(i) => {
+                leaf.entries.insert(i, (key, value));
+                (&mut leaf.entries[i].1, None)
+            }
+        },
+        Node::Internal(node) => {
+            let mut i = node.child_index(&key);
+            if node.children[i].is_full() {
+                node.split_child(i);
+                if key >= node.keys[i] {
+                    i += 1;
+                }
+            }
+            insert(&mut node.children[i], key, value)
+        }
+    }
+}
+
+// cond: node has at least B keys or is the root
+fn pop_edge<K: Clone, V, const B: usize>(node: &mut Node<K, V, B>, last: bool) -> Option<(K, V)> {
+    match node {
+        Node::Leaf(leaf) if last => leaf.entries.pop(),
+        Node::Leaf(leaf) => (!leaf.entries.is_empty()).then(|| leaf.entries.remove(0)),
+        Node::Internal(node) => {
+            let i = node.fill(if last { node.children.len() - 1 } else { 0 });
+            pop_edge(&mut node.children[i], last)
+        }
+    }
+}
+
+// cond: node has at least B keys or is the root
+fn remove<K, V, Q, const B: usize>(node: &mut Node<K, V, B>, key: &Q) -> Option<(K, V)>
+where
+    K: Borrow<Q> + Clone,
+    Q: Ord + ?Sized,
+{
+    // the separators stay as they are, they still divide the keys
+    match node {
+        Node::Leaf(leaf) => leaf.search(key).ok().map(|i| leaf.entries.remove(i)),
+        Node::Internal(node) => {
+            let i = node.fill(node.child_index(key));
+            remove(&mut node.children[i], key)
+        }
+    }
+}
+
+// splits len items into parts of as even sizes as possible
+fn part_len(len: usize, parts: usize, i: usize) -> usize {
+    len / parts + (i < len % parts) as usize
+}
+
+// B+ tree of minimum degree B. The entries are in the leaves, which form a doubly linked
+// list for sequential scans, and the internal nodes hold copies of the keys which separate
+// their children, hence K: Clone. Every node but the root holds B - 1 to 2B - 1 keys
+// inline in the node. Nodes are split and refilled on the way down like in BTreeMap.
+pub struct BPlusTreeMap<K, V, const B: usize = 6> {
+    root: Option<Box<Node<K, V, B>>>,
+    len: usize,
+}
+
+unsafe impl<K: Send, V: Send, const B: usize> Send for BPlusTreeMap<K, V, B> {}
+unsafe impl<K: Sync, V: Sync, const B: usize> Sync for BPlusTreeMap<K, V, B> {}
+
+impl<K, V, const B: usize> BPlusTreeMap<K, V, B> {
+    // panics if B is less than 2
+    pub fn new() -> Self {
+        assert!(B >= 2, "b-tree needs B of at least 2");
+        Self { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    // the number of levels, 0 if the map is empty
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut link = self.root.as_deref();
+        while let Some(node) = link {
+            height += 1;
+            link = match node {
+                Node::Internal(node) => node.children.first().map(|child| &**child),
+                Node::Leaf(_) => None,
+            };
+        }
+        height
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let leaf = self.root.as_ref()?.edge(false);
+        leaf.entries.first().map(|(k, v)| (k, v))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let leaf = self.root.as_ref()?.edge(true);
+        leaf.entries.last().map(|(k, v)| (k, v))
+    }
+
+    // drops the root once it has a single child or no entries
+    fn shrink(&mut self) {
+        let root = match &mut self.root {
+            Some(root) => root,
+            None => return,
+        };
+        match &mut **root {
+            Node::Internal(node) if node.keys.is_empty() => self.root = node.children.pop(),
+            Node::Leaf(leaf) if leaf.entries.is_empty() => self.root = None,
+            _ => {}
+        }
+    }
+
+    // in key order, walking the leaves
+    pub fn iter(&self) -> Iter<'_, K, V, B> {
+        let iter = self.root.as_ref().map(|root| {
+            let last = root.edge(true);
+            RawIter::new((root.edge(false), 0), (last, last.entries.len()))
+        });
+        Iter {
+            iter,
+            len: self.len,
+        }
+    }
+}
+
+impl<K: Ord + Clone, V, const B: usize> BPlusTreeMap<K, V, B> {
+    // equal keys keep the last value,
+    // panics if the keys are not in increasing order or B is less than 2
+    pub fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        let mut items: Vec<(K, V)> = Vec::new();
+        for (key, value) in iter {
+            match items.last_mut() {
+                Some(last) if last.0 == key => *last = (key, value),
+                Some(last) => {
+                    assert!(last.0 < key, "keys are not sorted");
+                    items.push((key, value));
+                }
+                None => items.push((key, value)),
+            }
+        }
+        map.len = items.len();
+        if map.len == 0 {
+            return map;
+        }
+
+        // the leaves, as full as possible, with their least keys
+        let leaves = map.len.div_ceil(2 * B - 1);
+        let mut items = items.into_iter();
+        let mut level: Vec<(K, Box<Node<K, V, B>>)> = Vec::with_capacity(leaves);
+        let mut prev: LeafLink<K, V, B> = None;
+        for i in 0..leaves {
+            let mut node = Node::leaf();
+            let leaf = match &mut *node {
+                Node::Leaf(leaf) => leaf,
+                Node::Internal(_) => unreachable!(),
+            };
+            for item in items.by_ref().take(part_len(map.len, leaves, i)) {
+                leaf.entries.push(item);
+            }
+            leaf.prev = prev;
+            let link = NonNull::from(&mut *leaf);
+            if let Some(mut prev) = prev {
+                unsafe { prev.as_mut().next = Some(link) };
+            }
+            prev = Some(link);
+            level.push((leaf.entries[0].0.clone(), node));
+        }
+
+        // the levels above, each node taking the least key of its first child
+        while level.len() > 1 {
+            let count = level.len();
+            let parents = count.div_ceil(2 * B);
+            let mut children = level.into_iter();
+            level = Vec::with_capacity(parents);
+            for i in 0..parents {
+                let mut node = Node::internal();
+                let internal = match &mut *node {
+                    Node::Internal(internal) => internal,
+                    Node::Leaf(_) => unreachable!(),
+                };
+                let mut group = children.by_ref().take(part_len(count, parents, i));
+                let (least, first) = group.next().unwrap();
+                internal.children.push(first);
+                for (key, child) in group {
+                    internal.keys.push(key);
+                    internal.children.push(child);
+                }
+                level.push((least, node));
+            }
+        }
+        map.root = level.pop().map(|(_, root)| root);
+        map
+    }
+
+    // descends to the leaf of the child chosen by pick in every internal node
+    fn descend<F: Fn(&[K]) -> usize>(&self, pick: F) -> Option<&Leaf<K, V, B>> {
+        let mut node = self.root.as_deref()?;
+        loop {
+            match node {
+                Node::Internal(internal) => node = &internal.children[pick(&internal.keys)],
+                Node::Leaf(leaf) => return Some(leaf),
+            }
+        }
+    }
+
+    fn find_leaf<Q>(&self, key: &Q) -> Option<&Leaf<K, V, B>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.descend(|keys| keys.partition_point(|k| k.borrow() <= key))
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<&(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let leaf = self.find_leaf(key)?;
+        leaf.search(key).ok().map(|i| &leaf.entries[i])
+    }
+
+    fn find_mut<Q>(&mut self, key: &Q) -> Option<&mut (K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut node = self.root.as_deref_mut()?;
+        loop {
+            match node {
+                Node::Internal(internal) => {
+                    let i = internal.child_index(key);
+                    node = &mut internal.children[i];
+                }
+                Node::Leaf(leaf) => {
+                    let i = leaf.search(key).ok()?;
+                    return Some(&mut leaf.entries[i]);
+                }
+            }
+        }
+    }
+
+    fn insert_entry(&mut self, key: K, value: V) -> (&mut V, Option<V>) {
+        let root = self.root.get_or_insert_with(Node::leaf);
+        if root.is_full() {
+            let old = mem::replace(root, Node::internal());
+            if let Node::Internal(root) = &mut **root {
+                root.children.push(old);
+                root.split_child(0);
+            }
+        }
+        let (value, old) = insert(root, key, value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        (value, old)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_entry(key, value).1
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, B> {
+        match self.find_mut(&key).map(NonNull::from) {
+            Some(entry) => Entry::Occupied(OccupiedEntry {
+                map: self,
+                key,
+                entry,
+            }),
+            None => Entry::Vacant(VacantEntry { map: self, key }),
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|(_, v)| v)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|(k, v)| (k, v))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_mut(key).map(|(_, v)| v)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let entry = remove(self.root.as_mut()?, key);
+        self.shrink();
+        if entry.is_some() {
+            self.len -= 1;
+        }
+        entry
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let entry = pop_edge(self.root.as_mut()?, false);
+        self.shrink();
+        if entry.is_some() {
+            self.len -= 1;
+        }
+        entry
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let entry = pop_edge(self.root.as_mut()?, true);
+        self.shrink();
+        if entry.is_some() {
+            self.len -= 1;
+        }
+        entry
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V, B>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let root = match self.root.as_deref() {
+            Some(root) => root,
+            None => return Range { iter: None },
+        };
+        let front = match range.start_bound() {
+            Bound::Included(start) => {
+                let leaf = self.find_leaf(start).unwrap();
+                (
+                    leaf,
+                    leaf.entries.partition_point(|(k, _)| k.borrow() < start),
+                )
+            }
+            Bound::Excluded(start) => {
+                let leaf = self.find_leaf(start).unwrap();
+                (
+                    leaf,
+                    leaf.entries.partition_point(|(k, _)| k.borrow() <= start),
+                )
+            }
+            Bound::Unbounded => (root.edge(false), 0),
+        };
+        let back = match range.end_bound() {
+            Bound::Included(end) => {
+                let leaf = self.find_leaf(end).unwrap();
+                (
+                    leaf,
+                    leaf.entries.partition_point(|(k, _)| k.borrow() <= end),
+                )
+            }
+            Bound::Excluded(end) => {
+                // the leaf of the keys just below the end
+                let leaf = self
+                    .descend(|keys| keys.partition_point(|k| k.borrow() < end))
+                    .unwrap();
+                (
+                    leaf,
+                    leaf.entries.partition_point(|(k, _)| k.borrow() < end),
+                )
+            }
+            Bound::Unbounded => {
+                let leaf = root.edge(true);
+                (leaf, leaf.entries.len())
+            }
+        };
+
+        let iter = RawIter::new(front, back);
+        let empty = match (iter.peek_front(), iter.peek_back()) {
+            (Some(first), Some(last)) => first.0 > last.0,
+            _ => true,
+        };
+        Range {
+            iter: if empty { None } else { Some(iter) },
+        }
+    }
+
+    // panics if the keys are out of order, a node is under- or overfull,
+    // the leaves are not all at the same depth or the leaf list is broken
+    pub fn check_invariants(&self)
+    where
+        K: Debug,
+    {
+        // returns the height and pushes the leaves
+        fn check<'a, K: Ord + Debug, V, const B: usize>(
+            node: &'a Node<K, V, B>,
+            lower: Option<&K>,
+            upper: Option<&K>,
+            root: bool,
+            leaves: &mut Vec<&'a Leaf<K, V, B>>,
+        ) -> usize {
+            let keys: Vec<&K> = match node {
+                Node::Internal(node) => node.keys.iter().collect(),
+                Node::Leaf(leaf) => leaf.entries.iter().map(|(k, _)| k).collect(),
+            };
+            assert!(
+                keys.len() < 2 * B && (root || keys.len() >= B - 1),
+                "{:?} has a wrong number of keys",
+                keys
+            );
+            assert!(
+                keys.windows(2).all(|w| w[0] < w[1]),
+                "{:?} is out of order",
+                keys
+            );
+            if let (Some(lower), Some(first)) = (lower, keys.first()) {
+                assert!(lower <= *first, "{:?} is out of order", keys);
+            }
+            if let (Some(upper), Some(last)) = (upper, keys.last()) {
+                assert!(*last < upper, "{:?} is out of order", keys);
+            }
+            let node = match node {
+                Node::Internal(node) => node,
+                Node::Leaf(leaf) => {
+                    leaves.push(leaf);
+                    return 1;
+                }
+            };
+            assert!(!keys.is_empty(), "empty internal node");
+            assert_eq!(
+                node.children.len(),
+                keys.len() + 1,
+                "wrong number of children"
+            );
+            let mut height = None;
+            for (i, child) in node.children.iter().enumerate() {
+                let lower = if i == 0 { lower } else { Some(keys[i - 1]) };
+                let upper = keys.get(i).copied().or(upper);
+                let child_height = check(child, lower, upper, false, leaves);
+                assert!(
+                    *height.get_or_insert(child_height) == child_height,
+                    "uneven leaves under {:?}",
+                    keys
+                );
+            }
+            height.unwrap() + 1
+        }
+
+        let mut leaves = Vec::new();
+        if let Some(root) = &self.root {
+            check(root, None, None, true, &mut leaves);
+        }
+        for (i, leaf) in leaves.iter().enumerate() {
+            let prev = i.checked_sub(1).map(|i| NonNull::from(leaves[i]));
+            let next = leaves.get(i + 1).map(|&leaf| NonNull::from(leaf));
+            assert!(leaf.prev == prev && leaf.next == next, "broken leaf links");
+        }
+        let len: usize = leaves.iter().map(|leaf| leaf.entries.len()).sum();
+        assert_eq!(len, self.len, "wrong length");
+    }
+}
+
+impl<K, V, const B: usize> Default for BPlusTreeMap<K, V, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// sorts the entries and loads them in bulk
+impl<K: Ord + Clone, V, const B: usize> FromIterator<(K, V)> for BPlusTreeMap<K, V, B> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut items: Vec<(K, V)> = iter.into_iter().collect();
+        // stable, so the last of equal keys stays last
+        items.sort_by(|a, b| a.0.cmp(&b.0));
+        Self::from_sorted_iter(items)
+    }
+}
+
+impl<K: Ord + Clone, V, const B: usize> Extend<(K, V)> for BPlusTreeMap<K, V, B> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+// ------------------------------------
+// begin: Entry
+
+pub enum Entry<'a, K, V, const B: usize> {
+    Occupied(OccupiedEntry<'a, K, V, B>),
+    Vacant(VacantEntry<'a, K, V, B>),
+}
+
+// keeps the given key to find the entry again for a removal, since the entries move around
+pub struct OccupiedEntry<'a, K, V, const B: usize> {
+    map: &'a mut BPlusTreeMap<K, V, B>,
+    key: K,
+    entry: NonNull<(K, V)>,
+}
+
+pub struct VacantEntry<'a, K, V, const B: usize> {
+    map: &'a mut BPlusTreeMap<K, V, B>,
+    key: K,
+}
+
+impl<'a, K: Ord + Clone, V, const B: usize> Entry<'a, K, V, B> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K: Ord + Clone, V, const B: usize> OccupiedEntry<'a, K, V, B> {
+    pub fn key(&self) -> &K {
+        unsafe { &self.entry.as_ref().0 }
+    }
+
+    pub fn get(&self) -> &V {
+        unsafe { &self.entry.as_ref().1 }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { &mut self.entry.as_mut().1 }
+    }
+
+    pub fn into_mut(mut self) -> &'a mut V {
+        unsafe { &mut self.entry.as_mut().1 }
+    }
+
+    // returns the old value
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        self.map.remove_entry(&self.key).unwrap()
+    }
+}
+
+impl<'a, K: Ord + Clone, V, const B: usize> VacantEntry<'a, K, V, B> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.map.insert_entry(self.key, value).0
+    }
+}
+
+// end: Entry
+// ------------------------------------
+
+// ------------------------------------
+// begin: IntoIter
+
+pub struct IntoIter<K, V, const B: usize>(BPlusTreeMap<K, V, B>);
+
+impl<K: Ord + Clone, V, const B: usize> Iterator for IntoIter<K, V, B> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_first()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<K: Ord + Clone, V, const B: usize> IntoIterator for BPlusTreeMap<K, V, B> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<K: Ord + Clone, V, const B: usize> DoubleEndedIterator for IntoIter<K, V, B> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_last()
+    }
+}
+
+impl<K: Ord + Clone, V, const B: usize> ExactSizeIterator for IntoIter<K, V, B> {}
+
+impl<K: Ord + Clone, V, const B: usize> FusedIterator for IntoIter<K, V, B> {}
+
+// end: IntoIter
+// ------------------------------------
+
+// walks the leaf list from both ends, front is the next entry and back is past
+// the last one, the traversals are done once they meet
+struct RawIter<'a, K, V, const B: usize> {
+    front: (NonNull<Leaf<K, V, B>>, usize),
+    back: (NonNull<Leaf<K, V, B>>, usize),
+    _marker: PhantomData<&'a Leaf<K, V, B>>,
+}
+
+impl<'a, K, V, const B: usize> RawIter<'a, K, V, B> {
+    fn new(front: (&'a Leaf<K, V, B>, usize), back: (&'a Leaf<K, V, B>, usize)) -> Self {
+        let mut iter = Self {
+            front: (NonNull::from(front.0), front.1),
+            back: (NonNull::from(back.0), back.1),
+            _marker: PhantomData,
+        };
+        // the ends step over to the neighbouring leaves, short of meeting
+        unsafe {
+            let (leaf, i) = iter.front;
+            if i == leaf.as_ref().entries.len() && leaf != iter.back.0 {
+                if let Some(next) = leaf.as_ref().next {
+                    iter.front = (next, 0);
+                }
+            }
+            let (leaf, i) = iter.back;
+            if i == 0 && leaf != iter.front.0 {
+                if let Some(prev) = leaf.as_ref().prev {
+                    iter.back = (prev, prev.as_ref().entries.len());
+                }
+            }
+        }
+        iter
+    }
+
+    fn is_done(&self) -> bool {
+        self.front.0 == self.back.0 && self.front.1 >= self.back.1
+    }
+
+    fn peek_front(&self) -> Option<&'a (K, V)> {
+        let (leaf, i) = self.front;
+        unsafe { leaf.as_ref() }.entries.get(i)
+    }
+
+    fn peek_back(&self) -> Option<&'a (K, V)> {
+        let (leaf, i) = self.back;
+        unsafe { leaf.as_ref() }.entries.get(i.checked_sub(1)?)
+    }
+
+    fn next_front(&mut self) -> Option<&'a (K, V)> {
+        if self.is_done() {
+            return None;
+        }
+        let entry = self.peek_front()?;
+        let (leaf, i) = &mut self.front;
+        *i += 1;
+        let current = unsafe { leaf.as_ref() };
+        if *i == current.entries.len() && *leaf != self.back.0 {
+            *leaf = current.next.unwrap();
+            *i = 0;
+        }
+        Some(entry)
+    }
+
+    fn next_back(&mut self) -> Option<&'a (K, V)> {
+        if self.is_done() {
+            return None;
+        }
+        let entry = self.peek_back()?;
+        let (leaf, i) = &mut self.back;
+        *i -= 1;
+        let current = unsafe { leaf.as_ref() };
+        if *i == 0 && *leaf != self.front.0 {
+            *leaf = current.prev.unwrap();
+            *i = unsafe { leaf.as_ref() }.entries.len();
+        }
+        Some(entry)
+    }
+}
+
+// ------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, K, V, const B: usize> {
+    iter: Option<RawIter<'a, K, V, B>>,
+    len: usize,
+}
+
+impl<'a, K, V, const B: usize> Iterator for Iter<'a, K, V, B> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (k, v) = self.iter.as_mut()?.next_front()?;
+        self.len -= 1;
+        Some((k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V, const B: usize> IntoIterator for &'a BPlusTreeMap<K, V, B> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V, const B: usize> DoubleEndedIterator for Iter<'_, K, V, B> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (k, v) = self.iter.as_mut()?.next_back()?;
+        self.len -= 1;
+        Some((k, v))
+    }
+}
+
+impl<K, V, const B: usize> ExactSizeIterator for Iter<'_, K, V, B> {}
+
+impl<K, V, const B: usize> FusedIterator for Iter<'_, K, V, B> {}
+
+// end: Iter
+// ------------------------------------
+
+// ------------------------------------
+// begin: Range
+
+pub struct Range<'a, K, V, const B: usize> {
+    iter: Option<RawIter<'a, K, V, B>>,
+}
+
+impl<'a, K, V, const B: usize> Iterator for Range<'a, K, V, B> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (k, v) = self.iter.as_mut()?.next_front()?;
+        Some((k, v))
+    }
+}
+
+impl<K, V, const B: usize> DoubleEndedIterator for Range<'_, K, V, B> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (k, v) = self.iter.as_mut()?.next_back()?;
+        Some((k, v))
+    }
+}
+
+impl<K, V, const B: usize> FusedIterator for Range<'_, K, V, B> {}
+
+// end: Range
+// ------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::{BPlusTreeMap, Entry, Node};
+    use crate::rng::Rng;
+
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+
+    #[test]
+    fn test_b_plus_tree_map() {
+        let mut map = BPlusTreeMap::<i32, i32, 2>::new();
+        assert_eq!(map.height(), 0);
+        assert_eq!(map.first(), None);
+        assert_eq!(map.iter().next(), None);
+        for k in 1..=9 {
+            assert_eq!(map.insert(k, k * 10), None);
+            map.check_invariants();
+        }
+        assert_eq!(map.height(), 3);
+        assert_eq!(map.insert(4, 0), Some(40));
+        assert_eq!(map.get(&4), Some(&0));
+        assert_eq!(map.get(&10), None);
+        *map.get_mut(&5).unwrap() += 1;
+        assert_eq!(map.first(), Some((&1, &10)));
+        assert_eq!(map.last(), Some((&9, &90)));
+
+        let keys = |iter: &mut dyn Iterator<Item = (&i32, &i32)>| {
+            iter.map(|(k, _)| *k).collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&mut map.iter()), [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(keys(&mut map.iter().rev()), [9, 8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(keys(&mut map.range(3..6)), [3, 4, 5]);
+        assert_eq!(
+            keys(&mut map.range((Bound::Excluded(2), Bound::Unbounded)).rev()),
+            [9, 8, 7, 6, 5, 4, 3]
+        );
+        assert_eq!(keys(&mut map.range(10..)), []);
+        assert_eq!(keys(&mut map.range(5..5)), []);
+
+        let mut iter = map.iter();
+        assert_eq!(iter.next(), Some((&1, &10)));
+        assert_eq!(iter.next_back(), Some((&9, &90)));
+        assert_eq!(iter.len(), 7);
+        assert_eq!(keys(&mut iter), [2, 3, 4, 5, 6, 7, 8]);
+
+        *map.entry(11).or_insert(0) += 110;
+        map.entry(1).and_modify(|v| *v = 11).or_insert(0);
+        assert_eq!(map.get(&1), Some(&11));
+        assert_eq!(map.get(&11), Some(&110));
+        match map.entry(4) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove_entry(), (4, 0)),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        match map.entry(4) {
+            Entry::Occupied(_) => unreachable!(),
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), 4),
+        }
+        map.check_invariants();
+
+        assert_eq!(map.remove(&5), Some(51));
+        assert_eq!(map.remove(&5), None);
+        assert_eq!(map.pop_first(), Some((1, 11)));
+        assert_eq!(map.pop_last(), Some((11, 110)));
+        map.check_invariants();
+        assert_eq!(
+            map.into_iter().collect::<Vec<_>>(),
+            [(2, 20), (3, 30), (6, 60), (7, 70), (8, 80), (9, 90)]
+        );
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let mut map = BPlusTreeMap::<(), ()>::new();
+        assert_eq!(map.insert((), ()), None);
+        assert_eq!(map.insert((), ()), Some(()));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&()), Some(&()));
+        assert_eq!(map.remove(&()), Some(()));
+        assert!(map.is_empty());
+
+        let mut map = BPlusTreeMap::<u32, (), 2>::new();
+        map.extend((0..100).map(|k| (k, ())));
+        map.check_invariants();
+        assert!(map.iter().map(|(k, _)| *k).eq(0..100));
+    }
+
+    #[test]
+    fn test_bulk_load() {
+        for len in 0..300 {
+            let map = BPlusTreeMap::<_, _, 3>::from_sorted_iter((0..len).map(|k| (k, k)));
+            map.check_invariants();
+            assert!(map.iter().map(|(k, _)| *k).eq(0..len));
+            assert!(map.iter().rev().map(|(k, _)| *k).eq((0..len).rev()));
+        }
+        let map: BPlusTreeMap<_, _, 2> = vec![(3, 'a'), (1, 'b'), (3, 'c'), (2, 'd')]
+            .into_iter()
+            .collect();
+        map.check_invariants();
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [(&1, &'b'), (&2, &'d'), (&3, &'c')]
+        );
+    }
+
+    // the copies of the keys in the internal nodes
+    fn separators<const B: usize>(node: &Node<u32, u32, B>, keys: &mut Vec<u32>) {
+        if let Node::Internal(node) = node {
+            keys.extend(node.keys.iter().copied());
+            node.children
+                .iter()
+                .for_each(|child| separators(child, keys));
+        }
+    }
+
+    #[test]
+    fn test_separators() {
+        let mut map = BPlusTreeMap::<u32, u32, 2>::from_sorted_iter((0..60).map(|k| (2 * k, k)));
+        let mut keys = Vec::new();
+        separators(map.root.as_ref().unwrap(), &mut keys);
+        keys.sort_unstable();
+        assert!(!keys.is_empty());
+
+        // a separator is the first key of a leaf, so ranges around it start or end at a leaf edge
+        let expected: BTreeMap<u32, u32> = (0..60).map(|k| (2 * k, k)).collect();
+        let check = |map: &BPlusTreeMap<u32, u32, 2>, expected: &BTreeMap<u32, u32>| {
+            for &k in &keys {
+                let bounds = [
+                    (Bound::Included(k), Bound::Unbounded),
+                    (Bound::Excluded(k), Bound::Unbounded),
+                    (Bound::Unbounded, Bound::Included(k)),
+                    (Bound::Unbounded, Bound::Excluded(k)),
+                    (Bound::Included(k - 1), Bound::Excluded(k + 1)),
+                ];
+                for &range in &bounds {
+                    assert!(map.range(range).eq(expected.range(range)));
+                    assert!(map.range(range).rev().eq(expected.range(range).rev()));
+                }
+            }
+        };
+        check(&map, &expected);
+
+        // and stay right once those keys are removed from the leaves
+        let mut expected = expected;
+        for &k in &keys {
+            assert_eq!(map.remove(&k), expected.remove(&k));
+            map.check_invariants();
+            assert_eq!(map.get(&k), None);
+        }
+        check(&map, &expected);
+        for &k in &keys {
+            assert_eq!(map.insert(k, 0), None);
+        }
+        map.check_invariants();
+        assert_eq!(map.len(), 60);
+    }
+
+    #[test]
+    fn test_random_operations() {
+        fn run<const B: usize>(seed: u64) {
+            let mut rng = Rng::new(seed);
+            let mut map = BPlusTreeMap::<u32, u64, B>::new();
+            let mut expected = BTreeMap::new();
+
+            for i in 0..5000 {
+                let key = (rng.next_u64() % 1000) as u32;
+                match rng.next_u64() % 4 {
+                    0 => assert_eq!(map.remove(&key), expected.remove(&key)),
+                    1 => assert_eq!(map.pop_last(), expected.pop_last()),
+                    _ => assert_eq!(map.insert(key, i), expected.insert(key, i)),
+                }
+                if i % 100 == 0 {
+                    map.check_invariants();
+                }
+            }
+
+            map.check_invariants();
+            assert_eq!(map.len(), expected.len());
+            assert!(map.iter().eq(expected.iter()));
+            assert!(map.iter().rev().eq(expected.iter().rev()));
+            // both ends of a range walk the leaf list until they meet
+            for _ in 0..100 {
+                let lo = (rng.next_u64() % 1000) as u32;
+                let hi = lo + (rng.next_u64() % 200) as u32;
+                let (mut a, mut b) = (map.range(lo..hi), expected.range(lo..hi));
+                while let Some(x) = a.next() {
+                    assert_eq!(Some(x), b.next());
+                    assert_eq!(a.next_back(), b.next_back());
+                }
+                assert_eq!(b.next(), None);
+            }
+        }
+
+        run::<2>(51);
+        run::<3>(52);
+        run::<6>(53);
+    }
+}
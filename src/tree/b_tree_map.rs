@@ -0,0 +1,1106 @@
+use super::inline_list::InlineList;
+
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::iter::FromIterator;
+use std::iter::FusedIterator;
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::ptr::{self, NonNull};
+
+// at most 2B - 1 entries and 2B children, inline so that a node is a single allocation
+struct Node<K, V, const B: usize> {
+    entries: InlineList<(K, V), B>,
+    // empty in the leaves
+    children: InlineList<Box<Node<K, V, B>>, B>,
+}
+
+impl<K, V, const B: usize> Node<K, V, B> {
+    fn new() -> Box<Self> {
+        Box::new(Self {
+            entries: InlineList::new(),
+            children: InlineList::new(),
+        })
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.entries.len() == 2 * B - 1
+    }
+
+    // the index of the key, or of the child whose subtree would hold it
+    fn search<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.entries.binary_search_by(|(k, _)| k.borrow().cmp(key))
+    }
+
+    // cond: self is not full and child i is full
+    // the median of the child moves up between the halves
+    fn split_child(&mut self, i: usize) {
+        let child = &mut self.children[i];
+        let entries = child.entries.split_off(B);
+        let children = if child.is_leaf() {
+            InlineList::new()
+        } else {
+            child.children.split_off(B)
+        };
+        let median = child.entries.pop().unwrap();
+        self.entries.insert(i, median);
+        self.children
+            .insert(i + 1, Box::new(Self { entries, children }));
+    }
+
+    // moves the last entry of child i up and the separator down into child i + 1
+    fn rotate_right(&mut self, i: usize) {
+        let (left, right) = pair(&mut self.children, i);
+        let entry = left.entries.pop().unwrap();
+        right
+            .entries
+            .insert(0, mem::replace(&mut self.entries[i], entry));
+        if let Some(child) = left.children.pop() {
+            right.children.insert(0, child);
+        }
+    }
+
+    // moves the first entry of child i + 1 up and the separator down into child i
+    fn rotate_left(&mut self, i: usize) {
+        let (left, right) = pair(&mut self.children, i);
+        let entry = right.entries.remove(0);
+        left.entries.push(mem::replace(&mut self.entries[i], entry));
+        if !right.is_leaf() {
+            left.children.push(right.children.remove(0));
+        }
+    }
+
+    // cond: children i and i + 1 have B - 1 entries
+    fn merge(&mut self, i: usize) {
+        let mut right = self.children.remove(i + 1);
+        let separator = self.entries.remove(i);
+        let left = &mut self.children[i];
+        left.entries.push(separator);
+        left.entries.append(&mut right.entries);
+        left.children.append(&mut right.children);
+    }
+
+    // makes child i hold at least B entries, so that one can be removed from its subtree,
+    // and returns the index of the child which now covers its keys
+    fn fill(&mut self, i: usize) -> usize {
+        let count = |node: &Self, i: usize| node.children.get(i).map_or(0, |c| c.entries.len());
+        if count(self, i) >= B {
+            i
+        } else if i > 0 && count(self, i - 1) >= B {
+            self.rotate_right(i - 1);
+            i
+        } else if count(self, i + 1) >= B {
+            self.rotate_left(i);
+            i
+        } else if i + 1 < self.children.len() {
+            self.merge(i);
+            i
+        } else {
+            self.merge(i - 1);
+            i - 1
+        }
+    }
+}
+
+fn pair<T>(list: &mut [T], i: usize) -> (&mut T, &mut T) {
+    let (left, right) = list.split_at_mut(i + 1);
+    (&mut left[i], &mut right[0])
+}
+
+// returns the value of the key and the old value
+// cond: node is not full
+fn insert<K: Ord, V, const B: usize>(
+    node: &mut Node<K, V, B>,
+    key: K,
+    value: V,
+) -> (&mut V, Option<V>) {
+    let mut i = match node.search(&key) {
+        Ok(i) => {
+            let old = mem::replace(&mut node.entries[i].1, value);
+            return (&mut node.entries[i].1, Some(old));
+        }
+        Err(i) => i,
+    };
+    if node.is_leaf() {
+        node.entries.insert(i, (key, value));
+        return (&mut node.entries[i].1, None);
+    }
+    if node.children[i].is_full() {
+        node.split_child(i);
+        if key > node.entries[i].0 {
+            i += 1;
+        } else if key == node.entries[i].0 {
+            let old = mem::replace(&mut node.entries[i].1, value);
+            return (&mut node.entries[i].1, Some(old));
+        }
+    }
+    insert(&mut node.children[i], key, value)
+}
+
+// cond: node has at least B entries or is the root
+fn pop_min<K, V, const B: usize>(node: &mut Node<K, V, B>) -> (K, V) {
+    if node.is_leaf() {
+        return node.entries.remove(0);
+    }
+    let i = node.fill(0);
+    pop_min(&mut node.children[i])
+}
+
+// cond: node has at least B entries or is the root
+fn pop_max<K, V, const B: usize>(node: &mut Node<K, V, B>) -> (K, V) {
+    if node.is_leaf() {
+        return node.entries.pop().unwrap();
+    }
+    let i = node.fill(node.children.len() - 1);
+    pop_max(&mut node.children[i])
+}
+
+// cond: node has at least B entries or is the root
+fn remove<K, V, Q, const B: usize>(node: &mut Node<K, V, B>, key: &Q) -> Option<(K, V)>
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    match node.search(key) {
+        Ok(i) if node.is_leaf() => Some(node.entries.remove(i)),
+        Ok(i) => {
+            // the predecessor or the successor takes the place of the entry
+            if node.children[i].entries.len() >= B {
+                let entry = pop_max(&mut node.children[i]);
+                Some(mem::replace(&mut node.entries[i], entry))
+            } else if node.children[i + 1].entries.len() >= B {
+                let entry = pop_min(&mut node.children[i + 1]);
+                Some(mem::replace(&mut node.entries[i], entry))
+            } else {
+                node.merge(i);
+                remove(&mut node.children[i], key)
+            }
+        }
+        Err(_) if node.is_leaf() => None,
+        Err(i) => {
+            let i = node.fill(i);
+            remove(&mut node.children[i], key)
+        }
+    }
+}
+
+// the most entries a tree of the given height holds
+fn capacity(b: usize, height: u32) -> usize {
+    (2 * b).saturating_pow(height) - 1
+}
+
+// builds a tree of the given height from the next len items
+// cond: len is between B^height - 1 (1 for the root) and (2B)^height - 1
+fn build<K, V, I, const B: usize>(
+    items: &mut I,
+    len: usize,
+    height: u32,
+    root: bool,
+) -> Box<Node<K, V, B>>
+where
+    I: Iterator<Item = (K, V)>,
+{
+    let mut node = Node::new();
+    if height == 1 {
+        for item in items.take(len) {
+            node.entries.push(item);
+        }
+        return node;
+    }
+    // as few children as fit, but at least B below the root
+    let mut children = (len + 1).div_ceil(capacity(B, height - 1) + 1);
+    if !root {
+        children = children.max(B);
+    }
+    let rest = len - (children - 1);
+    for i in 0..children {
+        let len = rest / children + (i < rest % children) as usize;
+        node.children.push(build(items, len, height - 1, false));
+        if i + 1 < children {
+            node.entries.push(items.next().unwrap());
+        }
+    }
+    node
+}
+
+fn push_front<'a, K, V, const B: usize>(
+    stack: &mut Vec<(&'a Node<K, V, B>, usize)>,
+    mut node: &'a Node<K, V, B>,
+) {
+    loop {
+        stack.push((node, 0));
+        match node.children.first() {
+            Some(child) => node = child,
+            None => break,
+        }
+    }
+}
+
+fn push_back<'a, K, V, const B: usize>(
+    stack: &mut Vec<(&'a Node<K, V, B>, usize)>,
+    mut node: &'a Node<K, V, B>,
+) {
+    loop {
+        stack.push((node, node.entries.len()));
+        match node.children.last() {
+            Some(child) => node = child,
+            None => break,
+        }
+    }
+}
+
+// B-tree of minimum degree B, every node but the root holds B - 1 to 2B - 1 entries
+// inline in the node, so a lookup reads about log_B(n) nodes of adjacent keys.
+// Full nodes are split on the way down an insertion and nodes with B - 1 entries
+// are refilled from a sibling or merged with it on the way down a removal,
+// so neither needs a second pass up the tree.
+pub struct BTreeMap<K, V, const B: usize = 6> {
+    root: Option<Box<Node<K, V, B>>>,
+    len: usize,
+}
+
+impl<K, V, const B: usize> BTreeMap<K, V, B> {
+    // panics if B is less than 2
+    pub fn new() -> Self {
+        assert!(B >= 2, "b-tree needs B of at least 2");
+        Self { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    // the number of levels, 0 if the map is empty
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut link = self.root.as_deref();
+        while let Some(node) = link {
+            height += 1;
+            link = node.children.first().map(|child| &**child);
+        }
+        height
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(child) = node.children.first() {
+            node = child;
+        }
+        node.entries.first().map(|(k, v)| (k, v))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(child) = node.children.last() {
+            node = child;
+        }
+        node.entries.last().map(|(k, v)| (k, v))
+    }
+
+    // drops the root once it runs out of entries
+    fn shrink(&mut self) {
+        if let Some(root) = &mut self.root {
+            if root.entries.is_empty() {
+                self.root = root.children.pop();
+            }
+        }
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let entry = pop_min(self.root.as_mut()?);
+        self.shrink();
+        self.len -= 1;
+        Some(entry)
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let entry = pop_max(self.root.as_mut()?);
+        self.shrink();
+        self.len -= 1;
+        Some(entry)
+    }
+
+    // in key order
+    pub fn iter(&self) -> Iter<'_, K, V, B> {
+        let mut iter = RawIter {
+            front: Vec::new(),
+            back: Vec::new(),
+        };
+        if let Some(root) = self.root.as_deref() {
+            push_front(&mut iter.front, root);
+            push_back(&mut iter.back, root);
+            iter.settle();
+        }
+        Iter {
+            iter,
+            len: self.len,
+        }
+    }
+}
+
+impl<K: Ord, V, const B: usize> BTreeMap<K, V, B> {
+    // equal keys keep the last value,
+    // panics if the keys are not in increasing order or B is less than 2
+    pub fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        let mut items: Vec<(K, V)> = Vec::new();
+        for (key, value) in iter {
+            match items.last_mut() {
+                Some(last) if last.0 == key => *last = (key, value),
+                Some(last) => {
+                    assert!(last.0 < key, "keys are not sorted");
+                    items.push((key, value));
+                }
+                None => items.push((key, value)),
+            }
+        }
+        map.len = items.len();
+        if map.len > 0 {
+            let mut height = 1;
+            while capacity(B, height) < map.len {
+                height += 1;
+            }
+            map.root = Some(build(&mut items.into_iter(), map.len, height, true));
+        }
+        map
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<&(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut node = self.root.as_deref()?;
+        loop {
+            match node.search(key) {
+                Ok(i) => return Some(&node.entries[i]),
+                Err(i) => node = node.children.get(i)?,
+            }
+        }
+    }
+
+    fn find_mut<Q>(&mut self, key: &Q) -> Option<&mut (K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut node = self.root.as_deref_mut()?;
+        loop {
+            match node.search(key) {
+                Ok(i) => return Some(&mut node.entries[i]),
+                Err(i) => node = node.children.get_mut(i)?,
+            }
+        }
+    }
+
+    fn insert_entry(&mut self, key: K, value: V) -> (&mut V, Option<V>) {
+        let root = self.root.get_or_insert_with(|| Node::new());
+        if root.is_full() {
+            let old = mem::replace(root, Node::new());
+            root.children.push(old);
+            root.split_child(0);
+        }
+        let (value, old) = insert(root, key, value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        (value, old)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_entry(key, value).1
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, B> {
+        match self.find_mut(&key).map(NonNull::from) {
+            Some(entry) => Entry::Occupied(OccupiedEntry {
+                map: self,
+                key,
+                entry,
+            }),
+            None => Entry::Vacant(VacantEntry { map: self, key }),
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|(_, v)| v)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|(k, v)| (k, v))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_mut(key).map(|(_, v)| v)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let entry = remove(self.root.as_mut()?, key);
+        self.shrink();
+        if entry.is_some() {
+            self.len -= 1;
+        }
+        entry
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V, B>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        // the paths to the first entry at or after the start and past the last entry
+        // at or before the end
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        let mut link = self.root.as_deref();
+        while let Some(node) = link {
+            let i = node
+                .entries
+                .partition_point(|(k, _)| match range.start_bound() {
+                    Bound::Included(start) => k.borrow() < start,
+                    Bound::Excluded(start) => k.borrow() <= start,
+                    Bound::Unbounded => false,
+                });
+            front.push((node, i));
+            link = node.children.get(i).map(|child| &**child);
+        }
+        let mut link = self.root.as_deref();
+        while let Some(node) = link {
+            let i = node
+                .entries
+                .partition_point(|(k, _)| match range.end_bound() {
+                    Bound::Included(end) => k.borrow() <= end,
+                    Bound::Excluded(end) => k.borrow() < end,
+                    Bound::Unbounded => true,
+                });
+            back.push((node, i));
+            link = node.children.get(i).map(|child| &**child);
+        }
+
+        let mut iter = RawIter { front, back };
+        iter.settle();
+        let empty = match (iter.peek_front(), iter.peek_back()) {
+            (Some(first), Some(last)) => first.0 > last.0,
+            _ => true,
+        };
+        if empty {
+            iter.front.clear();
+            iter.back.clear();
+        }
+        Range { iter }
+    }
+
+    // panics if the keys are out of order, a node is under- or overfull
+    // or the leaves are not all at the same depth
+    pub fn check_invariants(&self)
+    where
+        K: Debug,
+    {
+        // returns the height and the number of entries
+        fn check<K: Ord + Debug, V, const B: usize>(
+            node: &Node<K, V, B>,
+            lower: Option<&K>,
+            upper: Option<&K>,
+            root: bool,
+        ) -> (usize, usize) {
+            let keys: Vec<&K> = node.entries.iter().map(|(k, _)| k).collect();
+            assert!(
+                keys.len() < 2 * B && (root || keys.len() >= B - 1) && !keys.is_empty(),
+                "{:?} has a wrong number of entries",
+                keys
+            );
+            let bounds = lower.into_iter().chain(keys.iter().copied()).chain(upper);
+            assert!(
+                bounds.clone().zip(bounds.skip(1)).all(|(a, b)| a < b),
+                "{:?} is out of order",
+                keys
+            );
+            if node.is_leaf() {
+                return (1, keys.len());
+            }
+            assert_eq!(
+                node.children.len(),
+                keys.len() + 1,
+                "wrong number of children"
+            );
+            let mut height = None;
+            let mut len = keys.len();
+            for (i, child) in node.children.iter().enumerate() {
+                let lower = if i == 0 { lower } else { Some(keys[i - 1]) };
+                let upper = keys.get(i).copied().or(upper);
+                let (child_height, child_len) = check(child, lower, upper, false);
+                assert!(
+                    *height.get_or_insert(child_height) == child_height,
+                    "uneven leaves under {:?}",
+                    keys
+                );
+                len += child_len;
+            }
+            (height.unwrap() + 1, len)
+        }
+
+        let len = self
+            .root
+            .as_ref()
+            .map_or(0, |root| check(root, None, None, true).1);
+        assert_eq!(len, self.len, "wrong length");
+    }
+}
+
+impl<K, V, const B: usize> Default for BTreeMap<K, V, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// sorts the entries and loads them in bulk
+impl<K: Ord, V, const B: usize> FromIterator<(K, V)> for BTreeMap<K, V, B> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut items: Vec<(K, V)> = iter.into_iter().collect();
+        // stable, so the last of equal keys stays last
+        items.sort_by(|a, b| a.0.cmp(&b.0));
+        Self::from_sorted_iter(items)
+    }
+}
+
+impl<K: Ord, V, const B: usize> Extend<(K, V)> for BTreeMap<K, V, B> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+// ------------------------------------
+// begin: Entry
+
+pub enum Entry<'a, K, V, const B: usize> {
+    Occupied(OccupiedEntry<'a, K, V, B>),
+    Vacant(VacantEntry<'a, K, V, B>),
+}
+
+// keeps the given key to find the entry again for a removal, since the entries move around
+pub struct OccupiedEntry<'a, K, V, const B: usize> {
+    map: &'a mut BTreeMap<K, V, B>,
+    key: K,
+    entry: NonNull<(K, V)>,
+}
+
+pub struct VacantEntry<'a, K, V, const B: usize> {
+    map: &'a mut BTreeMap<K, V, B>,
+    key: K,
+}
+
+impl<'a, K: Ord, V, const B: usize> Entry<'a, K, V, B> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a, K: Ord, V, const B: usize> OccupiedEntry<'a, K, V, B> {
+    pub fn key(&self) -> &K {
+        unsafe { &self.entry.as_ref().0 }
+    }
+
+    pub fn get(&self) -> &V {
+        unsafe { &self.entry.as_ref().1 }
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { &mut self.entry.as_mut().1 }
+    }
+
+    pub fn into_mut(mut self) -> &'a mut V {
+        unsafe { &mut self.entry.as_mut().1 }
+    }
+
+    // returns the old value
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        self.map.remove_entry(&self.key).unwrap()
+    }
+}
+
+impl<'a, K: Ord, V, const B: usize> VacantEntry<'a, K, V, B> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        self.map.insert_entry(self.key, value).0
+    }
+}
+
+// end: Entry
+// ------------------------------------
+
+// ------------------------------------
+// begin: IntoIter
+
+pub struct IntoIter<K, V, const B: usize>(BTreeMap<K, V, B>);
+
+impl<K, V, const B: usize> Iterator for IntoIter<K, V, B> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_first()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<K, V, const B: usize> IntoIterator for BTreeMap<K, V, B> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<K, V, const B: usize> DoubleEndedIterator for IntoIter<K, V, B> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_last()
+    }
+}
+
+impl<K, V, const B: usize> ExactSizeIterator for IntoIter<K, V, B> {}
+
+impl<K, V, const B: usize> FusedIterator for IntoIter<K, V, B> {}
+
+// end: IntoIter
+// ------------------------------------
+
+// in-order traversal from both ends, the stacks hold the paths to the next entries,
+// (node, i) is before entry i of the node in the front stack and after entry i - 1
+// in the back stack
+struct RawIter<'a, K, V, const B: usize> {
+    front: Vec<(&'a Node<K, V, B>, usize)>,
+    back: Vec<(&'a Node<K, V, B>, usize)>,
+}
+
+impl<'a, K, V, const B: usize> RawIter<'a, K, V, B> {
+    // drops the finished nodes from the tops of the stacks
+    fn settle(&mut self) {
+        while let Some(&(node, i)) = self.front.last() {
+            if i < node.entries.len() {
+                break;
+            }
+            self.front.pop();
+        }
+        while let Some(&(_, i)) = self.back.last() {
+            if i > 0 {
+                break;
+            }
+            self.back.pop();
+        }
+    }
+
+    fn peek_front(&self) -> Option<&'a (K, V)> {
+        self.front.last().map(|&(node, i)| &node.entries[i])
+    }
+
+    fn peek_back(&self) -> Option<&'a (K, V)> {
+        self.back.last().map(|&(node, i)| &node.entries[i - 1])
+    }
+
+    // the traversals are done once they meet
+    fn meet(&mut self) -> bool {
+        let met = match (self.peek_front(), self.peek_back()) {
+            (Some(front), Some(back)) => ptr::eq(front, back),
+            _ => false,
+        };
+        if met {
+            self.front.clear();
+            self.back.clear();
+        }
+        met
+    }
+
+    fn next_front(&mut self) -> Option<&'a (K, V)> {
+        let entry = self.peek_front()?;
+        if !self.meet() {
+            let (node, i) = self.front.last_mut().unwrap();
+            *i += 1;
+            if let Some(child) = node.children.get(*i) {
+                push_front(&mut self.front, child);
+            }
+            self.settle();
+        }
+        Some(entry)
+    }
+
+    fn next_back(&mut self) -> Option<&'a (K, V)> {
+        let entry = self.peek_back()?;
+        if !self.meet() {
+            let (node, i) = self.back.last_mut().unwrap();
+            *i -= 1;
+            if let Some(child) = node.children.get(*i) {
+                push_back(&mut self.back, child);
+            }
+            self.settle();
+        }
+        Some(entry)
+    }
+}
+
+// ------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, K, V, const B: usize> {
+    iter: RawIter<'a, K, V, B>,
+    len: usize,
+}
+
+impl<'a, K, V, const B: usize> Iterator for Iter<'a, K, V, B> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (k, v) = self.iter.next_front()?;
+        self.len -= 1;
+        Some((k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V, const B: usize> IntoIterator for &'a BTreeMap<K, V, B> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, B>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V, const B: usize> DoubleEndedIterator for Iter<'_, K, V, B> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (k, v) = self.iter.next_back()?;
+        self.len -= 1;
+        Some((k, v))
+    }
+}
+
+impl<K, V, const B: usize> ExactSizeIterator for Iter<'_, K, V, B> {}
+
+impl<K, V, const B: usize> FusedIterator for Iter<'_, K, V, B> {}
+
+// end: Iter
+// ------------------------------------
+
+// ------------------------------------
+// begin: Range
+
+pub struct Range<'a, K, V, const B: usize> {
+    iter: RawIter<'a, K, V, B>,
+}
+
+impl<'a, K, V, const B: usize> Iterator for Range<'a, K, V, B> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next_front().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K, V, const B: usize> DoubleEndedIterator for Range<'_, K, V, B> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K, V, const B: usize> FusedIterator for Range<'_, K, V, B> {}
+
+// end: Range
+// ------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::{BTreeMap, Entry, Node};
+    use crate::rng::Rng;
+
+    use std::collections::BTreeMap as StdBTreeMap;
+    use std::ops::Bound;
+
+    #[test]
+    fn test_b_tree_map() {
+        let mut map = BTreeMap::<i32, i32, 2>::new();
+        assert_eq!(map.height(), 0);
+        assert_eq!(map.first(), None);
+        for k in 1..=9 {
+            assert_eq!(map.insert(k, k * 10), None);
+            map.check_invariants();
+        }
+        assert_eq!(map.height(), 3);
+        assert_eq!(map.insert(4, 0), Some(40));
+        assert_eq!(map.get(&4), Some(&0));
+        assert_eq!(map.get(&10), None);
+        *map.get_mut(&5).unwrap() += 1;
+        assert_eq!(map.first(), Some((&1, &10)));
+        assert_eq!(map.last(), Some((&9, &90)));
+
+        let keys = |iter: &mut dyn Iterator<Item = (&i32, &i32)>| {
+            iter.map(|(k, _)| *k).collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&mut map.iter()), [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(keys(&mut map.iter().rev()), [9, 8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(keys(&mut map.range(3..6)), [3, 4, 5]);
+        assert_eq!(
+            keys(&mut map.range((Bound::Excluded(2), Bound::Unbounded)).rev()),
+            [9, 8, 7, 6, 5, 4, 3]
+        );
+        assert_eq!(keys(&mut map.range(10..)), []);
+        assert_eq!(keys(&mut map.range(5..5)), []);
+
+        let mut iter = map.iter();
+        assert_eq!(iter.next(), Some((&1, &10)));
+        assert_eq!(iter.next_back(), Some((&9, &90)));
+        assert_eq!(iter.len(), 7);
+        assert_eq!(keys(&mut iter), [2, 3, 4, 5, 6, 7, 8]);
+
+        *map.entry(11).or_insert(0) += 110;
+        map.entry(1).and_modify(|v| *v = 11).or_insert(0);
+        assert_eq!(map.get(&1), Some(&11));
+        assert_eq!(map.get(&11), Some(&110));
+        match map.entry(4) {
+            Entry::Occupied(entry) => assert_eq!(entry.remove_entry(), (4, 0)),
+            Entry::Vacant(_) => unreachable!(),
+        }
+        match map.entry(4) {
+            Entry::Occupied(_) => unreachable!(),
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), 4),
+        }
+        map.check_invariants();
+
+        assert_eq!(map.remove(&5), Some(51));
+        assert_eq!(map.remove(&5), None);
+        assert_eq!(map.pop_first(), Some((1, 11)));
+        assert_eq!(map.pop_last(), Some((11, 110)));
+        map.check_invariants();
+        assert_eq!(
+            map.into_iter().collect::<Vec<_>>(),
+            [(2, 20), (3, 30), (6, 60), (7, 70), (8, 80), (9, 90)]
+        );
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let mut map = BTreeMap::<(), ()>::new();
+        assert_eq!(map.insert((), ()), None);
+        assert_eq!(map.insert((), ()), Some(()));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.get(&()), Some(&()));
+        assert_eq!(map.remove(&()), Some(()));
+        assert!(map.is_empty());
+
+        let mut map = BTreeMap::<u32, (), 2>::new();
+        map.extend((0..100).map(|k| (k, ())));
+        map.check_invariants();
+        assert!(map.iter().map(|(k, _)| *k).eq(0..100));
+    }
+
+    #[test]
+    fn test_bulk_load() {
+        for len in 0..300 {
+            let map = BTreeMap::<_, _, 3>::from_sorted_iter((0..len).map(|k| (k, k)));
+            map.check_invariants();
+            assert!(map.iter().map(|(k, _)| *k).eq(0..len));
+        }
+        let map: BTreeMap<_, _, 2> = vec![(3, 'a'), (1, 'b'), (3, 'c'), (2, 'd')]
+            .into_iter()
+            .collect();
+        map.check_invariants();
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            [(&1, &'b'), (&2, &'d'), (&3, &'c')]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "keys are not sorted")]
+    fn test_unsorted_bulk_load() {
+        BTreeMap::<_, _>::from_sorted_iter(vec![(2, ()), (1, ())]);
+    }
+
+    // the keys held by the internal nodes
+    fn inner_keys<const B: usize>(node: &Node<u32, u32, B>, keys: &mut Vec<u32>) {
+        if !node.is_leaf() {
+            keys.extend(node.entries.iter().map(|&(k, _)| k));
+            node.children
+                .iter()
+                .for_each(|child| inner_keys(child, keys));
+        }
+    }
+
+    #[test]
+    fn test_inner_removals() {
+        fn run<const B: usize>() {
+            let mut map = BTreeMap::<u32, u32, B>::from_sorted_iter((0..300).map(|k| (k, k)));
+            let mut keys = Vec::new();
+            inner_keys(map.root.as_ref().unwrap(), &mut keys);
+            assert!(!keys.is_empty());
+
+            // each removal from an inner node borrows a neighbour from a leaf
+            // and refills or merges the nodes on the way down
+            let height = map.height();
+            for &k in &keys {
+                assert_eq!(map.remove(&k), Some(k));
+                map.check_invariants();
+                assert!(map.height() <= height);
+            }
+            assert!(map
+                .iter()
+                .map(|(k, _)| *k)
+                .eq((0..300).filter(|k| !keys.contains(k))));
+
+            // the tree shrinks back to nothing from both ends
+            while let Some((k, _)) = map.pop_first() {
+                assert!(map.pop_last().is_none_or(|(last, _)| k < last));
+                map.check_invariants();
+            }
+            assert_eq!(map.height(), 0);
+        }
+
+        run::<2>();
+        run::<3>();
+    }
+
+    #[test]
+    fn test_random_operations() {
+        fn run<const B: usize>(seed: u64) {
+            let mut rng = Rng::new(seed);
+            let mut map = BTreeMap::<u32, u64, B>::new();
+            let mut expected = StdBTreeMap::new();
+
+            for i in 0..5000 {
+                let key = (rng.next_u64() % 1000) as u32;
+                match rng.next_u64() % 4 {
+                    0 => assert_eq!(map.remove(&key), expected.remove(&key)),
+                    1 => assert_eq!(
+                        map.entry(key).or_insert(i),
+                        expected.entry(key).or_insert(i)
+                    ),
+                    _ => assert_eq!(map.insert(key, i), expected.insert(key, i)),
+                }
+                if i % 100 == 0 {
+                    map.check_invariants();
+                }
+            }
+
+            map.check_invariants();
+            assert_eq!(map.len(), expected.len());
+            assert!(map.iter().eq(expected.iter()));
+            assert!(map.iter().rev().eq(expected.iter().rev()));
+            // a range ends inside a node or at one of its edges
+            let max = *expected.keys().last().unwrap();
+            for _ in 0..100 {
+                let lo = (rng.next_u64() % 1000) as u32;
+                let len = (rng.next_u64() % 64) as u32;
+                let range = lo..=lo.saturating_add(len).min(max);
+                if range.is_empty() {
+                    assert_eq!(map.range(range).count(), 0);
+                } else {
+                    assert!(map.range(range.clone()).eq(expected.range(range.clone())));
+                    assert!(map
+                        .range(range.clone())
+                        .rev()
+                        .eq(expected.range(range).rev()));
+                }
+            }
+        }
+
+        run::<2>(48);
+        run::<3>(49);
+        run::<6>(50);
+    }
+}
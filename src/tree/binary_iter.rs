@@ -0,0 +1,127 @@
+use std::ops::Bound;
+use std::ptr;
+
+// A node of a binary search tree as the in-order iterators of the maps see it.
+pub trait BinaryNode {
+    fn left(&self) -> Option<&Self>;
+    fn right(&self) -> Option<&Self>;
+}
+
+pub fn after_start<Q: Ord + ?Sized>(key: &Q, start: Bound<&Q>) -> bool {
+    match start {
+        Bound::Included(start) => key >= start,
+        Bound::Excluded(start) => key > start,
+        Bound::Unbounded => true,
+    }
+}
+
+pub fn before_end<Q: Ord + ?Sized>(key: &Q, end: Bound<&Q>) -> bool {
+    match end {
+        Bound::Included(end) => key <= end,
+        Bound::Excluded(end) => key < end,
+        Bound::Unbounded => true,
+    }
+}
+
+fn push_left<'a, N: BinaryNode>(stack: &mut Vec<&'a N>, mut link: Option<&'a N>) {
+    while let Some(node) = link {
+        stack.push(node);
+        link = node.left();
+    }
+}
+
+fn push_right<'a, N: BinaryNode>(stack: &mut Vec<&'a N>, mut link: Option<&'a N>) {
+    while let Some(node) = link {
+        stack.push(node);
+        link = node.right();
+    }
+}
+
+// in-order traversal from both ends, the stacks hold the nodes still to be visited
+// whose subtrees towards the middle are not entered yet
+pub struct RawIter<'a, N> {
+    front: Vec<&'a N>,
+    back: Vec<&'a N>,
+}
+
+impl<'a, N: BinaryNode> RawIter<'a, N> {
+    pub fn new(root: Option<&'a N>) -> Self {
+        let mut iter = Self {
+            front: Vec::new(),
+            back: Vec::new(),
+        };
+        push_left(&mut iter.front, root);
+        push_right(&mut iter.back, root);
+        iter
+    }
+
+    // the nodes which are after the start and before the end, as told by the predicates
+    pub fn range<F, G>(root: Option<&'a N>, after_start: F, before_end: G) -> Self
+    where
+        F: Fn(&N) -> bool,
+        G: Fn(&N) -> bool,
+    {
+        // the stacks hold the ancestors of the first and the last node of the range
+        // which are still to be visited
+        let mut front = Vec::new();
+        let mut link = root;
+        while let Some(node) = link {
+            if after_start(node) {
+                front.push(node);
+                link = node.left();
+            } else {
+                link = node.right();
+            }
+        }
+        let mut back = Vec::new();
+        let mut link = root;
+        while let Some(node) = link {
+            if before_end(node) {
+                back.push(node);
+                link = node.right();
+            } else {
+                link = node.left();
+            }
+        }
+
+        // the range is empty unless its first node is also before the end
+        match front.last() {
+            Some(&first) if before_end(first) => Self { front, back },
+            _ => Self {
+                front: Vec::new(),
+                back: Vec::new(),
+            },
+        }
+    }
+
+    // the traversals are done once they meet
+    fn meet(&mut self, node: &N) -> bool {
+        let met = match (self.front.last(), self.back.last()) {
+            (Some(&front), Some(&back)) => ptr::eq(front, back) && ptr::eq(front, node),
+            _ => false,
+        };
+        if met {
+            self.front.clear();
+            self.back.clear();
+        }
+        met
+    }
+
+    pub fn next_front(&mut self) -> Option<&'a N> {
+        let node = *self.front.last()?;
+        if !self.meet(node) {
+            self.front.pop();
+            push_left(&mut self.front, node.right());
+        }
+        Some(node)
+    }
+
+    pub fn next_back(&mut self) -> Option<&'a N> {
+        let node = *self.back.last()?;
+        if !self.meet(node) {
+            self.back.pop();
+            push_right(&mut self.back, node.left());
+        }
+        Some(node)
+    }
+}
@@ -0,0 +1,676 @@
+use super::augment::{Monoid, NoAugment};
+
+use crate::rng::Rng;
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::iter::FromIterator;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Bound, Index, Range, RangeBounds};
+
+type Link<T, S> = Option<Box<Node<T, S>>>;
+
+struct Node<T, S> {
+    elem: T,
+    priority: u64,
+    len: usize,
+    // the summaries of the subtree in order and in reverse order,
+    // both with the pending reversal of this node applied
+    summary: S,
+    rev_summary: S,
+    // the children are still to be swapped and reversed
+    reversed: bool,
+    left: Link<T, S>,
+    right: Link<T, S>,
+}
+
+impl<T, S> Node<T, S> {
+    // the children in order, given whether an ancestor reversal is pending
+    fn children(&self, parity: bool) -> (&Link<T, S>, &Link<T, S>) {
+        if parity ^ self.reversed {
+            (&self.right, &self.left)
+        } else {
+            (&self.left, &self.right)
+        }
+    }
+
+    fn toggle(&mut self) {
+        mem::swap(&mut self.summary, &mut self.rev_summary);
+        self.reversed = !self.reversed;
+    }
+
+    fn push_down(&mut self) {
+        if self.reversed {
+            mem::swap(&mut self.left, &mut self.right);
+            for child in self.left.iter_mut().chain(self.right.iter_mut()) {
+                child.toggle();
+            }
+            self.reversed = false;
+        }
+    }
+}
+
+fn len<T, S>(link: &Link<T, S>) -> usize {
+    link.as_ref().map_or(0, |node| node.len)
+}
+
+// cond: node has no pending reversal
+fn update<T, M: Monoid<T>>(node: &mut Node<T, M::Summary>) {
+    let elem = M::summarize(&node.elem);
+    let (mut summary, mut rev_summary) = (elem.clone(), elem);
+    if let Some(left) = &node.left {
+        summary = M::combine(&left.summary, &summary);
+        rev_summary = M::combine(&rev_summary, &left.rev_summary);
+    }
+    if let Some(right) = &node.right {
+        summary = M::combine(&summary, &right.summary);
+        rev_summary = M::combine(&right.rev_summary, &rev_summary);
+    }
+    node.len = len(&node.left) + len(&node.right) + 1;
+    node.summary = summary;
+    node.rev_summary = rev_summary;
+}
+
+// splits into the first `at` elements and the rest
+fn split<T, M: Monoid<T>>(
+    link: Link<T, M::Summary>,
+    at: usize,
+) -> (Link<T, M::Summary>, Link<T, M::Summary>) {
+    let mut node = match link {
+        Some(node) => node,
+        None => return (None, None),
+    };
+    node.push_down();
+    let left = len(&node.left);
+    if at <= left {
+        let (left, mid) = split::<T, M>(node.left.take(), at);
+        node.left = mid;
+        update::<T, M>(&mut node);
+        (left, Some(node))
+    } else {
+        let (mid, right) = split::<T, M>(node.right.take(), at - left - 1);
+        node.right = mid;
+        update::<T, M>(&mut node);
+        (Some(node), right)
+    }
+}
+
+fn merge<T, M: Monoid<T>>(
+    left: Link<T, M::Summary>,
+    right: Link<T, M::Summary>,
+) -> Link<T, M::Summary> {
+    match (left, right) {
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.push_down();
+                left.right = merge::<T, M>(left.right.take(), Some(right));
+                update::<T, M>(&mut left);
+                Some(left)
+            } else {
+                right.push_down();
+                right.left = merge::<T, M>(Some(left), right.left.take());
+                update::<T, M>(&mut right);
+                Some(right)
+            }
+        }
+        (left, right) => left.or(right),
+    }
+}
+
+// cond: index < node.len
+fn set<T, M: Monoid<T>>(node: &mut Node<T, M::Summary>, index: usize, elem: T) -> T {
+    node.push_down();
+    let left = len(&node.left);
+    let old = match index.cmp(&left) {
+        Ordering::Less => set::<T, M>(node.left.as_mut().unwrap(), index, elem),
+        Ordering::Greater => set::<T, M>(node.right.as_mut().unwrap(), index - left - 1, elem),
+        Ordering::Equal => mem::replace(&mut node.elem, elem),
+    };
+    update::<T, M>(node);
+    old
+}
+
+// the summary of the elements in start..end of the subtree
+fn fold<T, M: Monoid<T>>(
+    link: &Link<T, M::Summary>,
+    parity: bool,
+    start: usize,
+    end: usize,
+) -> M::Summary {
+    let node = match link {
+        Some(node) if start < end => node,
+        _ => return M::identity(),
+    };
+    if start == 0 && end == node.len {
+        return if parity {
+            node.rev_summary.clone()
+        } else {
+            node.summary.clone()
+        };
+    }
+    let (left, right) = node.children(parity);
+    let parity = parity ^ node.reversed;
+    let mid = len(left);
+    let mut summary = fold::<T, M>(left, parity, start, end.min(mid));
+    if start <= mid && mid < end {
+        summary = M::combine(&summary, &M::summarize(&node.elem));
+    }
+    if end > mid + 1 {
+        let right = fold::<T, M>(right, parity, start.max(mid + 1) - mid - 1, end - mid - 1);
+        summary = M::combine(&summary, &right);
+    }
+    summary
+}
+
+fn drain<T, S>(link: Link<T, S>, parity: bool, out: &mut Vec<T>) {
+    if let Some(node) = link {
+        let node = *node;
+        let parity = parity ^ node.reversed;
+        let (left, right) = if parity {
+            (node.right, node.left)
+        } else {
+            (node.left, node.right)
+        };
+        drain(left, parity, out);
+        out.push(node.elem);
+        drain(right, parity, out);
+    }
+}
+
+// panics if the range does not fit in 0..len
+fn bounds<R: RangeBounds<usize>>(range: R, len: usize) -> Range<usize> {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end + 1,
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    assert!(start <= end && end <= len, "range out of bounds");
+    start..end
+}
+
+// Sequence kept as a treap ordered by position instead of by key. Every node
+// stores the size of its subtree, so positions are found by descending on sizes,
+// and insertions, removals, splits and concatenations are O(log n) expected.
+// Reversing a range flips a lazy flag on the root of the split-out part, which is
+// pushed down the next time the node is entered. Subtrees also keep summaries
+// of a monoid M, in both directions so that a reversal only swaps them.
+pub struct ImplicitTreap<T, M: Monoid<T> = NoAugment> {
+    root: Link<T, M::Summary>,
+    rng: Rng,
+    _marker: PhantomData<M>,
+}
+
+impl<T> ImplicitTreap<T> {
+    pub fn new() -> Self {
+        Self::with_rng(Rng::from_entropy())
+    }
+}
+
+impl<T, M: Monoid<T>> ImplicitTreap<T, M> {
+    fn with_rng(rng: Rng) -> Self {
+        Self {
+            root: None,
+            rng,
+            _marker: PhantomData,
+        }
+    }
+
+    // for reproducible shapes
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(Rng::new(seed))
+    }
+
+    pub fn len(&self) -> usize {
+        len(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    pub fn get(&self, mut index: usize) -> Option<&T> {
+        let mut link = &self.root;
+        let mut parity = false;
+        while let Some(node) = link {
+            let (left, right) = node.children(parity);
+            parity ^= node.reversed;
+            let mid = len(left);
+            link = match index.cmp(&mid) {
+                Ordering::Less => left,
+                Ordering::Greater => {
+                    index -= mid + 1;
+                    right
+                }
+                Ordering::Equal => return Some(&node.elem),
+            };
+        }
+        None
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.get(self.len().checked_sub(1)?)
+    }
+
+    // replaces the element at index, returning the old one
+    pub fn set(&mut self, index: usize, elem: T) -> T {
+        match &mut self.root {
+            Some(root) if index < root.len => set::<T, M>(root, index, elem),
+            _ => panic!("index out of bounds"),
+        }
+    }
+
+    pub fn insert(&mut self, index: usize, elem: T) {
+        if index > self.len() {
+            panic!("index out of bounds")
+        }
+        let summary = M::summarize(&elem);
+        let node = Box::new(Node {
+            elem,
+            priority: self.rng.next_u64(),
+            len: 1,
+            summary: summary.clone(),
+            rev_summary: summary,
+            reversed: false,
+            left: None,
+            right: None,
+        });
+        let (left, right) = split::<T, M>(self.root.take(), index);
+        self.root = merge::<T, M>(merge::<T, M>(left, Some(node)), right);
+    }
+
+    pub fn remove(&mut self, index: usize) -> T {
+        if index >= self.len() {
+            panic!("index out of bounds")
+        }
+        let (left, right) = split::<T, M>(self.root.take(), index);
+        let (mid, right) = split::<T, M>(right, 1);
+        self.root = merge::<T, M>(left, right);
+        mid.unwrap().elem
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        self.insert(self.len(), elem)
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        self.insert(0, elem)
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let len = self.len().checked_sub(1)?;
+        Some(self.remove(len))
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.remove(0))
+    }
+
+    // moves the elements from at on into a new treap
+    pub fn split_off(&mut self, at: usize) -> Self {
+        if at > self.len() {
+            panic!("index out of bounds")
+        }
+        let (left, right) = split::<T, M>(self.root.take(), at);
+        self.root = left;
+        let mut other = Self::with_rng(Rng::new(self.rng.next_u64()));
+        other.root = right;
+        other
+    }
+
+    // moves all elements of other to the end
+    pub fn append(&mut self, other: &mut Self) {
+        self.root = merge::<T, M>(self.root.take(), other.root.take());
+    }
+
+    // the first at elements and the rest, split_off by value
+    pub fn split(mut self, at: usize) -> (Self, Self) {
+        let right = self.split_off(at);
+        (self, right)
+    }
+
+    // the elements of self followed by those of other, append by value
+    pub fn concat(mut self, mut other: Self) -> Self {
+        self.append(&mut other);
+        self
+    }
+
+    pub fn reverse<R: RangeBounds<usize>>(&mut self, range: R) {
+        let Range { start, end } = bounds(range, self.len());
+        if end - start < 2 {
+            return;
+        }
+        let (left, right) = split::<T, M>(self.root.take(), start);
+        let (mut mid, right) = split::<T, M>(right, end - start);
+        if let Some(mid) = &mut mid {
+            mid.toggle();
+        }
+        self.root = merge::<T, M>(merge::<T, M>(left, mid), right);
+    }
+
+    // the summary of all elements
+    pub fn summary(&self) -> M::Summary {
+        match &self.root {
+            Some(root) => root.summary.clone(),
+            None => M::identity(),
+        }
+    }
+
+    pub fn range_summary<R: RangeBounds<usize>>(&self, range: R) -> M::Summary {
+        let Range { start, end } = bounds(range, self.len());
+        fold::<T, M>(&self.root, false, start, end)
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, M::Summary> {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            len: self.len(),
+        };
+        iter.push_left(self.root.as_deref(), false);
+        iter
+    }
+
+    // panics if a priority is above its parent's or a size or summary is stale
+    pub fn check_invariants(&self)
+    where
+        M::Summary: PartialEq + Debug,
+    {
+        fn check<T, M: Monoid<T>>(link: &Link<T, M::Summary>, priority: u64, parity: bool) -> usize
+        where
+            M::Summary: PartialEq + Debug,
+        {
+            let node = match link {
+                Some(node) => node,
+                None => return 0,
+            };
+            assert!(node.priority <= priority, "out of heap order");
+            let (left, right) = node.children(parity);
+            let inner = parity ^ node.reversed;
+            let (left_len, right_len) = (
+                check::<T, M>(left, node.priority, inner),
+                check::<T, M>(right, node.priority, inner),
+            );
+            assert_eq!(node.len, left_len + right_len + 1, "stale size");
+
+            let elem = M::summarize(&node.elem);
+            let summary = M::combine(&fold::<T, M>(left, inner, 0, left_len), &elem);
+            let summary = M::combine(&summary, &fold::<T, M>(right, inner, 0, right_len));
+            let rev_summary = M::combine(&fold::<T, M>(right, !inner, 0, right_len), &elem);
+            let rev_summary = M::combine(&rev_summary, &fold::<T, M>(left, !inner, 0, left_len));
+            let (expected, rev_expected) = if parity {
+                (&node.rev_summary, &node.summary)
+            } else {
+                (&node.summary, &node.rev_summary)
+            };
+            assert_eq!(expected, &summary, "stale summary");
+            assert_eq!(rev_expected, &rev_summary, "stale summary");
+            node.len
+        }
+
+        check::<T, M>(&self.root, u64::MAX, false);
+    }
+}
+
+impl<T, M: Monoid<T>> Default for ImplicitTreap<T, M> {
+    fn default() -> Self {
+        Self::with_rng(Rng::from_entropy())
+    }
+}
+
+impl<T, M: Monoid<T>> Index<usize> for ImplicitTreap<T, M> {
+    type Output = T;
+    fn index(&self, idx: usize) -> &T {
+        match self.get(idx) {
+            Some(elem) => elem,
+            None => panic!("index out of bounds"),
+        }
+    }
+}
+
+impl<T, M: Monoid<T>> FromIterator<T> for ImplicitTreap<T, M> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut treap = Self::default();
+        treap.extend(iter);
+        treap
+    }
+}
+
+impl<T, M: Monoid<T>> Extend<T> for ImplicitTreap<T, M> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T, M: Monoid<T>> IntoIterator for ImplicitTreap<T, M> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(mut self) -> Self::IntoIter {
+        let mut elems = Vec::with_capacity(self.len());
+        drain(self.root.take(), false, &mut elems);
+        elems.into_iter()
+    }
+}
+
+// ------------------------------------
+// begin: Iter
+
+// the stack holds the nodes still to be visited with the reversal pending below them
+pub struct Iter<'a, T, S> {
+    stack: Vec<(&'a Node<T, S>, bool)>,
+    len: usize,
+}
+
+impl<'a, T, S> Iter<'a, T, S> {
+    fn push_left(&mut self, mut link: Option<&'a Node<T, S>>, mut parity: bool) {
+        while let Some(node) = link {
+            let (left, _) = node.children(parity);
+            parity ^= node.reversed;
+            self.stack.push((node, parity));
+            link = left.as_deref();
+        }
+    }
+}
+
+impl<'a, T, S> Iterator for Iter<'a, T, S> {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, parity) = self.stack.pop()?;
+        let right = if parity { &node.left } else { &node.right };
+        self.push_left(right.as_deref(), parity);
+        self.len -= 1;
+        Some(&node.elem)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T, M: Monoid<T>> IntoIterator for &'a ImplicitTreap<T, M> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, M::Summary>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T, S> ExactSizeIterator for Iter<'_, T, S> {}
+
+impl<T, S> FusedIterator for Iter<'_, T, S> {}
+
+// end: Iter
+// ------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::ImplicitTreap;
+    use crate::rng::Rng;
+    use crate::tree::{Max, Monoid, Sum};
+
+    // concatenation, to catch summaries combined in the wrong order
+    struct Concat;
+
+    impl Monoid<char> for Concat {
+        type Summary = String;
+
+        fn identity() -> String {
+            String::new()
+        }
+
+        fn summarize(elem: &char) -> String {
+            elem.to_string()
+        }
+
+        fn combine(left: &String, right: &String) -> String {
+            format!("{}{}", left, right)
+        }
+    }
+
+    #[test]
+    fn test_implicit_treap() {
+        let mut treap = ImplicitTreap::<_>::with_seed(49);
+        assert_eq!(treap.pop_front(), None);
+        for i in 0..5 {
+            treap.push_back(i);
+        }
+        treap.push_front(-1);
+        treap.insert(3, 10);
+        assert!(treap.iter().copied().eq(vec![-1, 0, 1, 10, 2, 3, 4]));
+        assert_eq!(treap.remove(3), 10);
+        assert_eq!(treap.set(0, -2), -1);
+        assert_eq!(treap[0], -2);
+        assert_eq!(treap.get(6), None);
+
+        treap.reverse(1..5);
+        assert!(treap.iter().copied().eq(vec![-2, 3, 2, 1, 0, 4]));
+        assert_eq!(treap.front(), Some(&-2));
+        assert_eq!(treap.back(), Some(&4));
+
+        let mut tail = treap.split_off(2);
+        assert!(tail.iter().copied().eq(vec![2, 1, 0, 4]));
+        tail.reverse(..);
+        tail.append(&mut treap);
+        assert!(treap.is_empty());
+        assert!(tail.iter().copied().eq(vec![4, 0, 1, 2, -2, 3]));
+
+        let (left, right) = tail.split(4);
+        assert!(left.iter().copied().eq(vec![4, 0, 1, 2]));
+        assert!(right.iter().copied().eq(vec![-2, 3]));
+        let (empty, right) = right.split(0);
+        assert!(empty.is_empty());
+        assert!(right.concat(left).into_iter().eq(vec![-2, 3, 4, 0, 1, 2]));
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn test_insert_out_of_bounds() {
+        let mut treap = ImplicitTreap::<_>::with_seed(49);
+        treap.push_back(0);
+        treap.insert(2, 1);
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut treap = ImplicitTreap::<i64, Sum>::with_seed(49);
+        treap.extend(1..=10);
+        assert_eq!(treap.summary(), 55);
+        assert_eq!(treap.range_summary(2..5), 3 + 4 + 5);
+        assert_eq!(treap.range_summary(3..3), 0);
+        let (left, right) = treap.split(4);
+        assert_eq!((left.summary(), right.summary()), (10, 45));
+        let treap = right.concat(left);
+        assert_eq!(treap.range_summary(6..), 10);
+        treap.check_invariants();
+
+        let mut treap = ImplicitTreap::<i64, Max>::with_seed(49);
+        assert_eq!(treap.summary(), None);
+        treap.extend(vec![3, 1, 4, 1, 5, 9, 2, 6]);
+        assert_eq!(treap.range_summary(..5), Some(5));
+        assert_eq!(treap.range_summary(6..=7), Some(6));
+
+        let mut treap: ImplicitTreap<char, Concat> = "abcdefg".chars().collect();
+        treap.reverse(1..6);
+        treap.check_invariants();
+        assert_eq!(treap.summary(), "afedcbg");
+        assert_eq!(treap.range_summary(2..=4), "edc");
+        treap.reverse(..4);
+        assert_eq!(treap.summary(), "defacbg");
+        assert_eq!(treap.range_summary(1..), "efacbg");
+    }
+
+    #[test]
+    fn test_against_vec() {
+        let mut rng = Rng::new(49);
+        let mut treap = ImplicitTreap::<char, Concat>::with_seed(49);
+        let mut expected: Vec<char> = Vec::new();
+
+        for i in 0..3000 {
+            let len = expected.len();
+            let a = (rng.next_u64() as usize) % (len + 1);
+            let b = (rng.next_u64() as usize) % (len + 1);
+            let (start, end) = (a.min(b), a.max(b));
+            match rng.next_u64() % 6 {
+                0 | 1 => {
+                    let elem = (b'a' + (rng.next_u64() % 26) as u8) as char;
+                    treap.insert(a, elem);
+                    expected.insert(a, elem);
+                }
+                2 if a < len => assert_eq!(treap.remove(a), expected.remove(a)),
+                3 => {
+                    treap.reverse(start..end);
+                    expected[start..end].reverse();
+                }
+                4 if i % 2 == 0 => {
+                    let mut tail = treap.split_off(a);
+                    tail.check_invariants();
+                    tail.reverse(..);
+                    treap.append(&mut tail);
+                    expected[a..].reverse();
+                }
+                4 => {
+                    let (left, right) = treap.split(a);
+                    left.check_invariants();
+                    treap = right.concat(left);
+                    expected.rotate_left(a);
+                }
+                _ => {
+                    let summary: String = expected[start..end].iter().collect();
+                    assert_eq!(treap.range_summary(start..end), summary);
+                }
+            }
+            if i % 100 == 0 {
+                treap.check_invariants();
+                assert!(treap.iter().eq(expected.iter()));
+            }
+        }
+
+        treap.check_invariants();
+        assert_eq!(treap.len(), expected.len());
+        for (i, elem) in expected.iter().enumerate() {
+            assert_eq!(treap.get(i), Some(elem));
+        }
+        assert_eq!(treap.summary(), expected.iter().collect::<String>());
+        assert!(treap.into_iter().eq(expected.into_iter()));
+    }
+}
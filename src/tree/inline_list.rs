@@ -0,0 +1,189 @@
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+
+// Fixed-capacity list stored inside its owner, like a SequenceList without the heap buffer,
+// so a b-tree node keeps its entries and children next to each other.
+// It has room for 2 * B elements, kept as two arrays of B since the length of a
+// single array cannot be computed from B in a type.
+pub struct InlineList<T, const B: usize> {
+    len: usize,
+    items: [[MaybeUninit<T>; B]; 2],
+    // invariant: len <= 2 * B, the first len items are initialized
+}
+
+impl<T, const B: usize> InlineList<T, B> {
+    pub fn new() -> Self {
+        Self {
+            len: 0,
+            // an array of MaybeUninit needs no initialization
+            items: unsafe { MaybeUninit::uninit().assume_init() },
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        2 * B
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn as_ptr(&self) -> *const T {
+        (&self.items as *const [[MaybeUninit<T>; B]; 2]).cast()
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        (&mut self.items as *mut [[MaybeUninit<T>; B]; 2]).cast()
+    }
+
+    pub fn push(&mut self, elem: T) {
+        if self.len == self.capacity() {
+            panic!("inline list is full")
+        }
+        unsafe { self.as_mut_ptr().add(self.len).write(elem) };
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(unsafe { self.as_ptr().add(self.len).read() })
+    }
+
+    pub fn insert(&mut self, index: usize, elem: T) {
+        if index > self.len {
+            panic!("index out of bounds")
+        }
+        if self.len == self.capacity() {
+            panic!("inline list is full")
+        }
+        unsafe {
+            let src = self.as_mut_ptr().add(index);
+            ptr::copy(src, src.add(1), self.len - index);
+            src.write(elem);
+        }
+        self.len += 1;
+    }
+
+    pub fn remove(&mut self, index: usize) -> T {
+        if index >= self.len {
+            panic!("index out of bounds")
+        }
+        self.len -= 1;
+        unsafe {
+            let dst = self.as_mut_ptr().add(index);
+            let elem = dst.read();
+            ptr::copy(dst.add(1), dst, self.len - index);
+            elem
+        }
+    }
+
+    // moves the elements from at on into a new list
+    pub fn split_off(&mut self, at: usize) -> Self {
+        if at > self.len {
+            panic!("index out of bounds")
+        }
+        let mut other = Self::new();
+        let count = self.len - at;
+        unsafe { ptr::copy_nonoverlapping(self.as_ptr().add(at), other.as_mut_ptr(), count) };
+        self.len = at;
+        other.len = count;
+        other
+    }
+
+    // moves all elements of other to the end
+    pub fn append(&mut self, other: &mut Self) {
+        if self.len + other.len > self.capacity() {
+            panic!("inline list is full")
+        }
+        unsafe {
+            let dst = self.as_mut_ptr().add(self.len);
+            ptr::copy_nonoverlapping(other.as_ptr(), dst, other.len);
+        }
+        self.len += other.len;
+        other.len = 0;
+    }
+}
+
+impl<T, const B: usize> Drop for InlineList<T, B> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.as_mut_ptr(), self.len)) }
+    }
+}
+
+impl<T, const B: usize> Deref for InlineList<T, B> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.as_ptr(), self.len) }
+    }
+}
+
+impl<T, const B: usize> DerefMut for InlineList<T, B> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { std::slice::from_raw_parts_mut(self.as_mut_ptr(), self.len) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::InlineList;
+
+    use std::rc::Rc;
+
+    #[test]
+    fn test_inline_list() {
+        let mut list = <InlineList<String, 3>>::new();
+        assert_eq!(list.capacity(), 6);
+        for s in &["b", "d", "e"] {
+            list.push(s.to_string());
+        }
+        list.insert(0, "a".to_string());
+        list.insert(2, "c".to_string());
+        assert_eq!(&*list, ["a", "b", "c", "d", "e"]);
+        assert_eq!(list.remove(1), "b");
+        assert_eq!(list.pop().unwrap(), "e");
+
+        let mut tail = list.split_off(1);
+        assert_eq!(&*list, ["a"]);
+        assert_eq!(&*tail, ["c", "d"]);
+        list.append(&mut tail);
+        assert!(tail.is_empty());
+        assert_eq!(&*list, ["a", "c", "d"]);
+
+        let mut units = <InlineList<(), 2>>::new();
+        for _ in 0..4 {
+            units.push(());
+        }
+        assert_eq!(units.len(), 4);
+    }
+
+    #[test]
+    fn test_drop() {
+        let counter = Rc::new(());
+        let mut list = <InlineList<Rc<()>, 4>>::new();
+        for _ in 0..5 {
+            list.push(counter.clone());
+        }
+        let tail = list.split_off(2);
+        drop(list);
+        assert_eq!(Rc::strong_count(&counter), 4);
+        drop(tail);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "inline list is full")]
+    fn test_full() {
+        let mut list = <InlineList<i32, 1>>::new();
+        list.insert(0, 1);
+        list.insert(0, 2);
+        list.insert(0, 3);
+    }
+}
@@ -0,0 +1,19 @@
+pub mod augment;
+pub mod avl_map;
+pub mod b_plus_tree_map;
+pub mod b_tree_map;
+mod binary_iter;
+pub mod implicit_treap;
+mod inline_list;
+pub mod rb_tree_map;
+pub mod splay_map;
+pub mod treap;
+
+pub use augment::{Augment, Max, Min, Monoid, NoAugment, Sum};
+pub use avl_map::AvlMap;
+pub use b_plus_tree_map::BPlusTreeMap;
+pub use b_tree_map::BTreeMap;
+pub use implicit_treap::ImplicitTreap;
+pub use rb_tree_map::RbTreeMap;
+pub use splay_map::SplayMap;
+pub use treap::Treap;
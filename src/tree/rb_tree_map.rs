@@ -0,0 +1,1211 @@
+use super::augment::{Augment, NoAugment};
+use super::binary_iter::{after_start, before_end, BinaryNode, RawIter};
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::iter::FromIterator;
+use std::iter::FusedIterator;
+use std::mem;
+use std::ops::RangeBounds;
+
+type Link<K, V, A> = Option<Box<Node<K, V, A>>>;
+// a tree and its black height
+type Part<K, V, A> = (Link<K, V, A>, usize);
+
+struct Node<K, V, A: Augment<K, V>> {
+    key: K,
+    value: V,
+    // the color of the link from the parent
+    red: bool,
+    // the number of nodes in the subtree
+    len: usize,
+    summary: A::Summary,
+    left: Link<K, V, A>,
+    right: Link<K, V, A>,
+}
+
+impl<K, V, A: Augment<K, V>> Node<K, V, A> {
+    fn new(key: K, value: V, red: bool) -> Box<Self> {
+        let summary = A::summarize(&key, &value);
+        Box::new(Self {
+            key,
+            value,
+            red,
+            len: 1,
+            summary,
+            left: None,
+            right: None,
+        })
+    }
+
+    fn update(&mut self) {
+        self.len = len(&self.left) + len(&self.right) + 1;
+        let mut summary = A::summarize(&self.key, &self.value);
+        if let Some(left) = &self.left {
+            summary = A::combine(&left.summary, &summary);
+        }
+        if let Some(right) = &self.right {
+            summary = A::combine(&summary, &right.summary);
+        }
+        self.summary = summary;
+    }
+}
+
+impl<K, V, A: Augment<K, V>> BinaryNode for Node<K, V, A> {
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+fn len<K, V, A: Augment<K, V>>(link: &Link<K, V, A>) -> usize {
+    link.as_ref().map_or(0, |node| node.len)
+}
+
+fn is_red<K, V, A: Augment<K, V>>(link: &Link<K, V, A>) -> bool {
+    link.as_ref().is_some_and(|node| node.red)
+}
+
+fn is_left_red<K, V, A: Augment<K, V>>(link: &Link<K, V, A>) -> bool {
+    link.as_ref().is_some_and(|node| is_red(&node.left))
+}
+
+// cond: the right child is not None
+fn rotate_left<K, V, A: Augment<K, V>>(mut node: Box<Node<K, V, A>>) -> Box<Node<K, V, A>> {
+    let mut right = node.right.take().unwrap();
+    node.right = right.left.take();
+    right.red = node.red;
+    node.red = true;
+    node.update();
+    right.left = Some(node);
+    right.update();
+    right
+}
+
+// cond: the left child is not None
+fn rotate_right<K, V, A: Augment<K, V>>(mut node: Box<Node<K, V, A>>) -> Box<Node<K, V, A>> {
+    let mut left = node.left.take().unwrap();
+    node.left = left.right.take();
+    left.red = node.red;
+    node.red = true;
+    node.update();
+    left.right = Some(node);
+    left.update();
+    left
+}
+
+// cond: both children are not None
+fn flip_colors<K, V, A: Augment<K, V>>(node: &mut Node<K, V, A>) {
+    node.red = !node.red;
+    for child in [&mut node.left, &mut node.right] {
+        let child = child.as_mut().unwrap();
+        child.red = !child.red;
+    }
+}
+
+// restores the invariants on the way up: red links lean left and no node has two red links
+fn fix_up<K, V, A: Augment<K, V>>(mut node: Box<Node<K, V, A>>) -> Box<Node<K, V, A>> {
+    if is_red(&node.right) && !is_red(&node.left) {
+        node = rotate_left(node);
+    }
+    if is_red(&node.left) && is_left_red(&node.left) {
+        node = rotate_right(node);
+    }
+    if is_red(&node.left) && is_red(&node.right) {
+        flip_colors(&mut node);
+    }
+    node.update();
+    node
+}
+
+// makes the left child or one of its children red, before descending to the left
+fn move_red_left<K, V, A: Augment<K, V>>(mut node: Box<Node<K, V, A>>) -> Box<Node<K, V, A>> {
+    flip_colors(&mut node);
+    if is_left_red(&node.right) {
+        node.right = node.right.take().map(rotate_right);
+        node = rotate_left(node);
+        flip_colors(&mut node);
+    }
+    node
+}
+
+// makes the right child or one of its children red, before descending to the right
+fn move_red_right<K, V, A: Augment<K, V>>(mut node: Box<Node<K, V, A>>) -> Box<Node<K, V, A>> {
+    flip_colors(&mut node);
+    if is_left_red(&node.left) {
+        node = rotate_right(node);
+        flip_colors(&mut node);
+    }
+    node
+}
+
+// returns the new subtree and the old value
+fn insert<K: Ord, V, A: Augment<K, V>>(
+    link: Link<K, V, A>,
+    key: K,
+    value: V,
+) -> (Box<Node<K, V, A>>, Option<V>) {
+    let mut node = match link {
+        Some(node) => node,
+        None => return (Node::new(key, value, true), None),
+    };
+    let old = match key.cmp(&node.key) {
+        Ordering::Less => {
+            let (left, old) = insert(node.left.take(), key, value);
+            node.left = Some(left);
+            old
+        }
+        Ordering::Greater => {
+            let (right, old) = insert(node.right.take(), key, value);
+            node.right = Some(right);
+            old
+        }
+        Ordering::Equal => Some(mem::replace(&mut node.value, value)),
+    };
+    (fix_up(node), old)
+}
+
+// returns the rest of the subtree and the removed entry
+// cond: the node or its left child is red
+fn pop_min<K, V, A: Augment<K, V>>(mut node: Box<Node<K, V, A>>) -> (Link<K, V, A>, (K, V)) {
+    if node.left.is_none() {
+        let node = *node;
+        return (None, (node.key, node.value));
+    }
+    if !is_red(&node.left) && !is_left_red(&node.left) {
+        node = move_red_left(node);
+    }
+    let (left, min) = pop_min(node.left.take().unwrap());
+    node.left = left;
+    (Some(fix_up(node)), min)
+}
+
+// cond: the node or its right child is red
+fn pop_max<K, V, A: Augment<K, V>>(mut node: Box<Node<K, V, A>>) -> (Link<K, V, A>, (K, V)) {
+    if is_red(&node.left) {
+        node = rotate_right(node);
+    }
+    if node.right.is_none() {
+        let node = *node;
+        return (None, (node.key, node.value));
+    }
+    if !is_red(&node.right) && !is_left_red(&node.right) {
+        node = move_red_right(node);
+    }
+    let (right, max) = pop_max(node.right.take().unwrap());
+    node.right = right;
+    (Some(fix_up(node)), max)
+}
+
+// cond: the key is in the subtree, the node or one of its children is red
+fn remove<K, V, A, Q>(mut node: Box<Node<K, V, A>>, key: &Q) -> (Link<K, V, A>, (K, V))
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+    A: Augment<K, V>,
+{
+    if key < node.key.borrow() {
+        if !is_red(&node.left) && !is_left_red(&node.left) {
+            node = move_red_left(node);
+        }
+        let (left, removed) = remove(node.left.take().unwrap(), key);
+        node.left = left;
+        return (Some(fix_up(node)), removed);
+    }
+    if is_red(&node.left) {
+        node = rotate_right(node);
+    }
+    if key == node.key.borrow() && node.right.is_none() {
+        let node = *node;
+        return (None, (node.key, node.value));
+    }
+    if !is_red(&node.right) && !is_left_red(&node.right) {
+        node = move_red_right(node);
+    }
+    let removed = if key == node.key.borrow() {
+        // the successor takes the place of the node
+        let (right, (key, value)) = pop_min(node.right.take().unwrap());
+        node.right = right;
+        (
+            mem::replace(&mut node.key, key),
+            mem::replace(&mut node.value, value),
+        )
+    } else {
+        let (right, removed) = remove(node.right.take().unwrap(), key);
+        node.right = right;
+        removed
+    };
+    (Some(fix_up(node)), removed)
+}
+
+// moves the entries of the subtree to the end of out in key order
+fn drain<K, V, A: Augment<K, V>>(link: Link<K, V, A>, out: &mut Vec<(K, V)>) {
+    if let Some(node) = link {
+        let node = *node;
+        drain(node.left, out);
+        out.push((node.key, node.value));
+        drain(node.right, out);
+    }
+}
+
+// builds a tree of len entries whose 2-3 tree has the given number of levels,
+// which needs 2^levels - 1 <= len <= 3^levels - 1
+fn build<K, V, A, I>(items: &mut I, len: usize, levels: u32) -> Link<K, V, A>
+where
+    A: Augment<K, V>,
+    I: Iterator<Item = (K, V)>,
+{
+    if len == 0 {
+        return None;
+    }
+    // the most entries a subtree of one level less holds
+    let most = 3usize.saturating_pow(levels - 1) - 1;
+    let mut node = if len <= most.saturating_mul(2) + 1 {
+        let left = build(items, (len - 1) / 2, levels - 1);
+        let (key, value) = items.next().unwrap();
+        let mut node = Node::new(key, value, false);
+        node.left = left;
+        node.right = build(items, len / 2, levels - 1);
+        node
+    } else {
+        // a 3-node, that is a black node with a red left child
+        let rest = len - 2;
+        let (a, b) = (rest / 3, (rest - rest / 3) / 2);
+        let left = build(items, a, levels - 1);
+        let (key, value) = items.next().unwrap();
+        let mut red = Node::new(key, value, true);
+        red.left = left;
+        red.right = build(items, b, levels - 1);
+        red.update();
+        let (key, value) = items.next().unwrap();
+        let mut node = Node::new(key, value, false);
+        node.left = Some(red);
+        node.right = build(items, rest - a - b, levels - 1);
+        node
+    };
+    node.update();
+    Some(node)
+}
+
+// the number of black nodes on a path from the root to a leaf, which is the same on all of them
+fn black_height<K, V, A: Augment<K, V>>(mut link: &Link<K, V, A>) -> usize {
+    let mut height = 0;
+    while let Some(node) = link {
+        height += !node.red as usize;
+        link = &node.left;
+    }
+    height
+}
+
+// makes the root black, which adds one to the black height of a red root
+fn blacken<K, V, A: Augment<K, V>>(link: Link<K, V, A>, height: usize) -> Part<K, V, A> {
+    match link {
+        Some(mut node) if node.red => {
+            node.red = false;
+            (Some(node), height + 1)
+        }
+        link => (link, height),
+    }
+}
+
+// makes mid a red node between two subtrees of the same black height
+fn place<K, V, A: Augment<K, V>>(
+    mut mid: Box<Node<K, V, A>>,
+    left: Link<K, V, A>,
+    right: Link<K, V, A>,
+) -> Box<Node<K, V, A>> {
+    mid.red = true;
+    mid.left = left;
+    mid.right = right;
+    mid.update();
+    mid
+}
+
+// Descends the right spine of the subtree of black height height down to the black height
+// of right, puts mid there and restores the invariants on the way up like an insert.
+fn join_right<K, V, A: Augment<K, V>>(
+    link: Link<K, V, A>,
+    height: usize,
+    mid: Box<Node<K, V, A>>,
+    right: Link<K, V, A>,
+    right_height: usize,
+) -> Box<Node<K, V, A>> {
+    match link {
+        Some(mut node) if height > right_height || node.red => {
+            let child = height - !node.red as usize;
+            node.right = Some(join_right(
+                node.right.take(),
+                child,
+                mid,
+                right,
+                right_height,
+            ));
+            fix_up(node)
+        }
+        link => place(mid, link, right),
+    }
+}
+
+// the mirror of join_right on the left spine, whose red nodes it passes
+fn join_left<K, V, A: Augment<K, V>>(
+    left: Link<K, V, A>,
+    left_height: usize,
+    mid: Box<Node<K, V, A>>,
+    link: Link<K, V, A>,
+    height: usize,
+) -> Box<Node<K, V, A>> {
+    match link {
+        Some(mut node) if height > left_height || node.red => {
+            let child = height - !node.red as usize;
+            node.left = Some(join_left(left, left_height, mid, node.left.take(), child));
+            fix_up(node)
+        }
+        link => place(mid, left, link),
+    }
+}
+
+// Joins the trees with the keys before and after the key of mid, given their black heights,
+// in O(1 + the difference of the black heights). Returns the tree and its black height.
+fn join<K, V, A: Augment<K, V>>(
+    left: Link<K, V, A>,
+    left_height: usize,
+    mid: Box<Node<K, V, A>>,
+    right: Link<K, V, A>,
+    right_height: usize,
+) -> (Box<Node<K, V, A>>, usize) {
+    let (left, left_height) = blacken(left, left_height);
+    let (right, right_height) = blacken(right, right_height);
+    let root = match left_height.cmp(&right_height) {
+        Ordering::Greater => join_right(left, left_height, mid, right, right_height),
+        Ordering::Less => join_left(left, left_height, mid, right, right_height),
+        Ordering::Equal => place(mid, left, right),
+    };
+    let (root, height) = blacken(Some(root), left_height.max(right_height));
+    (root.unwrap(), height)
+}
+
+// Splits the subtree of black height height into the keys less than the key and the others,
+// with their black heights. The subtrees hanging off the search path are joined back
+// on the way up, and the costs of the joins add up to O(log n).
+fn split<K, V, A, Q>(link: Link<K, V, A>, height: usize, key: &Q) -> (Part<K, V, A>, Part<K, V, A>)
+where
+    K: Borrow<Q>,
+    Q: Ord + ?Sized,
+    A: Augment<K, V>,
+{
+    let mut node = match link {
+        Some(node) => node,
+        None => return ((None, 0), (None, 0)),
+    };
+    let child = height - !node.red as usize;
+    let (left, right) = (node.left.take(), node.right.take());
+    if key <= node.key.borrow() {
+        let (less, (rest, rest_height)) = split(left, child, key);
+        let (tree, tree_height) = join(rest, rest_height, node, right, child);
+        (less, (Some(tree), tree_height))
+    } else {
+        let ((rest, rest_height), greater) = split(right, child, key);
+        let (tree, tree_height) = join(left, child, node, rest, rest_height);
+        ((Some(tree), tree_height), greater)
+    }
+}
+
+fn concat<K, V, A: Augment<K, V>>(
+    left: Option<A::Summary>,
+    right: Option<A::Summary>,
+) -> Option<A::Summary> {
+    match (left, right) {
+        (Some(left), Some(right)) => Some(A::combine(&left, &right)),
+        (left, right) => left.or(right),
+    }
+}
+
+// Left-leaning red-black tree (Sedgewick), a binary encoding of a 2-3 tree
+// where a red node is the smaller key of a 3-node, so the height is at most 2 log2(n + 1).
+// Every node keeps the size and the summary of its subtree, which gives select, rank
+// and range summaries in O(log n). Values are only replaced through insert so that
+// the summaries stay up to date. split_off and append split and join the trees by their
+// black heights in O(log n), append merges the entries in O(n) if the key ranges interleave.
+pub struct RbTreeMap<K, V, A: Augment<K, V> = NoAugment> {
+    root: Link<K, V, A>,
+}
+
+impl<K, V> RbTreeMap<K, V> {
+    pub fn new() -> Self {
+        Self { root: None }
+    }
+}
+
+impl<K, V, A: Augment<K, V>> RbTreeMap<K, V, A> {
+    pub fn len(&self) -> usize {
+        len(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    // cond: the map is not empty
+    fn remove_root<F, T>(&mut self, f: F) -> T
+    where
+        F: FnOnce(Box<Node<K, V, A>>) -> (Link<K, V, A>, T),
+    {
+        let mut root = self.root.take().unwrap();
+        if !is_red(&root.left) && !is_red(&root.right) {
+            root.red = true;
+        }
+        let (root, ret) = f(root);
+        self.root = root;
+        if let Some(root) = &mut self.root {
+            root.red = false;
+        }
+        ret
+    }
+
+    // builds from entries with increasing keys in linear time
+    fn from_sorted(items: Vec<(K, V)>) -> Self {
+        let len = items.len();
+        let levels = usize::BITS - 1 - (len + 1).leading_zeros();
+        Self {
+            root: build(&mut items.into_iter(), len, levels),
+        }
+    }
+
+    fn into_sorted(self) -> Vec<(K, V)> {
+        let mut items = Vec::with_capacity(self.len());
+        drain(self.root, &mut items);
+        items
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        self.root.as_ref()?;
+        Some(self.remove_root(pop_min))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.root.as_ref()?;
+        Some(self.remove_root(pop_max))
+    }
+
+    // the entry with index-th smallest key, from 0
+    pub fn select(&self, mut index: usize) -> Option<(&K, &V)> {
+        let mut link = self.root.as_deref();
+        while let Some(node) = link {
+            let left = len(&node.left);
+            link = match index.cmp(&left) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => {
+                    index -= left + 1;
+                    node.right.as_deref()
+                }
+                Ordering::Equal => return Some((&node.key, &node.value)),
+            };
+        }
+        None
+    }
+
+    // the summary of all the entries
+    pub fn summary(&self) -> Option<&A::Summary> {
+        self.root.as_ref().map(|node| &node.summary)
+    }
+
+    // in key order
+    pub fn iter(&self) -> Iter<'_, K, V, A> {
+        Iter {
+            iter: RawIter::new(self.root.as_deref()),
+            len: self.len(),
+        }
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>> RbTreeMap<K, V, A> {
+    fn find<Q>(&self, key: &Q) -> Option<&Node<K, V, A>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut link = self.root.as_deref();
+        while let Some(node) = link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return Some(node),
+            };
+        }
+        None
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (mut root, old) = insert(self.root.take(), key, value);
+        root.red = false;
+        self.root = Some(root);
+        old
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|node| &node.value)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|node| (&node.key, &node.value))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        // the top-down pass needs the key to be there
+        self.find(key)?;
+        Some(self.remove_root(|root| remove(root, key)))
+    }
+
+    // the number of keys less than the key
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut rank = 0;
+        let mut link = self.root.as_deref();
+        while let Some(node) = link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => {
+                    rank += len(&node.left) + 1;
+                    node.right.as_deref()
+                }
+                Ordering::Equal => return rank + len(&node.left),
+            };
+        }
+        rank
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V, A>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let iter = RawIter::range(
+            self.root.as_deref(),
+            |node| after_start(node.key.borrow(), range.start_bound()),
+            |node| before_end(node.key.borrow(), range.end_bound()),
+        );
+        Range { iter }
+    }
+
+    // the summary of the entries in the range, None if there are none
+    pub fn range_summary<Q, R>(&self, range: R) -> Option<A::Summary>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+        A::Summary: Clone,
+    {
+        let summary = |link: &Link<K, V, A>| link.as_ref().map(|node| node.summary.clone());
+        let entry = |node: &Node<K, V, A>| Some(A::summarize(&node.key, &node.value));
+
+        // the topmost node in the range splits it into a suffix of its left subtree
+        // and a prefix of its right subtree
+        let mut link = self.root.as_deref();
+        let split = loop {
+            let node = link?;
+            let key = node.key.borrow();
+            if !after_start(key, range.start_bound()) {
+                link = node.right.as_deref();
+            } else if !before_end(key, range.end_bound()) {
+                link = node.left.as_deref();
+            } else {
+                break node;
+            }
+        };
+
+        let mut suffix = None;
+        let mut link = split.left.as_deref();
+        while let Some(node) = link {
+            if after_start(node.key.borrow(), range.start_bound()) {
+                let part = concat::<K, V, A>(entry(node), summary(&node.right));
+                suffix = concat::<K, V, A>(part, suffix);
+                link = node.left.as_deref();
+            } else {
+                link = node.right.as_deref();
+            }
+        }
+        let mut prefix = None;
+        let mut link = split.right.as_deref();
+        while let Some(node) = link {
+            if before_end(node.key.borrow(), range.end_bound()) {
+                let part = concat::<K, V, A>(summary(&node.left), entry(node));
+                prefix = concat::<K, V, A>(prefix, part);
+                link = node.right.as_deref();
+            } else {
+                link = node.left.as_deref();
+            }
+        }
+        let middle = concat::<K, V, A>(suffix, entry(split));
+        concat::<K, V, A>(middle, prefix)
+    }
+
+    // moves the entries whose keys are at least the key into a new map
+    pub fn split_off<Q>(&mut self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let height = black_height(&self.root);
+        let ((less, _), (rest, _)) = split(self.root.take(), height, key);
+        self.root = blacken(less, 0).0;
+        Self {
+            root: blacken(rest, 0).0,
+        }
+    }
+
+    // Moves all the entries of other into self, the values of other replace those of the
+    // same keys in self. The trees are joined in O(log n) if all the keys of one map are
+    // less than those of the other, otherwise the merged entries are rebuilt into a tree.
+    pub fn append(&mut self, other: &mut Self) {
+        if other.is_empty() {
+            return;
+        }
+        if self.is_empty() {
+            mem::swap(self, other);
+            return;
+        }
+        let before = self.last().unwrap().0 < other.first().unwrap().0;
+        if before || other.last().unwrap().0 < self.first().unwrap().0 {
+            let (mut left, mut right) = (mem::take(self), mem::take(other));
+            if !before {
+                mem::swap(&mut left, &mut right);
+            }
+            let (key, value) = right.pop_first().unwrap();
+            let (left_height, right_height) = (black_height(&left.root), black_height(&right.root));
+            let mid = Node::new(key, value, true);
+            let (root, _) = join(left.root, left_height, mid, right.root, right_height);
+            self.root = Some(root);
+            return;
+        }
+        let mut left = mem::take(self).into_sorted().into_iter().peekable();
+        let mut right = mem::take(other).into_sorted().into_iter().peekable();
+        let mut items = Vec::with_capacity(left.len() + right.len());
+        loop {
+            let ord = match (left.peek(), right.peek()) {
+                (Some((l, _)), Some((r, _))) => l.cmp(r),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => break,
+            };
+            match ord {
+                Ordering::Less => items.extend(left.next()),
+                Ordering::Greater => items.extend(right.next()),
+                Ordering::Equal => {
+                    left.next();
+                    items.extend(right.next());
+                }
+            }
+        }
+        *self = Self::from_sorted(items);
+    }
+
+    // panics if the keys are out of order, a red link leans right, two red links are
+    // in a row, the black heights differ or a size is stale
+    pub fn check_invariants(&self)
+    where
+        K: Debug,
+    {
+        // returns the black height
+        fn check<K: Ord + Debug, V, A: Augment<K, V>>(
+            link: &Link<K, V, A>,
+            lower: Option<&K>,
+            upper: Option<&K>,
+        ) -> usize {
+            let node = match link {
+                Some(node) => node,
+                None => return 0,
+            };
+            if let Some(lower) = lower {
+                assert!(lower < &node.key, "{:?} is out of order", node.key);
+            }
+            if let Some(upper) = upper {
+                assert!(&node.key < upper, "{:?} is out of order", node.key);
+            }
+            assert!(!is_red(&node.right), "red right link at {:?}", node.key);
+            assert!(
+                !(node.red && is_red(&node.left)),
+                "two red links at {:?}",
+                node.key
+            );
+            let left = check(&node.left, lower, Some(&node.key));
+            let right = check(&node.right, Some(&node.key), upper);
+            assert_eq!(left, right, "unbalanced at {:?}", node.key);
+            assert_eq!(
+                node.len,
+                len(&node.left) + len(&node.right) + 1,
+                "stale size at {:?}",
+                node.key
+            );
+            left + !node.red as usize
+        }
+
+        assert!(!is_red(&self.root), "red root");
+        check(&self.root, None, None);
+    }
+}
+
+impl<K, V, A: Augment<K, V>> Default for RbTreeMap<K, V, A> {
+    fn default() -> Self {
+        Self { root: None }
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>> FromIterator<(K, V)> for RbTreeMap<K, V, A> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>> Extend<(K, V)> for RbTreeMap<K, V, A> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+// ------------------------------------
+// begin: IntoIter
+
+pub struct IntoIter<K, V, A: Augment<K, V>>(RbTreeMap<K, V, A>);
+
+impl<K, V, A: Augment<K, V>> Iterator for IntoIter<K, V, A> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_first()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len(), Some(self.0.len()))
+    }
+}
+
+impl<K, V, A: Augment<K, V>> IntoIterator for RbTreeMap<K, V, A> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<K, V, A: Augment<K, V>> DoubleEndedIterator for IntoIter<K, V, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_last()
+    }
+}
+
+impl<K, V, A: Augment<K, V>> ExactSizeIterator for IntoIter<K, V, A> {}
+
+impl<K, V, A: Augment<K, V>> FusedIterator for IntoIter<K, V, A> {}
+
+// end: IntoIter
+// ------------------------------------
+
+// ------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, K, V, A: Augment<K, V>> {
+    iter: RawIter<'a, Node<K, V, A>>,
+    len: usize,
+}
+
+impl<'a, K, V, A: Augment<K, V>> Iterator for Iter<'a, K, V, A> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.iter.next_front()?;
+        self.len -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V, A: Augment<K, V>> IntoIterator for &'a RbTreeMap<K, V, A> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V, A: Augment<K, V>> DoubleEndedIterator for Iter<'_, K, V, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.iter.next_back()?;
+        self.len -= 1;
+        Some((&node.key, &node.value))
+    }
+}
+
+impl<K, V, A: Augment<K, V>> ExactSizeIterator for Iter<'_, K, V, A> {}
+
+impl<K, V, A: Augment<K, V>> FusedIterator for Iter<'_, K, V, A> {}
+
+// end: Iter
+// ------------------------------------
+
+// ------------------------------------
+// begin: Range
+
+pub struct Range<'a, K, V, A: Augment<K, V>> {
+    iter: RawIter<'a, Node<K, V, A>>,
+}
+
+impl<'a, K, V, A: Augment<K, V>> Iterator for Range<'a, K, V, A> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next_front().map(|node| (&node.key, &node.value))
+    }
+}
+
+impl<K, V, A: Augment<K, V>> DoubleEndedIterator for Range<'_, K, V, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|node| (&node.key, &node.value))
+    }
+}
+
+impl<K, V, A: Augment<K, V>> FusedIterator for Range<'_, K, V, A> {}
+
+// end: Range
+// ------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::RbTreeMap;
+    use crate::rng::Rng;
+    use crate::tree::augment::{Augment, Max, Min, Sum};
+
+    use std::cell::Cell;
+    use std::collections::BTreeMap;
+    use std::ops::{Bound, RangeBounds};
+
+    thread_local! {
+        static SUMMARIZED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    // counts the nodes whose summaries are computed, which bounds the nodes an operation touches
+    struct Counted;
+
+    impl Augment<u32, u32> for Counted {
+        type Summary = ();
+
+        fn summarize(_: &u32, _: &u32) {
+            SUMMARIZED.with(|count| count.set(count.get() + 1));
+        }
+
+        fn combine(_: &(), _: &()) {}
+    }
+
+    fn summarized<F: FnOnce()>(f: F) -> usize {
+        let before = SUMMARIZED.with(Cell::get);
+        f();
+        SUMMARIZED.with(Cell::get) - before
+    }
+
+    #[test]
+    fn test_rb_tree_map() {
+        let mut map = RbTreeMap::new();
+        assert_eq!(map.first(), None);
+        assert_eq!(map.select(0), None);
+        for k in (1..=9).rev() {
+            assert_eq!(map.insert(k, k * 10), None);
+            map.check_invariants();
+        }
+        assert_eq!(map.insert(4, 0), Some(40));
+        assert_eq!(map.get(&4), Some(&0));
+        assert_eq!(map.get(&10), None);
+        assert_eq!(map.len(), 9);
+
+        let keys = |iter: &mut dyn Iterator<Item = (&i32, &i32)>| {
+            iter.map(|(k, _)| *k).collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&mut map.iter()), [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(keys(&mut map.range(3..6).rev()), [5, 4, 3]);
+        assert_eq!(keys(&mut map.range(10..)), []);
+
+        assert_eq!(map.select(0), Some((&1, &10)));
+        assert_eq!(map.select(8), Some((&9, &90)));
+        assert_eq!(map.select(9), None);
+        assert_eq!(map.rank(&1), 0);
+        assert_eq!(map.rank(&5), 4);
+        assert_eq!(map.rank(&0), 0);
+        assert_eq!(map.rank(&100), 9);
+
+        let mut tail = map.split_off(&6);
+        map.check_invariants();
+        tail.check_invariants();
+        assert_eq!(keys(&mut map.iter()), [1, 2, 3, 4, 5]);
+        assert_eq!(keys(&mut tail.iter()), [6, 7, 8, 9]);
+        assert_eq!(tail.rank(&8), 2);
+
+        let mut other: RbTreeMap<_, _> = (5..=7).map(|k| (k, 0)).collect();
+        map.append(&mut other);
+        assert!(other.is_empty());
+        assert_eq!(map.get(&5), Some(&0));
+        map.append(&mut tail);
+        map.check_invariants();
+        assert_eq!(keys(&mut map.iter()), [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(map.get(&7), Some(&70));
+
+        assert_eq!(map.remove(&5), Some(0));
+        assert_eq!(map.remove(&5), None);
+        assert_eq!(map.pop_first(), Some((1, 10)));
+        assert_eq!(map.pop_last(), Some((9, 90)));
+        map.check_invariants();
+        assert_eq!(
+            map.into_iter().collect::<Vec<_>>(),
+            [(2, 20), (3, 30), (4, 0), (6, 60), (7, 70), (8, 80)]
+        );
+    }
+
+    #[test]
+    fn test_augment() {
+        let mut sums = RbTreeMap::<u32, u64, Sum>::default();
+        let mut mins = RbTreeMap::<u32, u64, Min>::default();
+        let mut maxs = RbTreeMap::<u32, u64, Max>::default();
+        assert_eq!(sums.summary(), None);
+        for k in 0..100 {
+            let v = (k as u64 * 37) % 101;
+            sums.insert(k, v);
+            mins.insert(k, v);
+            maxs.insert(k, v);
+        }
+        assert_eq!(sums.summary(), Some(&(0..100).map(|k| k * 37 % 101).sum()));
+        assert_eq!(
+            sums.range_summary(10..20),
+            Some(sums.range(10..20).map(|(_, v)| v).sum())
+        );
+        assert_eq!(mins.range_summary(..), Some(0));
+        assert_eq!(mins.range_summary(1..), Some(1));
+        assert_eq!(
+            maxs.range_summary(..=50),
+            maxs.range(..=50).map(|(_, v)| *v).max()
+        );
+        assert_eq!(sums.range_summary(200..), None);
+        assert_eq!(sums.range_summary(5..5), None);
+
+        sums.insert(10, 1000);
+        sums.remove(&11);
+        assert_eq!(sums.range_summary(10..=11), Some(1000));
+        let tail = sums.split_off(&50);
+        assert_eq!(sums.summary(), Some(&sums.iter().map(|(_, v)| v).sum()));
+        assert_eq!(tail.summary(), Some(&tail.iter().map(|(_, v)| v).sum()));
+    }
+
+    #[test]
+    fn test_order_statistics() {
+        // even keys, so that the bounds fall both on keys and between them
+        let map: RbTreeMap<u32, u64, Sum> = (0..30).map(|k| (2 * k, u64::from(k))).collect();
+        map.check_invariants();
+        for i in 0..30 {
+            assert_eq!(map.select(i as usize), Some((&(2 * i), &u64::from(i))));
+            assert_eq!(map.rank(&(2 * i)), i as usize);
+            assert_eq!(map.rank(&(2 * i + 1)), i as usize + 1);
+        }
+        assert_eq!(map.select(30), None);
+
+        let bounds = |k| [Bound::Included(k), Bound::Excluded(k), Bound::Unbounded];
+        for lo in 0..62 {
+            for hi in 0..62 {
+                for &start in &bounds(lo) {
+                    for &end in &bounds(hi) {
+                        let within: Vec<_> = map
+                            .iter()
+                            .filter(|&(k, _)| (start, end).contains(k))
+                            .collect();
+                        assert!(map.range((start, end)).eq(within.iter().copied()));
+                        let sum = within.iter().map(|&(_, v)| v).sum::<u64>();
+                        let summary = map.range_summary((start, end));
+                        assert_eq!(summary, Some(sum).filter(|_| !within.is_empty()));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_off_append() {
+        let mut rng = Rng::new(47);
+        let mut map = RbTreeMap::<u32, u64, Sum>::default();
+        let mut expected = BTreeMap::new();
+        for i in 0..2000 {
+            let key = (rng.next_u64() % 1000) as u32;
+            map.insert(key, i);
+            expected.insert(key, i);
+        }
+
+        for at in [0, 1, 333, 999, 1000] {
+            let mut tail = map.split_off(&at);
+            let expected_tail = expected.split_off(&at);
+            map.check_invariants();
+            tail.check_invariants();
+            assert!(map.iter().eq(expected.iter()));
+            assert!(tail.iter().eq(expected_tail.iter()));
+            assert_eq!(
+                tail.summary().copied(),
+                expected_tail.values().copied().reduce(|a, b| a + b)
+            );
+            map.append(&mut tail);
+            expected.extend(expected_tail);
+            map.check_invariants();
+            assert!(map.iter().eq(expected.iter()));
+        }
+
+        // trees of every size
+        for n in 0..200 {
+            let mut map: RbTreeMap<_, _> = (0..n).map(|k| (k, k)).collect();
+            let tail = map.split_off(&(n / 3));
+            map.check_invariants();
+            tail.check_invariants();
+            assert_eq!(map.len() + tail.len(), n);
+        }
+    }
+
+    #[test]
+    fn test_split_join() {
+        // random splits, joined back in either order since the key ranges do not interleave
+        let mut rng = Rng::new(47);
+        let mut map: RbTreeMap<u32, u64, Sum> = (0..3000).map(|k| (k, u64::from(k))).collect();
+        for _ in 0..300 {
+            let at = (rng.next_u64() % 3100) as u32;
+            let mut tail = map.split_off(&at);
+            map.check_invariants();
+            tail.check_invariants();
+            assert_eq!(map.len(), at.min(3000) as usize);
+            assert_eq!(
+                map.last().map(|(k, _)| k + 1),
+                Some(at.min(3000)).filter(|&k| k > 0)
+            );
+            assert_eq!(
+                tail.first().map(|(k, _)| *k),
+                Some(at).filter(|&k| k < 3000)
+            );
+            assert_eq!(
+                tail.summary().copied(),
+                Some((u64::from(at)..3000).sum()).filter(|_| at < 3000)
+            );
+            if rng.next_u64() & 1 == 0 {
+                map.append(&mut tail);
+            } else {
+                tail.append(&mut map);
+                map = tail;
+            }
+            map.check_invariants();
+            assert_eq!(map.summary().copied(), Some((0..3000).sum()));
+        }
+        assert!(map.iter().map(|(k, _)| *k).eq(0..3000));
+        assert!((0..3000).all(|k| map.rank(&k) == k as usize));
+    }
+
+    #[test]
+    fn test_split_join_cost() {
+        // splitting and joining touch O(log n) nodes, rebuilding would touch all of them
+        let mut map: RbTreeMap<u32, u32, Counted> = (0..1 << 16).map(|k| (k, k)).collect();
+        let mut tail = RbTreeMap::default();
+        assert!(summarized(|| tail = map.split_off(&40_000)) < 200);
+        assert!(summarized(|| map.append(&mut tail)) < 200);
+        assert!(summarized(|| tail = map.split_off(&1)) < 200);
+        assert!(summarized(|| tail.append(&mut map)) < 200);
+        tail.check_invariants();
+        assert_eq!(tail.len(), 1 << 16);
+
+        // interleaved keys are merged
+        let mut odd: RbTreeMap<u32, u32, Counted> = (0..100).map(|k| (2 * k + 1, k)).collect();
+        tail.append(&mut odd);
+        tail.check_invariants();
+        assert_eq!(tail.len(), 1 << 16);
+        assert_eq!(tail.get(&99), Some(&49));
+        assert_eq!(tail.get(&100), Some(&100));
+    }
+
+    #[test]
+    fn test_random_operations() {
+        let mut rng = Rng::new(48);
+        let mut map = RbTreeMap::<u32, u64, Max>::default();
+        let mut expected = BTreeMap::new();
+
+        for i in 0..5000 {
+            let key = (rng.next_u64() % 1000) as u32;
+            match rng.next_u64() % 4 {
+                0 => assert_eq!(map.remove(&key), expected.remove(&key)),
+                1 => assert_eq!(map.pop_first(), expected.pop_first()),
+                _ => assert_eq!(map.insert(key, i), expected.insert(key, i)),
+            }
+            if i % 100 == 0 {
+                map.check_invariants();
+                // the sizes and summaries survive the rotations and color flips
+                assert_eq!(map.len(), expected.len());
+                assert_eq!(map.summary().copied(), expected.values().copied().max());
+            }
+        }
+
+        map.check_invariants();
+        assert!(map.iter().eq(expected.iter()));
+        assert!(map.iter().rev().eq(expected.iter().rev()));
+        for (i, (k, _)) in expected.iter().enumerate() {
+            assert_eq!(map.select(i).map(|(k, _)| k), Some(k));
+            assert_eq!(map.rank(k), i);
+        }
+    }
+}
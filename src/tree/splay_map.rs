@@ -0,0 +1,702 @@
+use super::binary_iter::{after_start, before_end, BinaryNode, RawIter};
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::iter::FromIterator;
+use std::iter::FusedIterator;
+use std::mem;
+use std::ops::RangeBounds;
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // the number of nodes in the subtree
+    len: usize,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K, V> Node<K, V> {
+    fn new(key: K, value: V) -> Box<Self> {
+        Box::new(Self {
+            key,
+            value,
+            len: 1,
+            left: None,
+            right: None,
+        })
+    }
+
+    fn update(&mut self) {
+        self.len = len(&self.left) + len(&self.right) + 1;
+    }
+}
+
+impl<K, V> BinaryNode for Node<K, V> {
+    fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+}
+
+fn len<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.len)
+}
+
+// top-down splay: walks down from the root once, moving the nodes passed by into
+// a left tree of smaller keys and a right tree of greater keys, with a rotation
+// for every two steps in the same direction, then hangs the two trees below the
+// node it stopped at, which becomes the new root.
+// cmp tells where the wanted key is relative to a key, the walk stops at the
+// wanted key or at the last node before falling off the tree
+fn splay<K, V, F>(mut node: Box<Node<K, V>>, cmp: F) -> Box<Node<K, V>>
+where
+    F: Fn(&K) -> Ordering,
+{
+    // the nodes of the left and the right tree, each one to become the right or
+    // the left child of the one before it
+    let mut left = Vec::new();
+    let mut right = Vec::new();
+    loop {
+        match cmp(&node.key) {
+            Ordering::Less => {
+                let mut child = match node.left.take() {
+                    Some(child) => child,
+                    None => break,
+                };
+                if cmp(&child.key) == Ordering::Less {
+                    node.left = child.right.take();
+                    node.update();
+                    child.right = Some(node);
+                    node = child;
+                    child = match node.left.take() {
+                        Some(child) => child,
+                        None => break,
+                    };
+                }
+                right.push(node);
+                node = child;
+            }
+            Ordering::Greater => {
+                let mut child = match node.right.take() {
+                    Some(child) => child,
+                    None => break,
+                };
+                if cmp(&child.key) == Ordering::Greater {
+                    node.right = child.left.take();
+                    node.update();
+                    child.left = Some(node);
+                    node = child;
+                    child = match node.right.take() {
+                        Some(child) => child,
+                        None => break,
+                    };
+                }
+                left.push(node);
+                node = child;
+            }
+            Ordering::Equal => break,
+        }
+    }
+
+    let mut link = node.left.take();
+    while let Some(mut parent) = left.pop() {
+        parent.right = link;
+        parent.update();
+        link = Some(parent);
+    }
+    node.left = link;
+    let mut link = node.right.take();
+    while let Some(mut parent) = right.pop() {
+        parent.left = link;
+        parent.update();
+        link = Some(parent);
+    }
+    node.right = link;
+    node.update();
+    node
+}
+
+// cond: the keys of left are less than those of right
+fn join<K, V>(left: Link<K, V>, right: Link<K, V>) -> Link<K, V> {
+    let mut node = match left {
+        Some(left) => splay(left, |_| Ordering::Greater),
+        None => return right,
+    };
+    node.right = right;
+    node.update();
+    Some(node)
+}
+
+// Splay tree, every access moves the node found to the root, so recently used keys
+// stay near the top. A single operation can take O(n), but any sequence of m
+// operations takes O((m + n) log n). The tree can degenerate into a path, so
+// nothing walks it recursively, dropping included.
+pub struct SplayMap<K, V> {
+    root: Link<K, V>,
+}
+
+impl<K, V> SplayMap<K, V> {
+    pub fn new() -> Self {
+        Self { root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        len(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        // rotates every left child up, so that nodes are freed without recursion
+        let mut link = self.root.take();
+        while let Some(mut node) = link {
+            link = match node.left.take() {
+                Some(mut left) => {
+                    node.left = left.right.take();
+                    left.right = Some(node);
+                    Some(left)
+                }
+                None => node.right.take(),
+            };
+        }
+    }
+
+    // the number of nodes on the longest path from the root, 0 if the map is empty
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut stack: Vec<_> = self
+            .root
+            .as_deref()
+            .map(|node| (node, 1))
+            .into_iter()
+            .collect();
+        while let Some((node, depth)) = stack.pop() {
+            height = height.max(depth);
+            for child in node.left.iter().chain(node.right.iter()) {
+                stack.push((child, depth + 1));
+            }
+        }
+        height
+    }
+
+    // does not splay
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    // does not splay
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let mut node = splay(self.root.take()?, |_| Ordering::Less);
+        self.root = node.right.take();
+        Some((node.key, node.value))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let mut node = splay(self.root.take()?, |_| Ordering::Greater);
+        self.root = node.left.take();
+        Some((node.key, node.value))
+    }
+
+    // in key order, does not splay
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            iter: RawIter::new(self.root.as_deref()),
+            len: self.len(),
+        }
+    }
+}
+
+impl<K: Ord, V> SplayMap<K, V> {
+    // moves the node with the key, or a neighbour of the key, to the root
+    fn splay<Q>(&mut self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        if let Some(root) = self.root.take() {
+            self.root = Some(splay(root, |k| key.cmp(k.borrow())));
+        }
+    }
+
+    // splays, then returns the root if it has the key
+    fn find<Q>(&mut self, key: &Q) -> Option<&mut Node<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.splay(key);
+        match self.root.as_deref_mut() {
+            Some(root) if root.key.borrow() == key => Some(root),
+            _ => None,
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.splay(&key);
+        let mut root = match self.root.take() {
+            Some(root) => root,
+            None => {
+                self.root = Some(Node::new(key, value));
+                return None;
+            }
+        };
+        let mut node = Node::new(key, value);
+        match node.key.cmp(&root.key) {
+            Ordering::Less => {
+                node.left = root.left.take();
+                root.update();
+                node.right = Some(root);
+            }
+            Ordering::Greater => {
+                node.right = root.right.take();
+                root.update();
+                node.left = Some(root);
+            }
+            Ordering::Equal => {
+                let old = mem::replace(&mut root.value, node.value);
+                self.root = Some(root);
+                return Some(old);
+            }
+        }
+        node.update();
+        self.root = Some(node);
+        None
+    }
+
+    // splays the key or a neighbour of it to the root
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|node| &node.value)
+    }
+
+    pub fn get_key_value<Q>(&mut self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|node| (&node.key, &node.value))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|node| &mut node.value)
+    }
+
+    pub fn contains_key<Q>(&mut self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    // a plain search which leaves the tree as it is, for shared access
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut link = self.root.as_deref();
+        while let Some(node) = link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return Some(&node.value),
+            };
+        }
+        None
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key)?;
+        let mut root = self.root.take().unwrap();
+        self.root = join(root.left.take(), root.right.take());
+        Some((root.key, root.value))
+    }
+
+    // moves the entries whose keys are at least the key into a new map
+    pub fn split<Q>(&mut self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.splay(key);
+        let mut root = match self.root.take() {
+            Some(root) => root,
+            None => return Self::new(),
+        };
+        if root.key.borrow() < key {
+            let right = root.right.take();
+            root.update();
+            self.root = Some(root);
+            Self { root: right }
+        } else {
+            self.root = root.left.take();
+            root.update();
+            Self { root: Some(root) }
+        }
+    }
+
+    // moves all entries of other to the end, panics unless the keys of other
+    // are greater than those of self
+    pub fn join(&mut self, mut other: Self) {
+        let right = match other.root.take() {
+            Some(right) => splay(right, |_| Ordering::Less),
+            None => return,
+        };
+        let mut root = match self.root.take() {
+            Some(root) => splay(root, |_| Ordering::Greater),
+            None => {
+                self.root = Some(right);
+                return;
+            }
+        };
+        assert!(root.key < right.key, "keys are not ordered");
+        root.right = Some(right);
+        root.update();
+        self.root = Some(root);
+    }
+
+    // does not splay
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let iter = RawIter::range(
+            self.root.as_deref(),
+            |node| after_start(node.key.borrow(), range.start_bound()),
+            |node| before_end(node.key.borrow(), range.end_bound()),
+        );
+        Range { iter }
+    }
+
+    // panics if the keys are out of order or a size is stale
+    pub fn check_invariants(&self)
+    where
+        K: Debug,
+    {
+        let mut prev: Option<&K> = None;
+        for (key, _) in self.iter() {
+            if let Some(prev) = prev {
+                assert!(prev < key, "{:?} is out of order", key);
+            }
+            prev = Some(key);
+        }
+        let mut stack: Vec<_> = self.root.as_deref().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            let len = len(&node.left) + len(&node.right) + 1;
+            assert_eq!(node.len, len, "stale size at {:?}", node.key);
+            stack.extend(node.left.as_deref());
+            stack.extend(node.right.as_deref());
+        }
+    }
+}
+
+impl<K, V> Drop for SplayMap<K, V> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<K, V> Default for SplayMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SplayMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for SplayMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+// ------------------------------------
+// begin: IntoIter
+
+pub struct IntoIter<K, V>(SplayMap<K, V>);
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_first()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.len();
+        (len, Some(len))
+    }
+}
+
+impl<K, V> IntoIterator for SplayMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.pop_last()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+// end: IntoIter
+// ------------------------------------
+
+// ------------------------------------
+// begin: Iter
+
+pub struct Iter<'a, K, V> {
+    iter: RawIter<'a, Node<K, V>>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.iter.next_front()?;
+        self.len -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SplayMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.iter.next_back()?;
+        self.len -= 1;
+        Some((&node.key, &node.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+// end: Iter
+// ------------------------------------
+
+// ------------------------------------
+// begin: Range
+
+pub struct Range<'a, K, V> {
+    iter: RawIter<'a, Node<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next_front().map(|node| (&node.key, &node.value))
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Range<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.iter.next_back().map(|node| (&node.key, &node.value))
+    }
+}
+
+impl<K, V> FusedIterator for Range<'_, K, V> {}
+
+// end: Range
+// ------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::SplayMap;
+    use crate::rng::Rng;
+
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_splay_map() {
+        let mut map = SplayMap::new();
+        assert_eq!(map.get(&1), None);
+        assert_eq!(map.pop_first(), None);
+        for k in (1..=9).rev() {
+            assert_eq!(map.insert(k, k * 10), None);
+        }
+        map.check_invariants();
+        assert_eq!(map.insert(4, 0), Some(40));
+
+        // peek leaves the root alone, get brings the key up
+        assert_eq!(map.peek(&7), Some(&70));
+        assert_eq!(map.first(), Some((&1, &10)));
+        assert_eq!(map.get(&7), Some(&70));
+        assert_eq!(map.root.as_ref().map(|root| root.key), Some(7));
+        assert_eq!(map.get(&10), None);
+        assert_eq!(map.root.as_ref().map(|root| root.key), Some(9));
+        *map.get_mut(&5).unwrap() += 1;
+        assert!(map.contains_key(&5));
+        map.check_invariants();
+
+        assert!(map.range(3..=6).map(|(k, _)| *k).eq(3..=6));
+        assert!(map
+            .range(..4)
+            .rev()
+            .map(|(k, v)| (*k, *v))
+            .eq(vec![(3, 30), (2, 20), (1, 10)]));
+        assert_eq!(map.range(20..).next(), None);
+
+        assert_eq!(map.remove(&4), Some(0));
+        assert_eq!(map.remove(&4), None);
+        assert_eq!(map.pop_first(), Some((1, 10)));
+        assert_eq!(map.pop_last(), Some((9, 90)));
+        map.check_invariants();
+        assert_eq!(map.len(), 6);
+        assert!(map.into_iter().map(|(k, _)| k).eq(vec![2, 3, 5, 6, 7, 8]));
+    }
+
+    #[test]
+    fn test_split_join() {
+        let mut map: SplayMap<_, _> = (0..100).map(|k| (k * 2, k)).collect();
+        let mut tail = map.split(&51);
+        map.check_invariants();
+        tail.check_invariants();
+        assert_eq!(map.len(), 26);
+        assert_eq!(tail.len(), 74);
+        assert_eq!(map.last(), Some((&50, &25)));
+        assert_eq!(tail.first(), Some((&52, &26)));
+
+        let rest = tail.split(&52);
+        assert!(tail.is_empty());
+        map.join(tail);
+        map.join(rest);
+        map.check_invariants();
+        assert!(map.iter().map(|(k, _)| *k).eq((0..100).map(|k| k * 2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "keys are not ordered")]
+    fn test_join_overlapping() {
+        let mut map: SplayMap<_, _> = (0..10).map(|k| (k, k)).collect();
+        let mut other = map.split(&5);
+        other.join(map);
+    }
+
+    #[test]
+    fn test_path() {
+        // ascending insertions leave a path, which must not be walked recursively
+        let mut map: SplayMap<_, _> = (0..100_000).map(|k| (k, k)).collect();
+        assert_eq!(map.height(), 100_000);
+        assert_eq!(map.get(&0), Some(&0));
+        // splaying the deepest node of a path about halves its depth
+        assert!(map.height() <= 100_000 / 2 + 1);
+        assert!(map.iter().map(|(k, _)| *k).eq(0..100_000));
+    }
+
+    #[test]
+    fn test_random_operations() {
+        let mut rng = Rng::new(50);
+        let mut map = SplayMap::new();
+        let mut expected = BTreeMap::new();
+        let root = |map: &SplayMap<u32, u64>| map.root.as_ref().map(|root| root.key);
+
+        for i in 0..5000 {
+            let key = (rng.next_u64() % 1000) as u32;
+            match rng.next_u64() % 4 {
+                0 => assert_eq!(map.remove(&key), expected.remove(&key)),
+                1 => {
+                    assert_eq!(map.get(&key), expected.get(&key));
+                    // a hit is splayed to the root
+                    if expected.contains_key(&key) {
+                        assert_eq!(root(&map), Some(key));
+                    }
+                }
+                2 => {
+                    let tail = map.split(&key);
+                    assert_eq!(map.len(), expected.range(..key).count());
+                    map.join(tail);
+                }
+                _ => {
+                    assert_eq!(map.insert(key, i), expected.insert(key, i));
+                    assert_eq!(root(&map), Some(key));
+                }
+            }
+            if i % 100 == 0 {
+                map.check_invariants();
+            }
+        }
+
+        map.check_invariants();
+        assert_eq!(map.len(), expected.len());
+        // the read-only walks leave the tree as it is
+        let before = root(&map);
+        assert!(map.iter().eq(expected.iter()));
+        assert!(map.iter().rev().eq(expected.iter().rev()));
+        assert!(map.range(100..=500).eq(expected.range(100..=500)));
+        for (k, v) in &expected {
+            assert_eq!(map.peek(k), Some(v));
+        }
+        assert_eq!(root(&map), before);
+    }
+}
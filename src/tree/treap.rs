@@ -0,0 +1,463 @@
+use crate::rng::Rng;
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::iter::FromIterator;
+use std::iter::FusedIterator;
+use std::mem;
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    priority: u64,
+    // the number of nodes in the subtree
+    len: usize,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K, V> Node<K, V> {
+    fn update(&mut self) {
+        self.len = len(&self.left) + len(&self.right) + 1;
+    }
+}
+
+fn len<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.len)
+}
+
+// splits into the nodes for which left returns true and the rest,
+// left must hold for a prefix of the keys
+fn split<K, V, F>(link: Link<K, V>, left: &F) -> (Link<K, V>, Link<K, V>)
+where
+    F: Fn(&K) -> bool,
+{
+    let mut node = match link {
+        Some(node) => node,
+        None => return (None, None),
+    };
+    if left(&node.key) {
+        let (mid, right) = split(node.right.take(), left);
+        node.right = mid;
+        node.update();
+        (Some(node), right)
+    } else {
+        let (left, mid) = split(node.left.take(), left);
+        node.left = mid;
+        node.update();
+        (left, Some(node))
+    }
+}
+
+// cond: the keys of left are less than those of right
+fn merge<K, V>(left: Link<K, V>, right: Link<K, V>) -> Link<K, V> {
+    match (left, right) {
+        (Some(mut left), Some(mut right)) => {
+            if left.priority > right.priority {
+                left.right = merge(left.right.take(), Some(right));
+                left.update();
+                Some(left)
+            } else {
+                right.left = merge(Some(left), right.left.take());
+                right.update();
+                Some(right)
+            }
+        }
+        (left, right) => left.or(right),
+    }
+}
+
+fn push_left<'a, K, V>(stack: &mut Vec<&'a Node<K, V>>, mut link: Option<&'a Node<K, V>>) {
+    while let Some(node) = link {
+        stack.push(node);
+        link = node.left.as_deref();
+    }
+}
+
+// Treap, a binary search tree on the keys which is a heap on random priorities,
+// so it has the shape of a tree built from random insertions, with an expected
+// height of O(log n). Insertions and removals split the tree at the key and merge
+// the parts back. Every node keeps the size of its subtree for select and rank.
+pub struct Treap<K, V> {
+    root: Link<K, V>,
+    rng: Rng,
+}
+
+impl<K, V> Treap<K, V> {
+    pub fn new() -> Self {
+        Self::with_rng(Rng::from_entropy())
+    }
+
+    // for reproducible shapes
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(Rng::new(seed))
+    }
+
+    fn with_rng(rng: Rng) -> Self {
+        Self { root: None, rng }
+    }
+
+    pub fn len(&self) -> usize {
+        len(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    // the number of nodes on the longest path from the root, 0 if the treap is empty
+    pub fn height(&self) -> usize {
+        fn height<K, V>(link: &Link<K, V>) -> usize {
+            link.as_ref()
+                .map_or(0, |node| height(&node.left).max(height(&node.right)) + 1)
+        }
+        height(&self.root)
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    // the entry with index-th smallest key, from 0
+    pub fn select(&self, mut index: usize) -> Option<(&K, &V)> {
+        let mut link = self.root.as_deref();
+        while let Some(node) = link {
+            let left = len(&node.left);
+            link = match index.cmp(&left) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => {
+                    index -= left + 1;
+                    node.right.as_deref()
+                }
+                Ordering::Equal => return Some((&node.key, &node.value)),
+            };
+        }
+        None
+    }
+
+    // in key order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut stack = Vec::new();
+        push_left(&mut stack, self.root.as_deref());
+        Iter {
+            stack,
+            len: self.len(),
+        }
+    }
+}
+
+impl<K: Ord, V> Treap<K, V> {
+    fn find<Q>(&self, key: &Q) -> Option<&Node<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut link = self.root.as_deref();
+        while let Some(node) = link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => node.right.as_deref(),
+                Ordering::Equal => return Some(node),
+            };
+        }
+        None
+    }
+
+    fn find_mut<Q>(&mut self, key: &Q) -> Option<&mut Node<K, V>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut link = self.root.as_deref_mut();
+        while let Some(node) = link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Less => node.left.as_deref_mut(),
+                Ordering::Greater => node.right.as_deref_mut(),
+                Ordering::Equal => return Some(node),
+            };
+        }
+        None
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(node) = self.find_mut(&key) {
+            return Some(mem::replace(&mut node.value, value));
+        }
+        let (left, right) = split(self.root.take(), &|k: &K| *k < key);
+        let node = Box::new(Node {
+            key,
+            value,
+            priority: self.rng.next_u64(),
+            len: 1,
+            left: None,
+            right: None,
+        });
+        self.root = merge(merge(left, Some(node)), right);
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).map(|node| &node.value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find_mut(key).map(|node| &mut node.value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (left, right) = split(self.root.take(), &|k: &K| k.borrow() < key);
+        let (mid, right) = split(right, &|k: &K| k.borrow() <= key);
+        self.root = merge(left, right);
+        mid.map(|node| (node.key, node.value))
+    }
+
+    // the number of keys less than the key
+    pub fn rank<Q>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut rank = 0;
+        let mut link = self.root.as_deref();
+        while let Some(node) = link {
+            link = match key.cmp(node.key.borrow()) {
+                Ordering::Less => node.left.as_deref(),
+                Ordering::Greater => {
+                    rank += len(&node.left) + 1;
+                    node.right.as_deref()
+                }
+                Ordering::Equal => return rank + len(&node.left),
+            };
+        }
+        rank
+    }
+
+    // moves the entries whose keys are at least the key into a new treap
+    pub fn split_off<Q>(&mut self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (left, right) = split(self.root.take(), &|k: &K| k.borrow() < key);
+        self.root = left;
+        Self {
+            root: right,
+            rng: Rng::new(self.rng.next_u64()),
+        }
+    }
+
+    // panics if the keys are out of order, a priority is above its parent's
+    // or a size is stale
+    pub fn check_invariants(&self)
+    where
+        K: Debug,
+    {
+        fn check<K: Ord + Debug, V>(
+            link: &Link<K, V>,
+            lower: Option<&K>,
+            upper: Option<&K>,
+            priority: u64,
+        ) -> usize {
+            let node = match link {
+                Some(node) => node,
+                None => return 0,
+            };
+            if let Some(lower) = lower {
+                assert!(lower < &node.key, "{:?} is out of order", node.key);
+            }
+            if let Some(upper) = upper {
+                assert!(&node.key < upper, "{:?} is out of order", node.key);
+            }
+            assert!(
+                node.priority <= priority,
+                "{:?} is out of heap order",
+                node.key
+            );
+            let left = check(&node.left, lower, Some(&node.key), node.priority);
+            let right = check(&node.right, Some(&node.key), upper, node.priority);
+            assert_eq!(node.len, left + right + 1, "stale size at {:?}", node.key);
+            node.len
+        }
+
+        check(&self.root, None, None, u64::MAX);
+    }
+}
+
+impl<K, V> Default for Treap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for Treap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut treap = Self::new();
+        treap.extend(iter);
+        treap
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for Treap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+// ------------------------------------
+// begin: Iter
+
+// the stack holds the nodes still to be visited whose right subtrees are not entered yet
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        push_left(&mut self.stack, node.right.as_deref());
+        self.len -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a Treap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+// end: Iter
+// ------------------------------------
+
+#[cfg(test)]
+mod test {
+    use super::Treap;
+    use crate::rng::Rng;
+
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_treap() {
+        let mut treap = Treap::with_seed(49);
+        assert_eq!(treap.first(), None);
+        for k in 1..=9 {
+            assert_eq!(treap.insert(k, k * 10), None);
+        }
+        treap.check_invariants();
+        assert_eq!(treap.insert(4, 0), Some(40));
+        assert_eq!(treap.get(&4), Some(&0));
+        assert_eq!(treap.get(&10), None);
+        *treap.get_mut(&5).unwrap() += 1;
+        assert_eq!(treap.first(), Some((&1, &10)));
+        assert_eq!(treap.last(), Some((&9, &90)));
+        assert_eq!(treap.select(4), Some((&5, &51)));
+        assert_eq!(treap.rank(&5), 4);
+        assert_eq!(treap.rank(&100), 9);
+
+        let mut tail = treap.split_off(&7);
+        treap.check_invariants();
+        tail.check_invariants();
+        assert!(treap.iter().map(|(k, _)| *k).eq(1..=6));
+        assert!(tail.iter().map(|(k, _)| *k).eq(7..=9));
+
+        assert_eq!(treap.remove(&5), Some(51));
+        assert_eq!(treap.remove(&5), None);
+        assert_eq!(tail.remove_entry(&9), Some((9, 90)));
+        treap.check_invariants();
+        assert_eq!(treap.len(), 5);
+        assert_eq!(tail.len(), 2);
+        tail.clear();
+        assert!(tail.is_empty());
+    }
+
+    #[test]
+    fn test_against_btree_map() {
+        let mut rng = Rng::new(49);
+        let mut treap = Treap::with_seed(49);
+        let mut expected = BTreeMap::new();
+
+        for i in 0..5000 {
+            let key = (rng.next_u64() % 1000) as u32;
+            if rng.next_u64() % 3 == 1 {
+                assert_eq!(treap.remove(&key), expected.remove(&key));
+            } else {
+                assert_eq!(treap.insert(key, i), expected.insert(key, i));
+            }
+            if i % 100 == 0 {
+                treap.check_invariants();
+            }
+        }
+
+        treap.check_invariants();
+        assert_eq!(treap.len(), expected.len());
+        assert!(treap.iter().eq(expected.iter()));
+        for (i, (k, _)) in expected.iter().enumerate() {
+            assert_eq!(treap.select(i).map(|(k, _)| k), Some(k));
+            assert_eq!(treap.rank(k), i);
+        }
+        // the expected height is about 3 log2(n)
+        assert!(treap.height() <= 4 * (usize::BITS - treap.len().leading_zeros()) as usize);
+    }
+}
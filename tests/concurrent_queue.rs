@@ -0,0 +1,133 @@
+// Stress harness for the lock-free queues: producers push tagged sequences,
+// consumers pop until every element has been seen exactly once.
+
+use data_structure::deque::{BoundedConcurrentQueue, ConcurrentQueue};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+const PRODUCERS: usize = 4;
+const CONSUMERS: usize = 4;
+const PER_PRODUCER: usize = 20_000;
+
+trait Queue: Send + Sync + 'static {
+    fn push(&self, elem: (usize, usize));
+    fn pop(&self) -> Option<(usize, usize)>;
+    fn check(&self) {}
+}
+
+impl Queue for ConcurrentQueue<(usize, usize)> {
+    fn push(&self, elem: (usize, usize)) {
+        ConcurrentQueue::push(self, elem)
+    }
+
+    fn pop(&self) -> Option<(usize, usize)> {
+        ConcurrentQueue::pop(self)
+    }
+}
+
+impl Queue for BoundedConcurrentQueue<(usize, usize)> {
+    fn push(&self, mut elem: (usize, usize)) {
+        while let Err(back) = BoundedConcurrentQueue::push(self, elem) {
+            elem = back;
+            thread::yield_now();
+        }
+    }
+
+    fn pop(&self) -> Option<(usize, usize)> {
+        BoundedConcurrentQueue::pop(self)
+    }
+
+    fn check(&self) {
+        assert!(self.len() <= self.capacity());
+    }
+}
+
+fn run<Q: Queue>(queue: Q) {
+    let queue = Arc::new(queue);
+    let barrier = Arc::new(Barrier::new(PRODUCERS + CONSUMERS));
+    let consumed = Arc::new(AtomicUsize::new(0));
+
+    let producers: Vec<_> = (0..PRODUCERS)
+        .map(|p| {
+            let (queue, barrier) = (queue.clone(), barrier.clone());
+            thread::spawn(move || {
+                barrier.wait();
+                for seq in 0..PER_PRODUCER {
+                    queue.push((p, seq));
+                }
+            })
+        })
+        .collect();
+
+    let consumers: Vec<_> = (0..CONSUMERS)
+        .map(|_| {
+            let (queue, barrier, consumed) = (queue.clone(), barrier.clone(), consumed.clone());
+            thread::spawn(move || {
+                barrier.wait();
+                let mut seen = Vec::new();
+                // elements of one producer must come out in the order they went in
+                let mut last = [None; PRODUCERS];
+                while consumed.load(Ordering::SeqCst) < PRODUCERS * PER_PRODUCER {
+                    match queue.pop() {
+                        Some((p, seq)) => {
+                            assert!(last[p] < Some(seq), "producer {} out of order", p);
+                            last[p] = Some(seq);
+                            seen.push((p, seq));
+                            consumed.fetch_add(1, Ordering::SeqCst);
+                        }
+                        None => thread::yield_now(),
+                    }
+                    queue.check();
+                }
+                seen
+            })
+        })
+        .collect();
+
+    for handle in producers {
+        handle.join().unwrap();
+    }
+    let mut seen: Vec<_> = consumers
+        .into_iter()
+        .flat_map(|h| h.join().unwrap())
+        .collect();
+    assert!(queue.pop().is_none());
+
+    seen.sort_unstable();
+    let expected = (0..PRODUCERS).flat_map(|p| (0..PER_PRODUCER).map(move |seq| (p, seq)));
+    assert!(seen.into_iter().eq(expected));
+}
+
+#[test]
+fn stress_concurrent_queue() {
+    run(ConcurrentQueue::new());
+}
+
+#[test]
+fn stress_bounded_concurrent_queue() {
+    run(BoundedConcurrentQueue::new(64));
+}
+
+#[test]
+fn stress_drop_with_elements() {
+    let queue = Arc::new(ConcurrentQueue::new());
+    let handles: Vec<_> = (0..PRODUCERS)
+        .map(|p| {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                for i in 0..1000 {
+                    queue.push(format!("{}-{}", p, i));
+                    if i % 3 == 0 {
+                        queue.pop();
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(queue.len(), PRODUCERS * 1000 - PRODUCERS * 334);
+}